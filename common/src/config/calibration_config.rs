@@ -0,0 +1,90 @@
+/*
+ * EpiRust
+ * Copyright (c) 2020  ThoughtWorks, Inc.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ */
+
+use std::error::Error;
+use std::fs::File;
+
+use crate::disease::DiseaseParameter;
+use crate::models::custom_types::Percentage;
+
+/// Describes how disease parameters are fitted against an observed time series
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone)]
+pub struct CalibrationConfig {
+    pub observed_data: String,
+    pub parameters: Vec<CalibrationParameter>,
+    pub method: CalibrationMethod,
+    #[serde(default = "default_runs_per_evaluation")]
+    pub runs_per_evaluation: u32,
+    #[serde(default)]
+    pub output_file: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Copy, Clone)]
+pub struct CalibrationParameter {
+    pub name: DiseaseParameter,
+    pub min: Percentage,
+    pub max: Percentage,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Copy, Clone)]
+pub enum CalibrationMethod {
+    NelderMead { max_iterations: u32, tolerance: f64 },
+    AbcSmc { particles: u32, generations: u32, acceptance_quantile: f64 },
+}
+
+fn default_runs_per_evaluation() -> u32 {
+    1
+}
+
+impl CalibrationConfig {
+    pub fn read(filename: &str) -> Result<CalibrationConfig, Box<dyn Error>> {
+        let reader = File::open(filename)?;
+        let config: CalibrationConfig = serde_json::from_reader(reader)?;
+        if config.parameters.is_empty() {
+            return Err("Calibration needs at least one parameter to fit".into());
+        }
+        if let Some(p) = config.parameters.iter().find(|p| p.min > p.max || p.min < 0.0 || p.max > 1.0) {
+            return Err(format!("Invalid bounds for calibration parameter {:?}", p.name).into());
+        }
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_read_calibration_config() {
+        let config = CalibrationConfig::read("config/test/calibration.json").unwrap();
+
+        let expected = CalibrationConfig {
+            observed_data: "config/test/observed_cases.csv".to_string(),
+            parameters: vec![
+                CalibrationParameter { name: DiseaseParameter::RegularTransmissionRate, min: 0.01, max: 0.5 },
+                CalibrationParameter { name: DiseaseParameter::DeathRate, min: 0.0, max: 0.1 },
+            ],
+            method: CalibrationMethod::NelderMead { max_iterations: 50, tolerance: 0.001 },
+            runs_per_evaluation: 1,
+            output_file: None,
+        };
+
+        assert_eq!(expected, config);
+    }
+}
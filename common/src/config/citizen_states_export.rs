@@ -0,0 +1,75 @@
+/*
+ * EpiRust
+ * Copyright (c) 2020  ThoughtWorks, Inc.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ */
+
+use crate::models::custom_types::{Hour, Percentage};
+
+/// Writes agent level trajectories to a gzip compressed JSON lines file, so they can be replayed without Kafka
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, Copy)]
+pub struct CitizenStatesExport {
+    #[serde(default)]
+    pub mode: CitizenStatesExportMode,
+    /// fraction of the population whose trajectories are written
+    #[serde(default = "default_sampling_rate")]
+    pub sampling_rate: Percentage,
+    /// hours between two snapshots, only used in snapshot mode
+    #[serde(default = "default_snapshot_interval")]
+    pub snapshot_interval: Hour,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CitizenStatesExportMode {
+    /// state and location of every sampled citizen
+    Snapshot,
+    /// only the sampled citizens whose state changed since they were last written
+    #[default]
+    Delta,
+}
+
+fn default_sampling_rate() -> Percentage {
+    1.0
+}
+
+fn default_snapshot_interval() -> Hour {
+    1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_default_to_deltas_of_every_citizen() {
+        let export: CitizenStatesExport = serde_json::from_str("{}").unwrap();
+
+        assert_eq!(export.mode, CitizenStatesExportMode::Delta);
+        assert_eq!(export.sampling_rate, 1.0);
+        assert_eq!(export.snapshot_interval, 1);
+    }
+
+    #[test]
+    fn should_read_snapshot_mode() {
+        let export: CitizenStatesExport =
+            serde_json::from_str(r#"{"mode": "snapshot", "sampling_rate": 0.1, "snapshot_interval": 24}"#).unwrap();
+
+        assert_eq!(export.mode, CitizenStatesExportMode::Snapshot);
+        assert_eq!(export.sampling_rate, 0.1);
+        assert_eq!(export.snapshot_interval, 24);
+    }
+}
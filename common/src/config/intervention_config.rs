@@ -26,6 +26,8 @@ pub enum InterventionConfig {
     Vaccinate(VaccinateConfig),
     Lockdown(LockdownConfig),
     BuildNewHospital(BuildNewHospitalConfig),
+    TravelRestriction(TravelRestrictionConfig),
+    EntryScreening(EntryScreeningConfig),
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize, Copy, Clone, Validate)]
@@ -46,3 +48,78 @@ pub struct LockdownConfig {
 pub struct BuildNewHospitalConfig {
     pub spread_rate_threshold: u32,
 }
+
+/// Throttles travel in and out of a region while it, or the region at the other end, has too many infections
+#[derive(Debug, PartialEq, Serialize, Deserialize, Copy, Clone, Validate)]
+pub struct TravelRestrictionConfig {
+    /// Restrict all travel to and from this region while it has more infections than this
+    pub at_number_of_infections: Option<Count>,
+    /// Restrict travel to and from any region that has more infections than this
+    pub at_number_of_remote_infections: Option<Count>,
+    /// Share of the planned travellers still allowed through while restricted, 0 closes the region
+    #[validate(custom = "validate_percentage")]
+    pub travel_allowed: Percentage,
+}
+
+impl TravelRestrictionConfig {
+    /// Whether travel between this region and `other` is restricted, given the infections in each
+    pub fn applies(&self, infected: Count, other_infected: Count) -> bool {
+        matches!(self.at_number_of_infections, Some(threshold) if infected > threshold)
+            || matches!(self.at_number_of_remote_infections, Some(threshold) if other_infected > threshold)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Copy, Clone)]
+pub enum ScreeningAction {
+    /// Detected travellers are isolated on arrival, commuters for the working day and migrators for the quarantine period
+    Quarantine,
+    /// Detected travellers are turned back and stay in their home region
+    Reject,
+}
+
+/// Screens travellers arriving in a region. Citizens with symptoms don't travel, so screening detects the infected
+/// travellers who don't show them yet.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Copy, Clone, Validate)]
+pub struct EntryScreeningConfig {
+    #[validate(custom = "validate_percentage")]
+    pub detection_rate: Percentage,
+    pub action: ScreeningAction,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_restrict_travel_above_local_or_remote_threshold() {
+        let restriction = TravelRestrictionConfig {
+            at_number_of_infections: Some(100),
+            at_number_of_remote_infections: Some(50),
+            travel_allowed: 0.0,
+        };
+
+        assert!(!restriction.applies(100, 50));
+        assert!(restriction.applies(101, 0));
+        assert!(restriction.applies(0, 51));
+    }
+
+    #[test]
+    fn should_not_restrict_travel_without_thresholds() {
+        let restriction =
+            TravelRestrictionConfig { at_number_of_infections: None, at_number_of_remote_infections: None, travel_allowed: 0.5 };
+
+        assert!(!restriction.applies(1000, 1000));
+    }
+
+    #[test]
+    fn should_parse_entry_screening() {
+        let json = r#"{"EntryScreening": {"detection_rate": 0.6, "action": "Reject"}}"#;
+
+        let intervention: InterventionConfig = serde_json::from_str(json).unwrap();
+
+        assert_eq!(
+            intervention,
+            InterventionConfig::EntryScreening(EntryScreeningConfig { detection_rate: 0.6, action: ScreeningAction::Reject })
+        );
+    }
+}
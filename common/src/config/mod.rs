@@ -17,23 +17,32 @@
  *
  */
 
+mod calibration_config;
+mod citizen_states_export;
 mod geography_parameters;
+mod output_config;
 mod population;
 mod starting_infections;
 mod travel_plan_config;
+mod visualisation_config;
 
 pub mod intervention_config;
 pub mod request;
+pub mod travel_matrix;
 
-pub use travel_plan_config::TravelPlanConfig;
+pub use travel_plan_config::{StayLength, TransitHub, TravelPlanConfig};
 
 use std::error::Error;
 use std::fs::File;
 use validator::Validate;
 
+pub use crate::config::calibration_config::*;
+pub use crate::config::citizen_states_export::*;
 pub use crate::config::geography_parameters::GeographyParameters;
+pub use crate::config::output_config::*;
 pub use crate::config::population::*;
 pub use crate::config::starting_infections::StartingInfections;
+pub use crate::config::visualisation_config::*;
 
 use crate::disease::{Disease, DiseaseOverride};
 use crate::models::custom_types::{Hour, Size};
@@ -53,6 +62,27 @@ pub struct Config {
     enable_citizen_state_messages: bool,
     #[serde(default)]
     starting_infections: StartingInfections,
+    #[serde(default)]
+    transmission_tree: Option<TransmissionTreeFormat>,
+    #[serde(default)]
+    enable_hotspot_snapshots: bool,
+    #[serde(default)]
+    output: Option<OutputConfig>,
+    #[serde(default)]
+    citizen_states_export: Option<CitizenStatesExport>,
+    #[serde(default)]
+    visualisation: Option<VisualisationConfig>,
+    #[serde(default)]
+    draw_grid: bool,
+    #[serde(default)]
+    epidemic_curves: Option<ChartFormat>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum TransmissionTreeFormat {
+    Csv,
+    Columnar,
 }
 
 impl Config {
@@ -75,6 +105,13 @@ impl Config {
             output_file,
             enable_citizen_state_messages: true,
             starting_infections: StartingInfections::default(),
+            transmission_tree: None,
+            enable_hotspot_snapshots: false,
+            output: None,
+            citizen_states_export: None,
+            visualisation: None,
+            draw_grid: false,
+            epidemic_curves: None,
         }
     }
 
@@ -82,6 +119,10 @@ impl Config {
         self.disease.unwrap()
     }
 
+    pub fn set_disease(&mut self, disease: Disease) {
+        self.disease = Some(disease);
+    }
+
     pub fn get_starting_infections(&self) -> &StartingInfections {
         &self.starting_infections
     }
@@ -106,10 +147,42 @@ impl Config {
         self.output_file.clone()
     }
 
+    pub fn set_output_file(&mut self, output_file: String) {
+        self.output_file = Some(output_file);
+    }
+
     pub fn enable_citizen_state_messages(&self) -> bool {
         self.enable_citizen_state_messages
     }
 
+    pub fn get_output_config(&self) -> Option<&OutputConfig> {
+        self.output.as_ref()
+    }
+
+    pub fn get_citizen_states_export(&self) -> Option<CitizenStatesExport> {
+        self.citizen_states_export
+    }
+
+    pub fn get_visualisation(&self) -> Option<VisualisationConfig> {
+        self.visualisation
+    }
+
+    pub fn get_epidemic_curves_format(&self) -> Option<ChartFormat> {
+        self.epidemic_curves
+    }
+
+    pub fn draw_grid(&self) -> bool {
+        self.draw_grid
+    }
+
+    pub fn enable_hotspot_snapshots(&self) -> bool {
+        self.enable_hotspot_snapshots
+    }
+
+    pub fn get_transmission_tree_format(&self) -> Option<TransmissionTreeFormat> {
+        self.transmission_tree
+    }
+
     pub fn get_geography_parameters(&self) -> &GeographyParameters {
         &self.geography_parameters
     }
@@ -154,6 +227,13 @@ mod tests {
             output_file: None,
             enable_citizen_state_messages: false,
             starting_infections: StartingInfections::default(),
+            transmission_tree: None,
+            enable_hotspot_snapshots: false,
+            output: None,
+            citizen_states_export: None,
+            visualisation: None,
+            draw_grid: false,
+            epidemic_curves: None,
         };
 
         assert_eq!(expected_config, read_config);
@@ -181,6 +261,13 @@ mod tests {
             output_file: Some("simulation_default_config".to_string()),
             enable_citizen_state_messages: false,
             starting_infections: StartingInfections::new(2, 3, 4, 5),
+            transmission_tree: Some(TransmissionTreeFormat::Csv),
+            enable_hotspot_snapshots: false,
+            output: None,
+            citizen_states_export: None,
+            visualisation: None,
+            draw_grid: false,
+            epidemic_curves: None,
         };
 
         assert_eq!(expected_config, read_config);
@@ -0,0 +1,84 @@
+/*
+ * EpiRust
+ * Copyright (c) 2020  ThoughtWorks, Inc.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ */
+
+/// Selects where the simulation output goes and which of it is written. Every sink receives every stream.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone)]
+pub struct OutputConfig {
+    #[serde(default = "default_sinks")]
+    pub sinks: Vec<OutputSink>,
+    #[serde(default = "default_streams")]
+    pub streams: Vec<OutputStream>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputSink {
+    Csv,
+    JsonLines,
+    Parquet,
+    Sqlite,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone, Copy, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputStream {
+    Counts,
+    Interventions,
+    Travel,
+    CitizenStates,
+}
+
+impl OutputStream {
+    pub fn name(&self) -> &'static str {
+        match self {
+            OutputStream::Counts => "counts",
+            OutputStream::Interventions => "interventions",
+            OutputStream::Travel => "travel",
+            OutputStream::CitizenStates => "citizen_states",
+        }
+    }
+}
+
+impl OutputConfig {
+    pub fn includes(&self, stream: OutputStream) -> bool {
+        self.streams.contains(&stream)
+    }
+}
+
+fn default_sinks() -> Vec<OutputSink> {
+    vec![OutputSink::Csv]
+}
+
+fn default_streams() -> Vec<OutputStream> {
+    vec![OutputStream::Counts, OutputStream::Interventions, OutputStream::Travel]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_default_to_csv_sink_without_citizen_states() {
+        let config: OutputConfig = serde_json::from_str(r#"{"sinks": ["parquet", "sqlite"]}"#).unwrap();
+
+        assert_eq!(config.sinks, vec![OutputSink::Parquet, OutputSink::Sqlite]);
+        assert!(config.includes(OutputStream::Counts));
+        assert!(!config.includes(OutputStream::CitizenStates));
+    }
+}
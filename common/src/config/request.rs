@@ -41,6 +41,6 @@ pub struct MultiSimRequest {
 #[derive(Debug, Deserialize)]
 #[serde(untagged)]
 pub enum Request {
-    SimulationRequest(SimulationRequest),
-    MultiSimRequest(MultiSimRequest),
+    SimulationRequest(Box<SimulationRequest>),
+    MultiSimRequest(Box<MultiSimRequest>),
 }
@@ -0,0 +1,216 @@
+/*
+ * EpiRust
+ * Copyright (c) 2020  ThoughtWorks, Inc.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ */
+
+//! Commute and migration matrices generated from the populations and locations of the regions, instead of being
+//! written by hand
+
+use crate::models::custom_types::{Count, Percentage};
+
+/// Where a region is, and how many people live there. The population defaults to the agents of its engine, and can be
+/// set to the real population when the engines simulate a sample of it.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct RegionLocation {
+    pub engine_id: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    #[serde(default)]
+    pub population: Option<Count>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+pub enum TravelModel {
+    /// Destinations are picked in proportion to population^destination_exponent / distance^distance_exponent
+    Gravity { destination_exponent: f64, distance_exponent: f64 },
+    /// Travellers go to the closest region with better opportunities than they have at home, as in Simini et al.
+    /// (2012). The only parameter is the share of the population travelling.
+    Radiation,
+}
+
+/// How to generate one of the matrices
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+pub struct MatrixGeneration {
+    pub model: TravelModel,
+    /// Share of the agents of each region travelling every day
+    pub share: Percentage,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct TravelMatrixConfig {
+    pub regions: Vec<RegionLocation>,
+    #[serde(default)]
+    pub commute: Option<MatrixGeneration>,
+    #[serde(default)]
+    pub migration: Option<MatrixGeneration>,
+}
+
+const EARTH_RADIUS_KM: f64 = 6371.0;
+/// Regions at the same spot are treated as this far apart, so the gravity model doesn't divide by zero
+const MIN_DISTANCE_KM: f64 = 0.1;
+
+fn distance_km(from: &RegionLocation, to: &RegionLocation) -> f64 {
+    let (lat1, lat2) = (from.latitude.to_radians(), to.latitude.to_radians());
+    let d_lat = lat2 - lat1;
+    let d_lon = (to.longitude - from.longitude).to_radians();
+    let a = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+    (2.0 * EARTH_RADIUS_KM * a.sqrt().asin()).max(MIN_DISTANCE_KM)
+}
+
+impl TravelModel {
+    /// How attractive each destination is to the travellers leaving each region, not normalised
+    fn weights(&self, regions: &[RegionLocation], populations: &[f64]) -> Vec<Vec<f64>> {
+        let n = regions.len();
+        let distances: Vec<Vec<f64>> =
+            regions.iter().map(|from| regions.iter().map(|to| distance_km(from, to)).collect()).collect();
+        (0..n)
+            .map(|i| {
+                (0..n)
+                    .map(|j| {
+                        if i == j {
+                            return 0.0;
+                        }
+                        match *self {
+                            TravelModel::Gravity { destination_exponent, distance_exponent } => {
+                                populations[j].powf(destination_exponent) / distances[i][j].powf(distance_exponent)
+                            }
+                            TravelModel::Radiation => {
+                                let (origin, destination) = (populations[i], populations[j]);
+                                let between: f64 = (0..n)
+                                    .filter(|k| *k != i && *k != j && distances[i][*k] < distances[i][j])
+                                    .map(|k| populations[k])
+                                    .sum();
+                                origin * destination / ((origin + between) * (origin + destination + between))
+                            }
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// Splits `total` into whole numbers in proportion to `weights`, handing the remainder to the largest fractions
+fn apportion(total: u32, weights: &[f64]) -> Vec<u32> {
+    let sum: f64 = weights.iter().sum();
+    if sum <= 0.0 {
+        return vec![0; weights.len()];
+    }
+    let exact: Vec<f64> = weights.iter().map(|weight| total as f64 * weight / sum).collect();
+    let mut shares: Vec<u32> = exact.iter().map(|share| share.floor() as u32).collect();
+    let mut by_remainder: Vec<usize> = (0..weights.len()).collect();
+    by_remainder.sort_by(|a, b| (exact[*b] - exact[*b].floor()).total_cmp(&(exact[*a] - exact[*a].floor())));
+    let remainder = total - shares.iter().sum::<u32>();
+    by_remainder.iter().take(remainder as usize).for_each(|i| shares[*i] += 1);
+    shares
+}
+
+/// Generates the matrix of travellers between `regions`, with `outgoing[i]` travellers leaving region `i` every day.
+/// `populations` weigh the destinations, `outgoing` is usually a share of the agents of each engine.
+pub fn generate_matrix(model: TravelModel, regions: &[RegionLocation], populations: &[f64], outgoing: &[u32]) -> Vec<Vec<u32>> {
+    model.weights(regions, populations).iter().zip(outgoing).map(|(weights, total)| apportion(*total, weights)).collect()
+}
+
+/// Checks that no region sends more travellers a day than its `limits`
+pub fn validate_matrix(kind: &str, regions: &[String], matrix: &[Vec<u32>], limits: &[u32]) -> Result<(), String> {
+    let over: Vec<String> = regions
+        .iter()
+        .zip(matrix)
+        .zip(limits)
+        .filter(|((_, row), limit)| row.iter().sum::<u32>() > **limit)
+        .map(|((region, row), limit)| format!("{} sends {} {} but has only {}", region, row.iter().sum::<u32>(), kind, limit))
+        .collect();
+    if over.is_empty() {
+        Ok(())
+    } else {
+        Err(over.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn region(engine_id: &str, longitude: f64) -> RegionLocation {
+        RegionLocation { engine_id: engine_id.to_string(), latitude: 0.0, longitude, population: None }
+    }
+
+    fn regions() -> Vec<RegionLocation> {
+        vec![region("engine1", 0.0), region("engine2", 0.1), region("engine3", 1.0)]
+    }
+
+    #[test]
+    fn should_send_gravity_travellers_to_closer_and_larger_regions() {
+        let model = TravelModel::Gravity { destination_exponent: 1.0, distance_exponent: 2.0 };
+
+        let matrix = generate_matrix(model, &regions(), &[1000.0, 1000.0, 1000.0], &[100, 100, 100]);
+
+        assert_eq!(matrix.iter().map(|row| row.iter().sum::<u32>()).collect::<Vec<_>>(), vec![100, 100, 100]);
+        assert_eq!(matrix[0][0], 0);
+        assert!(matrix[0][1] > matrix[0][2]);
+
+        let matrix = generate_matrix(
+            model,
+            &[region("engine1", 0.0), region("engine2", 0.1), region("engine3", -0.1)],
+            &[1000.0, 1000.0, 4000.0],
+            &[100, 0, 0],
+        );
+        assert_eq!(matrix[0], vec![0, 20, 80]);
+    }
+
+    #[test]
+    fn should_send_radiation_travellers_less_far_when_regions_are_in_between() {
+        let matrix = generate_matrix(TravelModel::Radiation, &regions(), &[1000.0, 1000.0, 1000.0], &[90, 90, 90]);
+
+        assert_eq!(matrix[0].iter().sum::<u32>(), 90);
+        // half of engine1's travellers would go to engine3 without engine2 in between
+        assert!(matrix[0][2] < 45);
+        assert!(matrix[1][0] > matrix[1][2]);
+    }
+
+    #[test]
+    fn should_apportion_whole_travellers() {
+        assert_eq!(apportion(10, &[1.0, 1.0, 1.0]), vec![4, 3, 3]);
+        assert_eq!(apportion(5, &[0.0, 0.0]), vec![0, 0]);
+    }
+
+    #[test]
+    fn should_validate_matrix_against_limits() {
+        let regions = vec!["engine1".to_string(), "engine2".to_string()];
+        let matrix = vec![vec![0, 10], vec![30, 0]];
+
+        assert!(validate_matrix("commuters", &regions, &matrix, &[10, 30]).is_ok());
+        assert_eq!(
+            validate_matrix("commuters", &regions, &matrix, &[10, 20]),
+            Err("engine2 sends 30 commuters but has only 20".to_string())
+        );
+    }
+
+    #[test]
+    fn should_parse_travel_matrix_config() {
+        let json = r#"{
+            "regions": [{"engine_id": "engine1", "latitude": 18.5, "longitude": 73.8, "population": 185014}],
+            "commute": {"model": {"Gravity": {"destination_exponent": 1.0, "distance_exponent": 2.0}}, "share": 0.05},
+            "migration": {"model": "Radiation", "share": 0.001}
+        }"#;
+
+        let config: TravelMatrixConfig = serde_json::from_str(json).unwrap();
+
+        assert_eq!(config.regions[0].population, Some(185014));
+        assert_eq!(config.migration.unwrap().model, TravelModel::Radiation);
+    }
+}
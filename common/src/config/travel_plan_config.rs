@@ -17,7 +17,50 @@
  *
  */
 
+use rand::Rng;
+
+use crate::models::custom_types::{Day, Hour};
 use crate::models::{CommutePlan, MigrationPlan};
+use crate::transport::wire_format::WireFormat;
+use crate::utils::RandomWrapper;
+
+/// Travel from `from_day` on, until the next phase. The matrix replaces the one of the whole run, and is then
+/// scaled, e.g. by 0.2 for a lockdown cutting travel by 80%.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TravelPhase {
+    pub from_day: Day,
+    #[serde(default)]
+    pub matrix: Option<Vec<Vec<u32>>>,
+    #[serde(default = "no_scaling")]
+    pub scale: f64,
+}
+
+fn no_scaling() -> f64 {
+    1.0
+}
+
+/// How many days migrators stay before they return home
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum StayLength {
+    Fixed { days: Day },
+    Uniform { min_days: Day, max_days: Day },
+    Exponential { mean_days: f64 },
+}
+
+impl StayLength {
+    /// Days a migrator leaving now stays away, at least one
+    pub fn sample(&self, rng: &mut RandomWrapper) -> Day {
+        let days = match *self {
+            StayLength::Fixed { days } => days,
+            StayLength::Uniform { min_days, max_days } => rng.get().gen_range(min_days..=max_days.max(min_days)),
+            StayLength::Exponential { mean_days } => {
+                let uniform: f64 = rng.get().gen();
+                (-mean_days * (1.0 - uniform).ln()).round() as Day
+            }
+        };
+        days.max(1)
+    }
+}
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Migration {
@@ -25,12 +68,63 @@ pub struct Migration {
     matrix: Option<Vec<Vec<u32>>>,
     start_migration_hour: u32,
     end_migration_hour: u32,
+    #[serde(default)]
+    phases: Vec<TravelPhase>,
+    /// Scales the migrators leaving a region while it is locked down
+    #[serde(default = "no_scaling")]
+    lockdown_scale: f64,
+    /// Makes migrators return home after a stay of this length, instead of settling where they go
+    #[serde(default)]
+    stay_length: Option<StayLength>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Commute {
     pub enabled: bool,
     pub matrix: Option<Vec<Vec<u32>>>,
+    #[serde(default)]
+    phases: Vec<TravelPhase>,
+    /// Scales the commuters leaving a region while it is locked down
+    #[serde(default = "no_scaling")]
+    lockdown_scale: f64,
+}
+
+/// The matrix of the phase `day` falls in
+fn matrix_on(matrix: &[Vec<u32>], phases: &[TravelPhase], day: Day) -> Vec<Vec<u32>> {
+    match phases.iter().filter(|phase| phase.from_day <= day).max_by_key(|phase| phase.from_day) {
+        None => matrix.to_vec(),
+        Some(phase) => phase
+            .matrix
+            .as_deref()
+            .unwrap_or(matrix)
+            .iter()
+            .map(|row| row.iter().map(|travellers| (*travellers as f64 * phase.scale).round() as u32).collect())
+            .collect(),
+    }
+}
+
+fn validate_phases(kind: &str, regions: usize, phases: &[TravelPhase]) -> Result<(), String> {
+    for phase in phases {
+        if phase.scale < 0.0 {
+            return Err(format!("{} phase from day {} has a negative scale", kind, phase.from_day));
+        }
+        if let Some(matrix) = &phase.matrix {
+            if matrix.len() != regions || matrix.iter().any(|row| row.len() != regions) {
+                return Err(format!("{} phase from day {} needs a {}x{} matrix", kind, phase.from_day, regions, regions));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// A station or airport that the travellers arriving in a region pass through together, meeting
+/// `contacts_per_hour` of the others in each of the `hours` they spend there
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TransitHub {
+    pub name: String,
+    pub engine_id: String,
+    pub hours: Hour,
+    pub contacts_per_hour: u32,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -38,6 +132,10 @@ pub struct TravelPlanConfig {
     pub regions: Vec<String>,
     pub migration: Migration,
     pub commute: Commute,
+    #[serde(default)]
+    wire_format: WireFormat,
+    #[serde(default)]
+    hubs: Vec<TransitHub>,
 }
 
 impl TravelPlanConfig {
@@ -53,6 +151,16 @@ impl TravelPlanConfig {
         self.migration.matrix.clone()
     }
 
+    /// How engines encode the commuters and migrators they send each other
+    pub fn get_wire_format(&self) -> WireFormat {
+        self.wire_format
+    }
+
+    /// The hub travellers arriving in the region pass through, if it has one
+    pub fn get_transit_hub(&self, engine_id: &str) -> Option<&TransitHub> {
+        self.hubs.iter().find(|hub| hub.engine_id == engine_id)
+    }
+
     pub fn get_regions(&self) -> Vec<String> {
         self.regions.clone()
     }
@@ -61,6 +169,18 @@ impl TravelPlanConfig {
         regions.len() == self.regions.len() && regions.iter().all(|region| self.regions.contains(region))
     }
 
+    /// Enables migration with a generated matrix
+    pub fn set_migration_matrix(&mut self, matrix: Vec<Vec<u32>>) {
+        self.migration.enabled = true;
+        self.migration.matrix = Some(matrix);
+    }
+
+    /// Enables commute with a generated matrix
+    pub fn set_commute_matrix(&mut self, matrix: Vec<Vec<u32>>) {
+        self.commute.enabled = true;
+        self.commute.matrix = Some(matrix);
+    }
+
     pub fn migration_plan(&self) -> MigrationPlan {
         MigrationPlan::new(self.get_regions(), self.get_migration_matrix().unwrap())
     }
@@ -68,4 +188,151 @@ impl TravelPlanConfig {
     pub fn commute_plan(&self) -> CommutePlan {
         CommutePlan { regions: self.regions.clone(), matrix: self.commute.matrix.as_ref().unwrap().clone() }
     }
+
+    /// The migration plan in effect on `day`
+    pub fn migration_plan_on(&self, day: Day) -> MigrationPlan {
+        MigrationPlan::new(self.get_regions(), matrix_on(self.migration.matrix.as_ref().unwrap(), &self.migration.phases, day))
+    }
+
+    /// The commute plan in effect on `day`. Commuters are assigned to their offices with the plan of the whole run,
+    /// and only part of them leave for work when this one has fewer.
+    pub fn commute_plan_on(&self, day: Day) -> CommutePlan {
+        CommutePlan {
+            regions: self.regions.clone(),
+            matrix: matrix_on(self.commute.matrix.as_ref().unwrap(), &self.commute.phases, day),
+        }
+    }
+
+    /// Whether anyone migrates on `day`. Engines only exchange migrators, and wait for the orchestrator to tick
+    /// them through, on such days.
+    pub fn is_migration_active(&self, day: Day) -> bool {
+        self.migration.enabled && self.migration_plan_on(day).matrix.iter().flatten().any(|travellers| *travellers > 0)
+    }
+
+    /// Whether anyone commutes on `day`. Engines only exchange commuters, and wait for the orchestrator to tick
+    /// them through, on such days.
+    pub fn is_commute_active(&self, day: Day) -> bool {
+        self.commute.enabled && self.commute_plan_on(day).matrix.iter().flatten().any(|travellers| *travellers > 0)
+    }
+
+    pub fn get_migration_lockdown_scale(&self) -> f64 {
+        self.migration.lockdown_scale
+    }
+
+    /// How long migrators stay before returning home, `None` when they settle where they go
+    pub fn get_migration_stay_length(&self) -> Option<StayLength> {
+        self.migration.stay_length
+    }
+
+    pub fn get_commute_lockdown_scale(&self) -> f64 {
+        self.commute.lockdown_scale
+    }
+
+    pub fn validate_phases(&self) -> Result<(), String> {
+        validate_phases("Migration", self.regions.len(), &self.migration.phases)?;
+        validate_phases("Commute", self.regions.len(), &self.commute.phases)
+    }
+
+    pub fn validate_hubs(&self) -> Result<(), String> {
+        for (i, hub) in self.hubs.iter().enumerate() {
+            if !self.regions.contains(&hub.engine_id) {
+                return Err(format!("Hub {} is in {}, which is not a region of the travel plan", hub.name, hub.engine_id));
+            }
+            if self.hubs[..i].iter().any(|other| other.engine_id == hub.engine_id) {
+                return Err(format!("Region {} has more than one hub", hub.engine_id));
+            }
+            if hub.hours == 0 {
+                return Err(format!("Travellers need to spend at least an hour in hub {}", hub.name));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn travel_plan(commute_phases: &str) -> TravelPlanConfig {
+        serde_json::from_str(&format!(
+            r#"{{
+                "regions": ["engine1", "engine2"],
+                "migration": {{"enabled": true, "matrix": [[0, 10], [20, 0]], "start_migration_hour": 0,
+                    "end_migration_hour": 240}},
+                "commute": {{"enabled": true, "matrix": [[0, 100], [50, 0]], "phases": {}}}
+            }}"#,
+            commute_phases
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn should_keep_the_matrix_without_phases() {
+        let travel_plan = travel_plan("[]");
+        assert_eq!(travel_plan.commute_plan_on(30).matrix, vec![vec![0, 100], vec![50, 0]]);
+        assert_eq!(travel_plan.migration_plan_on(30).matrix, vec![vec![0, 10], vec![20, 0]]);
+        assert_eq!(travel_plan.get_commute_lockdown_scale(), 1.0);
+    }
+
+    #[test]
+    fn should_follow_the_phase_of_the_day() {
+        let travel_plan = travel_plan(
+            r#"[{"from_day": 20, "matrix": [[0, 10], [10, 0]]}, {"from_day": 10, "scale": 0.2},
+                {"from_day": 30, "scale": 0}]"#,
+        );
+
+        assert_eq!(travel_plan.commute_plan_on(9).matrix, vec![vec![0, 100], vec![50, 0]]);
+        assert_eq!(travel_plan.commute_plan_on(10).matrix, vec![vec![0, 20], vec![10, 0]]);
+        assert_eq!(travel_plan.commute_plan_on(25).matrix, vec![vec![0, 10], vec![10, 0]]);
+        assert!(travel_plan.is_commute_active(25));
+        assert!(!travel_plan.is_commute_active(30));
+        assert!(travel_plan.is_migration_active(30));
+    }
+
+    #[test]
+    fn should_sample_stay_lengths_of_at_least_a_day() {
+        let mut rng = RandomWrapper::new();
+        assert_eq!(StayLength::Fixed { days: 0 }.sample(&mut rng), 1);
+        for _ in 0..100 {
+            let days = StayLength::Uniform { min_days: 3, max_days: 5 }.sample(&mut rng);
+            assert!((3..=5).contains(&days));
+            assert!(StayLength::Exponential { mean_days: 2.0 }.sample(&mut rng) >= 1);
+        }
+        assert_eq!(travel_plan("[]").get_migration_stay_length(), None);
+    }
+
+    #[test]
+    fn should_reject_phases_with_matrices_for_other_regions() {
+        assert!(travel_plan(r#"[{"from_day": 1, "scale": 0.5}]"#).validate_phases().is_ok());
+        assert_eq!(
+            travel_plan(r#"[{"from_day": 5, "matrix": [[0, 1, 2], [3, 0, 4]]}]"#).validate_phases(),
+            Err("Commute phase from day 5 needs a 2x2 matrix".to_string())
+        );
+        assert!(travel_plan(r#"[{"from_day": 5, "scale": -1}]"#).validate_phases().is_err());
+    }
+
+    #[test]
+    fn should_find_and_validate_transit_hubs() {
+        let hub = |name: &str, engine_id: &str, hours: Hour| TransitHub {
+            name: name.to_string(),
+            engine_id: engine_id.to_string(),
+            hours,
+            contacts_per_hour: 5,
+        };
+        let mut travel_plan = travel_plan("[]");
+        assert!(travel_plan.validate_hubs().is_ok());
+        assert_eq!(travel_plan.get_transit_hub("engine1"), None);
+
+        travel_plan.hubs = vec![hub("airport", "engine1", 2)];
+        assert!(travel_plan.validate_hubs().is_ok());
+        assert_eq!(travel_plan.get_transit_hub("engine1").unwrap().name, "airport");
+        assert_eq!(travel_plan.get_transit_hub("engine2"), None);
+
+        travel_plan.hubs = vec![hub("airport", "engine1", 2), hub("station", "engine1", 1)];
+        assert_eq!(travel_plan.validate_hubs(), Err("Region engine1 has more than one hub".to_string()));
+        travel_plan.hubs = vec![hub("airport", "engine3", 2)];
+        assert!(travel_plan.validate_hubs().is_err());
+        travel_plan.hubs = vec![hub("airport", "engine2", 0)];
+        assert!(travel_plan.validate_hubs().is_err());
+    }
 }
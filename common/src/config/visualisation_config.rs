@@ -0,0 +1,98 @@
+/*
+ * EpiRust
+ * Copyright (c) 2020  ThoughtWorks, Inc.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ */
+
+use crate::models::custom_types::Hour;
+
+/// Renders the state of every citizen on the grid at a fixed hourly interval
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone, Copy)]
+pub struct VisualisationConfig {
+    #[serde(default)]
+    pub format: VisualisationFormat,
+    #[serde(default = "default_frame_interval")]
+    pub frame_interval: Hour,
+    /// time each frame is shown for, only used for gifs
+    #[serde(default = "default_frame_delay_ms")]
+    pub frame_delay_ms: u32,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum VisualisationFormat {
+    /// a single animated gif
+    #[default]
+    Gif,
+    /// one numbered png per frame
+    Png,
+}
+
+/// Image format of the epidemic curve charts
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ChartFormat {
+    #[default]
+    Png,
+    Svg,
+}
+
+impl ChartFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ChartFormat::Png => "png",
+            ChartFormat::Svg => "svg",
+        }
+    }
+
+    /// Picks the format from the file extension, defaulting to png
+    pub fn of_file(file_name: &str) -> ChartFormat {
+        if file_name.to_lowercase().ends_with(".svg") {
+            ChartFormat::Svg
+        } else {
+            ChartFormat::Png
+        }
+    }
+}
+
+fn default_frame_interval() -> Hour {
+    24
+}
+
+fn default_frame_delay_ms() -> u32 {
+    200
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_default_to_daily_gif_frames() {
+        let config: VisualisationConfig = serde_json::from_str("{}").unwrap();
+
+        assert_eq!(config.format, VisualisationFormat::Gif);
+        assert_eq!(config.frame_interval, 24);
+        assert_eq!(config.frame_delay_ms, 200);
+    }
+
+    #[test]
+    fn should_pick_chart_format_from_file_name() {
+        assert_eq!(ChartFormat::of_file("comparison.SVG"), ChartFormat::Svg);
+        assert_eq!(ChartFormat::of_file("comparison.png"), ChartFormat::Png);
+        assert_eq!(ChartFormat::of_file("comparison"), ChartFormat::Png);
+    }
+}
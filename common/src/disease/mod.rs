@@ -121,6 +121,37 @@ impl Disease {
     pub fn get_pre_symptomatic_duration(&self) -> Hour {
         self.pre_symptomatic_duration
     }
+
+    pub fn get_parameter(&self, parameter: DiseaseParameter) -> Percentage {
+        match parameter {
+            DiseaseParameter::RegularTransmissionRate => self.regular_transmission_rate,
+            DiseaseParameter::HighTransmissionRate => self.high_transmission_rate,
+            DiseaseParameter::DeathRate => self.death_rate,
+            DiseaseParameter::PercentageAsymptomaticPopulation => self.percentage_asymptomatic_population,
+            DiseaseParameter::PercentageSevereInfectedPopulation => self.percentage_severe_infected_population,
+        }
+    }
+
+    pub fn set_parameter(&mut self, parameter: DiseaseParameter, value: Percentage) {
+        match parameter {
+            DiseaseParameter::RegularTransmissionRate => self.regular_transmission_rate = value,
+            DiseaseParameter::HighTransmissionRate => self.high_transmission_rate = value,
+            DiseaseParameter::DeathRate => self.death_rate = value,
+            DiseaseParameter::PercentageAsymptomaticPopulation => self.percentage_asymptomatic_population = value,
+            DiseaseParameter::PercentageSevereInfectedPopulation => self.percentage_severe_infected_population = value,
+        }
+    }
+}
+
+/// Disease parameters that can be fitted during calibration
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum DiseaseParameter {
+    RegularTransmissionRate,
+    HighTransmissionRate,
+    DeathRate,
+    PercentageAsymptomaticPopulation,
+    PercentageSevereInfectedPopulation,
 }
 
 /// Override disease parameters for a specific population trait
@@ -181,4 +212,15 @@ mod tests {
         };
         assert_eq!(expected, disease)
     }
+
+    #[test]
+    fn should_set_parameter() {
+        let mut disease = Disease::init("config/diseases.yaml", &String::from("small_pox"));
+        disease.set_parameter(DiseaseParameter::HighTransmissionRate, 0.3);
+        disease.set_parameter(DiseaseParameter::DeathRate, 0.1);
+
+        assert_eq!(disease.get_parameter(DiseaseParameter::HighTransmissionRate), 0.3);
+        assert_eq!(disease.get_parameter(DiseaseParameter::DeathRate), 0.1);
+        assert_eq!(disease.get_parameter(DiseaseParameter::RegularTransmissionRate), 0.05);
+    }
 }
@@ -17,10 +17,13 @@
  *
  */
 
+#[macro_use]
+extern crate log;
 #[macro_use]
 extern crate serde_derive;
 
 pub mod config;
 pub mod disease;
 pub mod models;
+pub mod transport;
 pub mod utils;
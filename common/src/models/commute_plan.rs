@@ -43,4 +43,29 @@ impl CommutePlan {
         }
         commuters_by_region
     }
+
+    /// The other regions that exchange commuters with `region` in either direction. They send it a batch at every
+    /// commute hour, even when nobody is travelling that day, so it knows how many batches to wait for.
+    pub fn connected_regions(&self, region: &String) -> Vec<String> {
+        self.regions
+            .iter()
+            .filter(|other| *other != region && (self.get_outgoing(region, other) > 0 || self.get_outgoing(other, region) > 0))
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_connect_regions_commuting_in_either_direction() {
+        let regions = vec!["engine1".to_string(), "engine2".to_string(), "engine3".to_string()];
+        let plan = CommutePlan { regions, matrix: vec![vec![4, 5, 0], vec![0, 0, 0], vec![0, 0, 0]] };
+
+        assert_eq!(plan.connected_regions(&"engine1".to_string()), vec!["engine2".to_string()]);
+        assert_eq!(plan.connected_regions(&"engine2".to_string()), vec!["engine1".to_string()]);
+        assert!(plan.connected_regions(&"engine3".to_string()).is_empty());
+    }
 }
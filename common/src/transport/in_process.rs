@@ -0,0 +1,233 @@
+/*
+ * EpiRust
+ * Copyright (c) 2020  ThoughtWorks, Inc.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ */
+
+use std::collections::HashMap;
+use std::future::Future;
+
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio::sync::watch;
+
+use crate::transport::{EngineTransport, OrchestratorTransport, Payload};
+
+/// Engine end of channels shared by engines running in the same process as the orchestrator
+pub struct InProcessEngineTransport {
+    engine_id: String,
+    ticks: UnboundedReceiver<Payload>,
+    acks: UnboundedSender<Payload>,
+    commuters: UnboundedReceiver<Payload>,
+    migrators: UnboundedReceiver<Payload>,
+    commuters_to: HashMap<String, UnboundedSender<Payload>>,
+    migrators_to: HashMap<String, UnboundedSender<Payload>>,
+    stopped: watch::Receiver<bool>,
+}
+
+impl InProcessEngineTransport {
+    pub fn engine_id(&self) -> &str {
+        &self.engine_id
+    }
+
+    fn send(engine_id: &str, senders: &HashMap<String, UnboundedSender<Payload>>, to_engine_id: &str, payload: Payload) {
+        match senders.get(to_engine_id) {
+            None => panic!("{}: No engine with id {} to send travellers to", engine_id, to_engine_id),
+            Some(sender) => {
+                if sender.send(payload).is_err() {
+                    debug!("{}: Engine {} has already finished, dropping travellers", engine_id, to_engine_id);
+                }
+            }
+        }
+    }
+
+    /// `None` once the orchestrator has stopped the engines, even though other engines could still send travellers
+    async fn unless_stopped(
+        stopped: &mut watch::Receiver<bool>,
+        receive: impl Future<Output = Option<Payload>>,
+    ) -> Option<Payload> {
+        tokio::select! {
+            biased;
+            payload = receive => payload,
+            Ok(_) = stopped.wait_for(|stopped| *stopped) => None,
+        }
+    }
+}
+
+impl EngineTransport for InProcessEngineTransport {
+    async fn receive_tick(&mut self) -> Option<Payload> {
+        self.ticks.recv().await
+    }
+
+    fn send_ack(&mut self, ack: Payload) {
+        if self.acks.send(ack).is_err() {
+            debug!("{}: Orchestrator has already finished, dropping ack", self.engine_id);
+        }
+    }
+
+    fn send_commuters(&mut self, to_engine_id: &str, commuters: Payload) {
+        Self::send(&self.engine_id, &self.commuters_to, to_engine_id, commuters);
+    }
+
+    fn send_migrators(&mut self, to_engine_id: &str, migrators: Payload) {
+        Self::send(&self.engine_id, &self.migrators_to, to_engine_id, migrators);
+    }
+
+    async fn receive_commuters(&mut self) -> Option<Payload> {
+        Self::unless_stopped(&mut self.stopped, self.commuters.recv()).await
+    }
+
+    async fn receive_migrators(&mut self) -> Option<Payload> {
+        Self::unless_stopped(&mut self.stopped, self.migrators.recv()).await
+    }
+}
+
+/// Orchestrator end of channels shared by engines running in the same process
+pub struct InProcessOrchestratorTransport {
+    ticks: Vec<UnboundedSender<Payload>>,
+    acks: UnboundedReceiver<Payload>,
+    stop: watch::Sender<bool>,
+}
+
+impl InProcessOrchestratorTransport {
+    /// Stops the engines waiting on travellers, so that after a failure the engines still waiting on the ones that
+    /// failed finish as well
+    pub fn stop_engines(&self) {
+        self.stop.send_replace(true);
+    }
+}
+
+impl OrchestratorTransport for InProcessOrchestratorTransport {
+    fn send_tick(&mut self, tick: Payload) {
+        for engine in &self.ticks {
+            if engine.send(tick.clone()).is_err() {
+                debug!("An engine has already finished, dropping tick");
+            }
+        }
+    }
+
+    async fn receive_ack(&mut self) -> Option<Payload> {
+        self.acks.recv().await
+    }
+}
+
+/// Wires the orchestrator to every engine and the engines to each other, returning the engine ends in the order of `engine_ids`
+pub fn connect(engine_ids: &[String]) -> (InProcessOrchestratorTransport, Vec<InProcessEngineTransport>) {
+    let (acks_sender, acks) = unbounded_channel();
+    let (stop, stopped) = watch::channel(false);
+    let mut ticks_senders = Vec::new();
+    let mut ticks_receivers = Vec::new();
+    let mut commuters_to = HashMap::new();
+    let mut commuters_receivers = Vec::new();
+    let mut migrators_to = HashMap::new();
+    let mut migrators_receivers = Vec::new();
+    for engine_id in engine_ids {
+        let (sender, receiver) = unbounded_channel();
+        ticks_senders.push(sender);
+        ticks_receivers.push(receiver);
+        let (sender, receiver) = unbounded_channel();
+        commuters_to.insert(engine_id.clone(), sender);
+        commuters_receivers.push(receiver);
+        let (sender, receiver) = unbounded_channel();
+        migrators_to.insert(engine_id.clone(), sender);
+        migrators_receivers.push(receiver);
+    }
+
+    let engines = engine_ids
+        .iter()
+        .zip(ticks_receivers)
+        .zip(commuters_receivers.into_iter().zip(migrators_receivers))
+        .map(|((engine_id, ticks), (commuters, migrators))| InProcessEngineTransport {
+            engine_id: engine_id.clone(),
+            ticks,
+            acks: acks_sender.clone(),
+            commuters,
+            migrators,
+            commuters_to: commuters_to.clone(),
+            migrators_to: migrators_to.clone(),
+            stopped: stopped.clone(),
+        })
+        .collect();
+
+    (InProcessOrchestratorTransport { ticks: ticks_senders, acks, stop }, engines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn engine_ids() -> Vec<String> {
+        vec!["engine1".to_string(), "engine2".to_string()]
+    }
+
+    #[test]
+    fn should_deliver_ticks_to_every_engine_and_acks_to_the_orchestrator() {
+        let (mut orchestrator, mut engines) = connect(&engine_ids());
+        orchestrator.send_tick(b"tick".to_vec());
+
+        futures::executor::block_on(async {
+            for engine in engines.iter_mut() {
+                assert_eq!(engine.receive_tick().await, Some(b"tick".to_vec()));
+                engine.send_ack(engine.engine_id().as_bytes().to_vec());
+            }
+            assert_eq!(orchestrator.receive_ack().await, Some(b"engine1".to_vec()));
+            assert_eq!(orchestrator.receive_ack().await, Some(b"engine2".to_vec()));
+        });
+    }
+
+    #[test]
+    fn should_deliver_travellers_to_the_addressed_engine() {
+        let (_orchestrator, mut engines) = connect(&engine_ids());
+        engines[0].send_commuters("engine2", b"commuters".to_vec());
+        engines[1].send_migrators("engine1", b"migrators".to_vec());
+
+        futures::executor::block_on(async {
+            assert_eq!(engines[1].receive_commuters().await, Some(b"commuters".to_vec()));
+            assert_eq!(engines[0].receive_migrators().await, Some(b"migrators".to_vec()));
+        });
+    }
+
+    #[test]
+    fn should_stop_engines_waiting_on_travellers() {
+        let (orchestrator, mut engines) = connect(&engine_ids());
+        engines[0].send_commuters("engine2", b"commuters".to_vec());
+        orchestrator.stop_engines();
+
+        futures::executor::block_on(async {
+            assert_eq!(engines[1].receive_commuters().await, Some(b"commuters".to_vec()));
+            assert_eq!(engines[1].receive_commuters().await, None);
+            assert_eq!(engines[0].receive_migrators().await, None);
+        });
+    }
+
+    #[test]
+    fn should_keep_travellers_coming_after_the_orchestrator_is_gone() {
+        let (orchestrator, mut engines) = connect(&engine_ids());
+        drop(orchestrator);
+        engines[0].send_commuters("engine2", b"commuters".to_vec());
+
+        futures::executor::block_on(async {
+            assert_eq!(engines[1].receive_commuters().await, Some(b"commuters".to_vec()));
+        });
+    }
+
+    #[test]
+    fn should_end_the_acks_once_all_engines_are_gone() {
+        let (mut orchestrator, engines) = connect(&engine_ids());
+        drop(engines);
+
+        assert_eq!(futures::executor::block_on(orchestrator.receive_ack()), None);
+    }
+}
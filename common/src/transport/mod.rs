@@ -0,0 +1,67 @@
+/*
+ * EpiRust
+ * Copyright (c) 2020  ThoughtWorks, Inc.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ */
+
+//! Messaging between the orchestrator and the engines of a multi-engine simulation.
+//!
+//! Transports only move encoded messages around, the engines and the orchestrator decide what goes in them.
+
+mod in_process;
+mod tcp;
+pub mod wire_format;
+
+use std::future::Future;
+
+pub use in_process::{connect, InProcessEngineTransport, InProcessOrchestratorTransport};
+pub use tcp::{TcpEngineTransport, TcpOrchestratorTransport};
+
+/// An encoded tick, acknowledgement, or batch of travellers
+pub type Payload = Vec<u8>;
+
+/// The engine's side of a multi-engine simulation
+pub trait EngineTransport {
+    /// Waits for the next tick from the orchestrator, `None` once the orchestrator has gone away
+    fn receive_tick(&mut self) -> impl Future<Output = Option<Payload>>;
+
+    fn send_ack(&mut self, ack: Payload);
+
+    fn send_commuters(&mut self, to_engine_id: &str, commuters: Payload);
+
+    fn send_migrators(&mut self, to_engine_id: &str, migrators: Payload);
+
+    /// Waits for the next batch of commuters sent to this engine, `None` once no other engine can send any
+    fn receive_commuters(&mut self) -> impl Future<Output = Option<Payload>>;
+
+    /// Waits for the next batch of migrators sent to this engine, `None` once no other engine can send any
+    fn receive_migrators(&mut self) -> impl Future<Output = Option<Payload>>;
+}
+
+/// The orchestrator's side of a multi-engine simulation
+pub trait OrchestratorTransport {
+    /// Sends the tick to every engine
+    fn send_tick(&mut self, tick: Payload);
+
+    /// Waits for the next acknowledgement from any engine, `None` once all the engines have gone away, or, for
+    /// transports that can tell which engines have gone, whenever another one has
+    fn receive_ack(&mut self) -> impl Future<Output = Option<Payload>>;
+
+    /// The engines known to have gone away, always empty for transports that cannot tell
+    fn gone(&self) -> &[String] {
+        &[]
+    }
+}
@@ -0,0 +1,579 @@
+/*
+ * EpiRust
+ * Copyright (c) 2020  ThoughtWorks, Inc.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ */
+
+//! Framed TCP between the orchestrator and the engines, and directly between engines.
+//!
+//! Every engine registers with the orchestrator, which answers with the simulation request and the address of
+//! every engine once all of them have registered. The engines then connect to each other to send travellers.
+
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::net::SocketAddr;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio::task::JoinHandle;
+
+use crate::transport::{EngineTransport, OrchestratorTransport, Payload};
+
+/// Same as the largest message the Kafka transport accepts
+const MAX_FRAME_LENGTH: usize = 104_857_600;
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+enum Frame {
+    Register { engine_id: String, address: SocketAddr },
+    Start { request: Payload, peers: HashMap<String, SocketAddr> },
+    Hello { engine_id: String },
+    Tick(Payload),
+    Ack(Payload),
+    Commuters(Payload),
+    Migrators(Payload),
+}
+
+impl Frame {
+    fn kind(&self) -> &'static str {
+        match self {
+            Frame::Register { .. } => "register",
+            Frame::Start { .. } => "start",
+            Frame::Hello { .. } => "hello",
+            Frame::Tick(_) => "tick",
+            Frame::Ack(_) => "ack",
+            Frame::Commuters(_) => "commuters",
+            Frame::Migrators(_) => "migrators",
+        }
+    }
+}
+
+fn invalid_data<E: Into<Box<dyn std::error::Error + Send + Sync>>>(error: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+fn unexpected(frame: Option<Frame>, expected: &str) -> io::Error {
+    match frame {
+        None => io::Error::new(io::ErrorKind::UnexpectedEof, format!("connection closed while waiting for {}", expected)),
+        Some(frame) => invalid_data(format!("received {} while waiting for {}", frame.kind(), expected)),
+    }
+}
+
+async fn write_frame<W: AsyncWrite + Unpin>(writer: &mut W, frame: &Frame) -> io::Result<()> {
+    let bytes = bincode::serialize(frame).map_err(invalid_data)?;
+    if bytes.len() > MAX_FRAME_LENGTH {
+        return Err(invalid_data(format!("{} frame of {} bytes is too large", frame.kind(), bytes.len())));
+    }
+    writer.write_u32(bytes.len() as u32).await?;
+    writer.write_all(&bytes).await
+}
+
+/// Reads the next frame, `None` if the connection was closed in between frames
+async fn read_frame<R: AsyncRead + Unpin>(reader: &mut R) -> io::Result<Option<Frame>> {
+    let length = match reader.read_u32().await {
+        Ok(length) => length as usize,
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    if length > MAX_FRAME_LENGTH {
+        return Err(invalid_data(format!("frame of {} bytes is too large", length)));
+    }
+    let mut bytes = vec![0; length];
+    reader.read_exact(&mut bytes).await?;
+    bincode::deserialize(&bytes).map(Some).map_err(invalid_data)
+}
+
+/// Writes the frames sent to the returned sender in order, until the sender is dropped or the connection fails
+fn spawn_writer(mut writer: OwnedWriteHalf, to: String) -> (UnboundedSender<Frame>, JoinHandle<()>) {
+    let (sender, mut frames) = unbounded_channel::<Frame>();
+    let handle = tokio::spawn(async move {
+        while let Some(frame) = frames.recv().await {
+            if let Err(e) = write_frame(&mut writer, &frame).await {
+                error!("Failed to send {} to {}: {}", frame.kind(), to, e);
+                return;
+            }
+        }
+    });
+    (sender, handle)
+}
+
+/// Where the frames read from a connection go
+#[derive(Clone, Default)]
+struct Inbox {
+    ticks: Option<UnboundedSender<Payload>>,
+    acks: Option<UnboundedSender<Payload>>,
+    commuters: Option<UnboundedSender<Payload>>,
+    migrators: Option<UnboundedSender<Payload>>,
+    /// Told who the connection was from once it closes
+    closed: Option<UnboundedSender<String>>,
+}
+
+impl Inbox {
+    /// Returns false once nobody is listening for this kind of frame any more
+    fn deliver(&self, frame: Frame, from: &str) -> bool {
+        let kind = frame.kind();
+        let (channel, payload) = match frame {
+            Frame::Tick(payload) => (&self.ticks, payload),
+            Frame::Ack(payload) => (&self.acks, payload),
+            Frame::Commuters(payload) => (&self.commuters, payload),
+            Frame::Migrators(payload) => (&self.migrators, payload),
+            _ => (&None, Vec::new()),
+        };
+        match channel {
+            None => {
+                error!("Ignoring unexpected {} from {}", kind, from);
+                true
+            }
+            Some(channel) => channel.send(payload).is_ok(),
+        }
+    }
+
+    fn spawn_reader(self, mut reader: OwnedReadHalf, from: String) {
+        tokio::spawn(async move {
+            self.read_all(&mut reader, &from).await;
+            if let Some(closed) = &self.closed {
+                let _ = closed.send(from);
+            }
+        });
+    }
+
+    async fn read_all(&self, reader: &mut OwnedReadHalf, from: &str) {
+        loop {
+            match read_frame(reader).await {
+                Ok(Some(frame)) => {
+                    if !self.deliver(frame, from) {
+                        return;
+                    }
+                }
+                Ok(None) => {
+                    debug!("{} closed the connection", from);
+                    return;
+                }
+                Err(e) => {
+                    error!("Failed to read from {}: {}", from, e);
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// The address other engines can reach this engine on. When listening on all interfaces, that is the interface
+/// used to reach the orchestrator.
+fn advertised_address(listening: SocketAddr, towards_orchestrator: SocketAddr) -> SocketAddr {
+    if listening.ip().is_unspecified() {
+        SocketAddr::new(towards_orchestrator.ip(), listening.port())
+    } else {
+        listening
+    }
+}
+
+async fn accept_engines(
+    listener: TcpListener,
+    commuters: UnboundedSender<Payload>,
+    migrators: UnboundedSender<Payload>,
+    closed: UnboundedSender<String>,
+) -> io::Result<()> {
+    loop {
+        let (stream, from) = listener.accept().await?;
+        stream.set_nodelay(true)?;
+        let (mut reader, _) = stream.into_split();
+        match read_frame(&mut reader).await {
+            Ok(Some(Frame::Hello { engine_id })) => {
+                debug!("Engine {} connected from {}", engine_id, from);
+                let inbox = Inbox {
+                    commuters: Some(commuters.clone()),
+                    migrators: Some(migrators.clone()),
+                    closed: Some(closed.clone()),
+                    ..Inbox::default()
+                };
+                inbox.spawn_reader(reader, engine_id);
+            }
+            Ok(frame) => error!("Rejected connection from {}: {}", from, unexpected(frame, "hello")),
+            Err(e) => error!("Rejected connection from {}: {}", from, e),
+        }
+    }
+}
+
+/// Keeps track of the other engines that have closed their connection to this one
+struct Senders {
+    closed: UnboundedReceiver<String>,
+    gone: HashSet<String>,
+    others: usize,
+    engine_id: String,
+}
+
+impl Senders {
+    /// The next payload from `payloads`, `None` once every other engine has closed its connection and everything
+    /// they sent before that has been received
+    async fn receive(&mut self, payloads: &mut UnboundedReceiver<Payload>) -> Option<Payload> {
+        loop {
+            if self.gone.len() == self.others {
+                return payloads.try_recv().ok();
+            }
+            tokio::select! {
+                biased;
+                Some(payload) = payloads.recv() => return Some(payload),
+                Some(engine_id) = self.closed.recv() => {
+                    if engine_id != self.engine_id {
+                        debug!("{}: Engine {} closed the connection", self.engine_id, engine_id);
+                        self.gone.insert(engine_id);
+                    }
+                }
+                else => return None,
+            }
+        }
+    }
+}
+
+/// Engine end of the TCP transport
+pub struct TcpEngineTransport {
+    engine_id: String,
+    ticks: UnboundedReceiver<Payload>,
+    commuters: UnboundedReceiver<Payload>,
+    migrators: UnboundedReceiver<Payload>,
+    senders: Senders,
+    orchestrator: UnboundedSender<Frame>,
+    peers: HashMap<String, UnboundedSender<Frame>>,
+    writers: Vec<JoinHandle<()>>,
+}
+
+impl TcpEngineTransport {
+    /// Listens for other engines on `listen`, registers with the orchestrator at `orchestrator`, and connects to the
+    /// other engines once all of them have registered. Returns the simulation request sent by the orchestrator.
+    pub async fn connect(engine_id: &str, orchestrator: &str, listen: &str) -> io::Result<(TcpEngineTransport, Payload)> {
+        let listener = TcpListener::bind(listen).await?;
+        let stream = TcpStream::connect(orchestrator).await?;
+        stream.set_nodelay(true)?;
+        let address = advertised_address(listener.local_addr()?, stream.local_addr()?);
+        let (mut orchestrator_reader, mut orchestrator_writer) = stream.into_split();
+        info!("{}: Registering with orchestrator at {}, listening for engines on {}", engine_id, orchestrator, address);
+        write_frame(&mut orchestrator_writer, &Frame::Register { engine_id: engine_id.to_string(), address }).await?;
+
+        let (commuters_sender, commuters) = unbounded_channel();
+        let (migrators_sender, migrators) = unbounded_channel();
+        let (closed_sender, closed) = unbounded_channel();
+        tokio::spawn(async move {
+            if let Err(e) = accept_engines(listener, commuters_sender, migrators_sender, closed_sender).await {
+                error!("Stopped accepting engines: {}", e);
+            }
+        });
+
+        let (request, addresses) = match read_frame(&mut orchestrator_reader).await? {
+            Some(Frame::Start { request, peers }) => (request, peers),
+            frame => return Err(unexpected(frame, "start")),
+        };
+
+        let mut writers = Vec::new();
+        let mut peers = HashMap::new();
+        for (peer_id, peer_address) in addresses {
+            let mut stream = TcpStream::connect(peer_address).await?;
+            stream.set_nodelay(true)?;
+            write_frame(&mut stream, &Frame::Hello { engine_id: engine_id.to_string() }).await?;
+            let (_, writer) = stream.into_split();
+            let (sender, handle) = spawn_writer(writer, peer_id.clone());
+            peers.insert(peer_id, sender);
+            writers.push(handle);
+        }
+        debug!("{}: Connected to {} engines", engine_id, peers.len());
+        let others = peers.keys().filter(|peer_id| *peer_id != engine_id).count();
+        let senders = Senders { closed, gone: HashSet::new(), others, engine_id: engine_id.to_string() };
+
+        let (ticks_sender, ticks) = unbounded_channel();
+        Inbox { ticks: Some(ticks_sender), ..Inbox::default() }.spawn_reader(orchestrator_reader, "orchestrator".to_string());
+        let (orchestrator, handle) = spawn_writer(orchestrator_writer, "orchestrator".to_string());
+        writers.push(handle);
+
+        let transport = TcpEngineTransport {
+            engine_id: engine_id.to_string(),
+            ticks,
+            commuters,
+            migrators,
+            senders,
+            orchestrator,
+            peers,
+            writers,
+        };
+        Ok((transport, request))
+    }
+
+    /// Waits until everything sent so far has been written out
+    pub async fn shutdown(self) {
+        let TcpEngineTransport { orchestrator, peers, writers, .. } = self;
+        drop(orchestrator);
+        drop(peers);
+        for writer in writers {
+            let _ = writer.await;
+        }
+    }
+
+    fn send_to_peer(&self, to_engine_id: &str, frame: Frame) {
+        match self.peers.get(to_engine_id) {
+            None => panic!("{}: No engine with id {} to send travellers to", self.engine_id, to_engine_id),
+            Some(peer) => {
+                if peer.send(frame).is_err() {
+                    error!("{}: Lost the connection to engine {}", self.engine_id, to_engine_id);
+                }
+            }
+        }
+    }
+}
+
+impl EngineTransport for TcpEngineTransport {
+    async fn receive_tick(&mut self) -> Option<Payload> {
+        self.ticks.recv().await
+    }
+
+    fn send_ack(&mut self, ack: Payload) {
+        if self.orchestrator.send(Frame::Ack(ack)).is_err() {
+            error!("{}: Lost the connection to the orchestrator", self.engine_id);
+        }
+    }
+
+    fn send_commuters(&mut self, to_engine_id: &str, commuters: Payload) {
+        self.send_to_peer(to_engine_id, Frame::Commuters(commuters));
+    }
+
+    fn send_migrators(&mut self, to_engine_id: &str, migrators: Payload) {
+        self.send_to_peer(to_engine_id, Frame::Migrators(migrators));
+    }
+
+    async fn receive_commuters(&mut self) -> Option<Payload> {
+        self.senders.receive(&mut self.commuters).await
+    }
+
+    async fn receive_migrators(&mut self) -> Option<Payload> {
+        self.senders.receive(&mut self.migrators).await
+    }
+}
+
+/// Orchestrator end of the TCP transport
+pub struct TcpOrchestratorTransport {
+    engines: Vec<UnboundedSender<Frame>>,
+    acks: UnboundedReceiver<Payload>,
+    closed: UnboundedReceiver<String>,
+    gone: Vec<String>,
+    writers: Vec<JoinHandle<()>>,
+}
+
+impl TcpOrchestratorTransport {
+    /// Waits on `listener` until every engine in `engine_ids` has registered, then sends all of them `request` and
+    /// the addresses of every engine
+    pub async fn accept(listener: TcpListener, engine_ids: &[String], request: Payload) -> io::Result<TcpOrchestratorTransport> {
+        info!("Waiting for {} engines on {}", engine_ids.len(), listener.local_addr()?);
+        let mut registered: HashMap<String, (TcpStream, SocketAddr)> = HashMap::new();
+        while registered.len() < engine_ids.len() {
+            let (mut stream, from) = listener.accept().await?;
+            match read_frame(&mut stream).await {
+                Ok(Some(Frame::Register { engine_id, address })) => {
+                    if !engine_ids.contains(&engine_id) {
+                        error!("Rejected unknown engine {} from {}", engine_id, from);
+                        continue;
+                    }
+                    match registered.entry(engine_id) {
+                        Entry::Occupied(entry) => {
+                            error!("Rejected engine {} from {}, it has already registered", entry.key(), from)
+                        }
+                        Entry::Vacant(entry) => {
+                            info!("Engine {} registered from {}", entry.key(), from);
+                            entry.insert((stream, address));
+                        }
+                    }
+                }
+                Ok(frame) => error!("Rejected connection from {}: {}", from, unexpected(frame, "register")),
+                Err(e) => error!("Rejected connection from {}: {}", from, e),
+            }
+        }
+
+        let peers: HashMap<String, SocketAddr> = registered.iter().map(|(id, (_, address))| (id.clone(), *address)).collect();
+        let (acks_sender, acks) = unbounded_channel();
+        let (closed_sender, closed) = unbounded_channel();
+        let mut engines = Vec::new();
+        let mut writers = Vec::new();
+        for (engine_id, (stream, _)) in registered {
+            stream.set_nodelay(true)?;
+            let (reader, mut writer) = stream.into_split();
+            write_frame(&mut writer, &Frame::Start { request: request.clone(), peers: peers.clone() }).await?;
+            let inbox = Inbox { acks: Some(acks_sender.clone()), closed: Some(closed_sender.clone()), ..Inbox::default() };
+            inbox.spawn_reader(reader, engine_id.clone());
+            let (sender, handle) = spawn_writer(writer, engine_id);
+            engines.push(sender);
+            writers.push(handle);
+        }
+        Ok(TcpOrchestratorTransport { engines, acks, closed, gone: Vec::new(), writers })
+    }
+
+    /// Waits until everything sent so far has been written out
+    pub async fn shutdown(self) {
+        drop(self.engines);
+        for writer in self.writers {
+            let _ = writer.await;
+        }
+    }
+}
+
+impl OrchestratorTransport for TcpOrchestratorTransport {
+    fn send_tick(&mut self, tick: Payload) {
+        for engine in &self.engines {
+            if engine.send(Frame::Tick(tick.clone())).is_err() {
+                error!("Lost the connection to an engine, dropping tick");
+            }
+        }
+    }
+
+    /// `None` whenever the connection to an engine closes, after the acks it sent before that
+    async fn receive_ack(&mut self) -> Option<Payload> {
+        tokio::select! {
+            biased;
+            Some(ack) = self.acks.recv() => Some(ack),
+            Some(engine_id) = self.closed.recv() => {
+                debug!("Engine {} closed the connection", engine_id);
+                self.gone.push(engine_id);
+                None
+            }
+            else => None,
+        }
+    }
+
+    fn gone(&self) -> &[String] {
+        &self.gone
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn should_read_back_written_frames() {
+        let frame = Frame::Commuters(vec![1, 2, 3]);
+        let mut bytes = Vec::new();
+        write_frame(&mut bytes, &frame).await.unwrap();
+        write_frame(&mut bytes, &Frame::Hello { engine_id: "engine1".to_string() }).await.unwrap();
+
+        let mut reader = bytes.as_slice();
+        assert_eq!(read_frame(&mut reader).await.unwrap(), Some(frame));
+        assert_eq!(read_frame(&mut reader).await.unwrap(), Some(Frame::Hello { engine_id: "engine1".to_string() }));
+        assert_eq!(read_frame(&mut reader).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn should_reject_oversized_frames() {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&(MAX_FRAME_LENGTH as u32 + 1).to_be_bytes());
+        assert!(read_frame(&mut bytes.as_slice()).await.is_err());
+    }
+
+    #[test]
+    fn should_advertise_the_interface_towards_the_orchestrator_when_listening_on_all() {
+        let towards_orchestrator: SocketAddr = "10.0.0.5:40000".parse().unwrap();
+        assert_eq!(
+            advertised_address("0.0.0.0:7001".parse().unwrap(), towards_orchestrator),
+            "10.0.0.5:7001".parse::<SocketAddr>().unwrap()
+        );
+        assert_eq!(
+            advertised_address("127.0.0.1:7001".parse().unwrap(), towards_orchestrator),
+            "127.0.0.1:7001".parse::<SocketAddr>().unwrap()
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn should_exchange_ticks_acks_and_travellers() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let orchestrator_address = listener.local_addr().unwrap().to_string();
+        let engine_ids = vec!["engine1".to_string(), "engine2".to_string()];
+
+        let (orchestrator, engine1, engine2) = tokio::join!(
+            TcpOrchestratorTransport::accept(listener, &engine_ids, b"request".to_vec()),
+            TcpEngineTransport::connect("engine1", &orchestrator_address, "127.0.0.1:0"),
+            TcpEngineTransport::connect("engine2", &orchestrator_address, "127.0.0.1:0"),
+        );
+        let mut orchestrator = orchestrator.unwrap();
+        let (mut engine1, request) = engine1.unwrap();
+        let (mut engine2, _) = engine2.unwrap();
+        assert_eq!(request, b"request".to_vec());
+
+        orchestrator.send_tick(b"tick".to_vec());
+        assert_eq!(engine1.receive_tick().await, Some(b"tick".to_vec()));
+        assert_eq!(engine2.receive_tick().await, Some(b"tick".to_vec()));
+
+        engine1.send_commuters("engine2", b"commuters".to_vec());
+        engine2.send_migrators("engine1", b"migrators".to_vec());
+        engine1.send_commuters("engine1", Vec::new());
+        assert_eq!(engine2.receive_commuters().await, Some(b"commuters".to_vec()));
+        assert_eq!(engine1.receive_migrators().await, Some(b"migrators".to_vec()));
+        assert_eq!(engine1.receive_commuters().await, Some(Vec::new()));
+
+        engine1.send_ack(b"ack".to_vec());
+        assert_eq!(orchestrator.receive_ack().await, Some(b"ack".to_vec()));
+
+        engine1.shutdown().await;
+        engine2.shutdown().await;
+        assert_eq!(orchestrator.receive_ack().await, None);
+        orchestrator.shutdown().await;
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn should_stop_receiving_acks_when_an_engine_disconnects() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let orchestrator_address = listener.local_addr().unwrap().to_string();
+        let engine_ids = vec!["engine1".to_string(), "engine2".to_string()];
+
+        let (orchestrator, engine1, engine2) = tokio::join!(
+            TcpOrchestratorTransport::accept(listener, &engine_ids, b"request".to_vec()),
+            TcpEngineTransport::connect("engine1", &orchestrator_address, "127.0.0.1:0"),
+            TcpEngineTransport::connect("engine2", &orchestrator_address, "127.0.0.1:0"),
+        );
+        let mut orchestrator = orchestrator.unwrap();
+        let (mut engine1, _) = engine1.unwrap();
+        let (_engine2, _) = engine2.unwrap();
+
+        engine1.send_ack(b"ack".to_vec());
+        engine1.shutdown().await;
+
+        assert_eq!(orchestrator.receive_ack().await, Some(b"ack".to_vec()));
+        assert_eq!(orchestrator.receive_ack().await, None);
+        assert_eq!(orchestrator.gone(), ["engine1".to_string()]);
+        orchestrator.shutdown().await;
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn should_stop_receiving_travellers_once_the_other_engines_disconnect() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let orchestrator_address = listener.local_addr().unwrap().to_string();
+        let engine_ids = vec!["engine1".to_string(), "engine2".to_string()];
+
+        let (orchestrator, engine1, engine2) = tokio::join!(
+            TcpOrchestratorTransport::accept(listener, &engine_ids, b"request".to_vec()),
+            TcpEngineTransport::connect("engine1", &orchestrator_address, "127.0.0.1:0"),
+            TcpEngineTransport::connect("engine2", &orchestrator_address, "127.0.0.1:0"),
+        );
+        let orchestrator = orchestrator.unwrap();
+        let (mut engine1, _) = engine1.unwrap();
+        let (mut engine2, _) = engine2.unwrap();
+
+        engine2.send_commuters("engine1", b"commuters".to_vec());
+        engine2.shutdown().await;
+
+        assert_eq!(engine1.receive_commuters().await, Some(b"commuters".to_vec()));
+        assert_eq!(engine1.receive_commuters().await, None);
+        assert_eq!(engine1.receive_migrators().await, None);
+        engine1.shutdown().await;
+        orchestrator.shutdown().await;
+    }
+}
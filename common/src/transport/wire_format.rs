@@ -0,0 +1,108 @@
+/*
+ * EpiRust
+ * Copyright (c) 2020  ThoughtWorks, Inc.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ */
+
+//! Encoding of the travellers moved between engines.
+//!
+//! JSON payloads are sent as they are. Binary payloads start with a header naming the format and its version, so
+//! an engine can read both and refuses payloads from an engine speaking another version.
+
+use std::error::Error;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::transport::Payload;
+
+const BINARY_MAGIC: &[u8; 3] = b"EPB";
+pub const BINARY_VERSION: u8 = 3;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WireFormat {
+    #[default]
+    Json,
+    Binary,
+}
+
+pub fn encode<T: Serialize>(format: WireFormat, message: &T) -> Payload {
+    match format {
+        WireFormat::Json => serde_json::to_vec(message).expect("Failed to encode message as JSON"),
+        WireFormat::Binary => {
+            let size = bincode::serialized_size(message).expect("Failed to encode message as binary") as usize;
+            let mut payload = Vec::with_capacity(BINARY_MAGIC.len() + 1 + size);
+            payload.extend_from_slice(BINARY_MAGIC);
+            payload.push(BINARY_VERSION);
+            bincode::serialize_into(&mut payload, message).expect("Failed to encode message as binary");
+            payload
+        }
+    }
+}
+
+/// Decodes a payload in either format
+pub fn decode<T: DeserializeOwned>(payload: &[u8]) -> Result<T, Box<dyn Error>> {
+    match payload.strip_prefix(BINARY_MAGIC) {
+        None => Ok(serde_json::from_slice(payload)?),
+        Some(versioned) => match versioned.split_first() {
+            Some((&BINARY_VERSION, message)) => Ok(bincode::deserialize(message)?),
+            Some((version, _)) => {
+                Err(format!("Unsupported binary wire format version {}, expected {}", version, BINARY_VERSION).into())
+            }
+            None => Err("Binary payload without a version".into()),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Message {
+        to_engine_id: String,
+        ids: Vec<u32>,
+        state: Option<(u8, bool)>,
+    }
+
+    fn message() -> Message {
+        Message { to_engine_id: "engine2".to_string(), ids: vec![1, 2, 3], state: Some((4, true)) }
+    }
+
+    #[test]
+    fn should_round_trip_both_formats() {
+        for format in [WireFormat::Json, WireFormat::Binary] {
+            let payload = encode(format, &message());
+            assert_eq!(decode::<Message>(&payload).unwrap(), message());
+        }
+    }
+
+    #[test]
+    fn should_prefix_binary_payloads_with_version_header() {
+        let payload = encode(WireFormat::Binary, &message());
+        assert_eq!(&payload[..4], b"EPB\x03");
+        assert!(payload.len() < encode(WireFormat::Json, &message()).len());
+    }
+
+    #[test]
+    fn should_reject_other_binary_versions() {
+        let mut payload = encode(WireFormat::Binary, &message());
+        payload[3] = BINARY_VERSION + 1;
+        let error = decode::<Message>(&payload).unwrap_err();
+        assert_eq!(error.to_string(), "Unsupported binary wire format version 4, expected 3");
+    }
+}
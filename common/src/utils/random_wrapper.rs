@@ -17,19 +17,101 @@
  *
  */
 
-use rand::rngs::ThreadRng;
-use rand::thread_rng;
+use rand::rngs::{StdRng, ThreadRng};
+use rand::{thread_rng, Error, Rng, RngCore, SeedableRng};
+
+/// Where a `RandomWrapper` draws its numbers from
+pub enum RandomSource {
+    Thread(ThreadRng),
+    Seeded(Box<StdRng>),
+}
+
+impl RngCore for RandomSource {
+    fn next_u32(&mut self) -> u32 {
+        match self {
+            RandomSource::Thread(rng) => rng.next_u32(),
+            RandomSource::Seeded(rng) => rng.next_u32(),
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        match self {
+            RandomSource::Thread(rng) => rng.next_u64(),
+            RandomSource::Seeded(rng) => rng.next_u64(),
+        }
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        match self {
+            RandomSource::Thread(rng) => rng.fill_bytes(dest),
+            RandomSource::Seeded(rng) => rng.fill_bytes(dest),
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        match self {
+            RandomSource::Thread(rng) => rng.try_fill_bytes(dest),
+            RandomSource::Seeded(rng) => rng.try_fill_bytes(dest),
+        }
+    }
+}
 
 pub struct RandomWrapper {
-    rng: ThreadRng,
+    rng: RandomSource,
 }
 
 impl RandomWrapper {
     pub fn new() -> RandomWrapper {
-        RandomWrapper { rng: thread_rng() }
+        RandomWrapper { rng: RandomSource::Thread(thread_rng()) }
+    }
+
+    /// Draws the same numbers for the same seed, e.g. to compare benchmark runs
+    pub fn seeded(seed: u64) -> RandomWrapper {
+        RandomWrapper { rng: RandomSource::Seeded(Box::new(StdRng::seed_from_u64(seed))) }
     }
 
-    pub fn get(&mut self) -> &mut ThreadRng {
+    /// A seed for the generators of the tasks run in parallel from here, if this one is seeded
+    pub fn stream_seed(&mut self) -> Option<u64> {
+        match self.rng {
+            RandomSource::Thread(_) => None,
+            RandomSource::Seeded(ref mut rng) => Some(rng.gen()),
+        }
+    }
+
+    /// The generator for the task identified by `key`. With a stream seed, each task draws the same numbers
+    /// whichever thread runs it.
+    pub fn for_stream(stream_seed: Option<u64>, key: u64) -> RandomWrapper {
+        match stream_seed {
+            Some(seed) => RandomWrapper::seeded(seed ^ key.wrapping_mul(0x9e37_79b9_7f4a_7c15)),
+            None => RandomWrapper::new(),
+        }
+    }
+
+    pub fn get(&mut self) -> &mut RandomSource {
         &mut self.rng
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_draw_the_same_numbers_for_the_same_seed() {
+        let draw = |rng: &mut RandomWrapper| (0..10).map(|_| rng.get().gen_range(0..1000)).collect::<Vec<u32>>();
+
+        assert_eq!(draw(&mut RandomWrapper::seeded(42)), draw(&mut RandomWrapper::seeded(42)));
+        assert_ne!(draw(&mut RandomWrapper::seeded(42)), draw(&mut RandomWrapper::seeded(43)));
+    }
+
+    #[test]
+    fn should_derive_streams_from_a_seeded_generator_only() {
+        let mut seeded = RandomWrapper::seeded(7);
+        let stream_seed = seeded.stream_seed();
+        let first: u64 = RandomWrapper::for_stream(stream_seed, 3).get().gen();
+
+        assert_eq!(first, RandomWrapper::for_stream(stream_seed, 3).get().gen::<u64>());
+        assert_ne!(first, RandomWrapper::for_stream(stream_seed, 4).get().gen::<u64>());
+        assert_eq!(RandomWrapper::new().stream_seed(), None);
+    }
+}
@@ -17,8 +17,13 @@
  *
  */
 
+#[macro_use]
+extern crate log;
+
+mod server;
+
 use clap::Parser;
-use common::config::Config;
+use common::config::{CalibrationConfig, Config};
 use common::disease::Disease;
 use engine::{EngineApp, RunMode};
 use opentelemetry::sdk::trace::{config, Span};
@@ -42,6 +47,38 @@ struct Args {
             distributed across multiple engines.")]
     id: Option<String>,
 
+    #[arg(long, value_name = "FILE")]
+    #[arg(help = "Fit disease parameters to observed data using a calibration config file. \
+            The simulation config is used as the starting point for every run")]
+    calibrate: Option<String>,
+
+    #[arg(long, value_name = "FILE", num_args = 1..)]
+    #[arg(help = "Draw the infected, hospitalized and deceased curves of the given counts CSV files \
+            on one chart instead of running a simulation")]
+    compare: Vec<String>,
+
+    #[arg(long, default_value_t = false, requires = "compare")]
+    #[arg(help = "Draw the compared runs as a median with a 5-95% band instead of one line per run")]
+    ensemble: bool,
+
+    #[arg(long, value_name = "FILE", default_value = "comparison.png", requires = "compare")]
+    #[arg(help = "Name of the comparison chart, written to EPI_OUTPUT_DIR. Use a .svg extension for an SVG")]
+    compare_output: String,
+
+    #[arg(long, value_name = "ADDRESS")]
+    #[arg(help = "Serve an HTTP API on the given address (e.g. 127.0.0.1:8080) to start, follow and cancel \
+            simulations without Kafka. The config argument is ignored")]
+    serve: Option<String>,
+
+    #[arg(long, value_name = "ADDRESS", requires = "id", conflicts_with = "daemon")]
+    #[arg(help = "Join the multi-engine simulation of the orchestrator listening on the given address \
+            (e.g. 10.0.0.1:7000) over TCP instead of Kafka")]
+    orchestrator: Option<String>,
+
+    #[arg(long, value_name = "ADDRESS", default_value = "0.0.0.0:0", requires = "orchestrator")]
+    #[arg(help = "Address to listen on for travellers from the other engines when running over TCP")]
+    listen: String,
+
     #[arg(short, long, default_value_t = 4)]
     #[arg(help = "Number of parallel threads for data parallelization")]
     threads: u32,
@@ -62,6 +99,11 @@ async fn main() {
     env_logger::init();
     let args = Args::parse();
 
+    if !args.compare.is_empty() {
+        EngineApp::compare_runs(&args.compare, args.ensemble, &args.compare_output);
+        return;
+    }
+
     let daemon = args.daemon;
     let has_named_engine = args.id.is_some();
     let default_engine_id = "default_engine".to_string();
@@ -82,12 +124,23 @@ async fn main() {
     let span: Span = _tracer.start("root");
     let cx: Context = Context::current_with_span(span);
 
-    if daemon {
+    if let Some(address) = args.serve {
+        server::serve(&address, number_of_threads).with_context(cx).await.expect("Failed to serve the HTTP API");
+    } else if let Some(orchestrator) = args.orchestrator {
+        EngineApp::start_over_tcp(&engine_id, &orchestrator, &args.listen, number_of_threads).with_context(cx).await;
+    } else if daemon {
         EngineApp::start_in_daemon(&engine_id, &run_mode, disease_handler, number_of_threads).with_context(cx).await;
     } else {
         let default_config_path = "config/default.json".to_string();
         let config_file = args.config.unwrap_or(default_config_path);
         let config = Config::read(&config_file).expect("Failed to read config file");
-        EngineApp::start_standalone(config, &run_mode, disease_handler, number_of_threads).await;
+        match args.calibrate {
+            Some(calibration_file) => {
+                let calibration_config =
+                    CalibrationConfig::read(&calibration_file).expect("Failed to read calibration config file");
+                EngineApp::start_calibration(config, calibration_config, number_of_threads);
+            }
+            None => EngineApp::start_standalone(config, &run_mode, disease_handler, number_of_threads).await,
+        }
     }
 }
@@ -0,0 +1,227 @@
+/*
+ * EpiRust
+ * Copyright (c) 2020  ThoughtWorks, Inc.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ */
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::error::Error;
+use std::fs;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+use axum::extract::{Path, State};
+use axum::http::{header, StatusCode};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::IntoResponse;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use common::config::Config;
+use common::models::custom_types::Hour;
+use engine::{Counts, EngineApp, RunControl, RunEvent};
+use futures::future::ready;
+use futures::stream::{self, Stream, StreamExt};
+use serde::Serialize;
+use tokio_stream::wrappers::BroadcastStream;
+
+struct Run {
+    control: RunControl,
+    total_hours: Hour,
+    output_prefix: String,
+    handle: JoinHandle<()>,
+}
+
+#[derive(Clone)]
+struct AppState {
+    runs: Arc<Mutex<HashMap<u32, Run>>>,
+    last_id: Arc<AtomicU32>,
+    threads: u32,
+}
+
+#[derive(Serialize)]
+struct RunStatus {
+    id: u32,
+    status: &'static str,
+    hour: Hour,
+    total_hours: Hour,
+    progress: f64,
+    counts: Option<Counts>,
+}
+
+impl RunStatus {
+    fn of(id: u32, run: &Run) -> RunStatus {
+        let progress = run.control.progress();
+        let status = match (progress.ended, run.control.is_cancelled()) {
+            (true, true) => "cancelled",
+            (true, false) => "finished",
+            // the simulation thread only stops without ending the run when it panicked
+            (false, _) if run.handle.is_finished() => "failed",
+            (false, true) => "cancelling",
+            (false, false) => "running",
+        };
+        let hour = progress.counts.map_or(0, |counts| counts.get_hour());
+        let fraction = match status {
+            "finished" => 1.0,
+            _ if run.total_hours == 0 => 0.0,
+            _ => hour as f64 / run.total_hours as f64,
+        };
+        RunStatus { id, status, hour, total_hours: run.total_hours, progress: fraction, counts: progress.counts }
+    }
+}
+
+/// Serves the HTTP API for starting, following and cancelling standalone simulations until the process is stopped
+pub async fn serve(address: &str, threads: u32) -> Result<(), Box<dyn Error>> {
+    let state = AppState { runs: Arc::new(Mutex::new(HashMap::new())), last_id: Arc::new(AtomicU32::new(0)), threads };
+    let app = Router::new()
+        .route("/runs", post(start_run).get(list_runs))
+        .route("/runs/:id", get(run_status).delete(cancel_run))
+        .route("/runs/:id/events", get(run_events))
+        .route("/runs/:id/outputs", get(list_outputs))
+        .route("/runs/:id/outputs/:name", get(get_output))
+        .with_state(state);
+    let listener = tokio::net::TcpListener::bind(address).await?;
+    info!("Listening on {}", address);
+    axum::serve(listener, app).await?;
+    Ok(())
+}
+
+async fn start_run(State(state): State<AppState>, Json(mut config): Json<Config>) -> impl IntoResponse {
+    let id = state.last_id.fetch_add(1, Ordering::SeqCst) + 1;
+    // a per run prefix keeps the outputs of concurrent runs apart
+    let output_prefix = format!("{}_run{}", config.get_output_file().unwrap_or_else(|| "simulation".to_string()), id);
+    config.set_output_file(output_prefix.clone());
+    let total_hours = config.get_hours();
+    let control = RunControl::new();
+    let run_control = control.clone();
+    let threads = state.threads;
+    let handle = thread::Builder::new()
+        .name(format!("run-{id}"))
+        .spawn(move || EngineApp::start_controlled(config, run_control, threads))
+        .expect("Failed to start simulation thread");
+    info!("Started run {}", id);
+
+    let run = Run { control, total_hours, output_prefix, handle };
+    let status = RunStatus::of(id, &run);
+    state.runs.lock().unwrap().insert(id, run);
+    (StatusCode::CREATED, Json(status))
+}
+
+async fn list_runs(State(state): State<AppState>) -> Json<Vec<RunStatus>> {
+    let runs = state.runs.lock().unwrap();
+    let mut statuses: Vec<RunStatus> = runs.iter().map(|(id, run)| RunStatus::of(*id, run)).collect();
+    statuses.sort_by_key(|status| status.id);
+    Json(statuses)
+}
+
+async fn run_status(State(state): State<AppState>, Path(id): Path<u32>) -> Result<Json<RunStatus>, StatusCode> {
+    let runs = state.runs.lock().unwrap();
+    let run = runs.get(&id).ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(RunStatus::of(id, run)))
+}
+
+async fn cancel_run(State(state): State<AppState>, Path(id): Path<u32>) -> Result<impl IntoResponse, StatusCode> {
+    let runs = state.runs.lock().unwrap();
+    let run = runs.get(&id).ok_or(StatusCode::NOT_FOUND)?;
+    run.control.cancel();
+    info!("Cancelling run {}", id);
+    Ok((StatusCode::ACCEPTED, Json(RunStatus::of(id, run))))
+}
+
+fn to_sse(event: RunEvent) -> Event {
+    match event {
+        RunEvent::Counts(counts) => Event::default().event("counts").json_data(counts).expect("Failed to serialize counts"),
+        RunEvent::Ended => Event::default().event("ended").data("{}"),
+    }
+}
+
+/// Pushes the counts of every hour as server sent events, followed by an `ended` event
+async fn run_events(
+    State(state): State<AppState>,
+    Path(id): Path<u32>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, StatusCode> {
+    let (receiver, ended) = {
+        let runs = state.runs.lock().unwrap();
+        let run = runs.get(&id).ok_or(StatusCode::NOT_FOUND)?;
+        // subscribe before checking whether the run ended, so the ended event can't be missed
+        let receiver = run.control.subscribe();
+        (receiver, run.control.progress().ended)
+    };
+    let events = if ended {
+        stream::iter(vec![RunEvent::Ended]).boxed()
+    } else {
+        BroadcastStream::new(receiver).filter_map(|event| ready(event.ok())).boxed()
+    };
+    let events = events.scan(false, |ended, event| {
+        if *ended {
+            return ready(None);
+        }
+        *ended = event == RunEvent::Ended;
+        ready(Some(Ok(to_sse(event))))
+    });
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+fn outputs_of(run: &Run) -> Vec<String> {
+    let prefix = format!("{}_", run.output_prefix);
+    let mut outputs: Vec<String> = fs::read_dir(engine::output_dir())
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.file_name().to_string_lossy().to_string())
+                .filter(|name| name.starts_with(&prefix))
+                .collect()
+        })
+        .unwrap_or_default();
+    outputs.sort();
+    outputs
+}
+
+async fn list_outputs(State(state): State<AppState>, Path(id): Path<u32>) -> Result<Json<Vec<String>>, StatusCode> {
+    let runs = state.runs.lock().unwrap();
+    let run = runs.get(&id).ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(outputs_of(run)))
+}
+
+fn content_type_of(name: &str) -> &'static str {
+    match name.rsplit('.').next() {
+        Some("csv") => "text/csv",
+        Some("json") => "application/json",
+        Some("png") => "image/png",
+        Some("gif") => "image/gif",
+        Some("svg") => "image/svg+xml",
+        _ => "application/octet-stream",
+    }
+}
+
+async fn get_output(
+    State(state): State<AppState>,
+    Path((id, name)): Path<(u32, String)>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let outputs = {
+        let runs = state.runs.lock().unwrap();
+        outputs_of(runs.get(&id).ok_or(StatusCode::NOT_FOUND)?)
+    };
+    // only files listed for the run can be read, so the name can't point anywhere else
+    if !outputs.contains(&name) {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    let mut path = engine::output_dir();
+    path.push(&name);
+    let contents = tokio::fs::read(path).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(([(header::CONTENT_TYPE, content_type_of(&name))], contents))
+}
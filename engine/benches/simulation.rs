@@ -0,0 +1,64 @@
+/*
+ * EpiRust
+ * Copyright (c) 2020  ThoughtWorks, Inc.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ */
+
+use common::models::custom_types::Count;
+use common::utils::RandomWrapper;
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use engine::bench::{self, Region};
+
+/// Every run starts from the same population, so runs before and after a change can be compared
+const SEED: u64 = 2020;
+const POPULATIONS: [Count; 2] = [10_000, 100_000];
+
+fn generate_population(c: &mut Criterion) {
+    let mut group = c.benchmark_group("generate_population");
+    group.sample_size(10);
+    for number_of_agents in POPULATIONS {
+        group.throughput(Throughput::Elements(number_of_agents as u64));
+        group.bench_function(BenchmarkId::from_parameter(number_of_agents), |b| {
+            b.iter_batched(
+                || (bench::grid_for(number_of_agents), RandomWrapper::seeded(SEED)),
+                |(mut grid, mut rng)| bench::generate_population(&mut grid, number_of_agents, &mut rng),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+/// A day of simulation per iteration, as its hours do very different amounts of work. The throughput is in hours.
+fn simulate_day(c: &mut Criterion) {
+    let mut group = c.benchmark_group("simulate_day");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(24));
+    for number_of_agents in POPULATIONS {
+        let region = Region::new(number_of_agents, &mut RandomWrapper::seeded(SEED));
+        group.bench_function(BenchmarkId::from_parameter(number_of_agents), |b| {
+            b.iter_batched(
+                || (region.clone(), RandomWrapper::seeded(SEED)),
+                |(mut region, mut rng)| region.simulate(1..25, &mut rng),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, generate_population, simulate_day);
+criterion_main!(benches);
@@ -0,0 +1,78 @@
+/*
+ * EpiRust
+ * Copyright (c) 2020  ThoughtWorks, Inc.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ */
+
+use common::models::custom_types::Size;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use engine::bench;
+use engine::geography::{Point, SpatialIndex};
+use fnv::FnvHashMap;
+
+/// Agents and the side of the grid they are scattered over, about one agent in every five cells
+const POPULATIONS: [(usize, Size); 2] = [(100_000, 700), (1_000_000, 2200)];
+
+fn neighbours(c: &mut Criterion) {
+    let mut group = c.benchmark_group("neighbour_lookup");
+    group.sample_size(10);
+    for (count, grid_size) in POPULATIONS {
+        let citizens = bench::citizens_on_grid(grid_size, count);
+        let hash_map: FnvHashMap<_, _> = citizens.iter().cloned().collect();
+        let mut index = SpatialIndex::new(grid_size, count);
+        citizens.iter().for_each(|(point, citizen)| {
+            index.insert(*point, *citizen);
+        });
+        let occupied = |lookup: &dyn Fn(&Point) -> bool| {
+            citizens.iter().flat_map(|(point, _)| point.neighbor_iterator()).filter(|p| lookup(p)).count()
+        };
+        group.throughput(Throughput::Elements(count as u64));
+        group.bench_function(BenchmarkId::new("fnv_hash_map", count), |b| b.iter(|| occupied(&|p| hash_map.contains_key(p))));
+        group.bench_function(BenchmarkId::new("spatial_index", count), |b| b.iter(|| occupied(&|p| index.contains(p))));
+    }
+    group.finish();
+}
+
+fn rebuild(c: &mut Criterion) {
+    let mut group = c.benchmark_group("rebuild");
+    group.sample_size(10);
+    for (count, grid_size) in POPULATIONS {
+        let citizens = bench::citizens_on_grid(grid_size, count);
+        let mut hash_map = FnvHashMap::with_capacity_and_hasher(count, Default::default());
+        let mut index = SpatialIndex::new(grid_size, count);
+        group.throughput(Throughput::Elements(count as u64));
+        group.bench_function(BenchmarkId::new("fnv_hash_map", count), |b| {
+            b.iter(|| {
+                hash_map.clear();
+                citizens.iter().for_each(|(point, citizen)| {
+                    hash_map.entry(*point).or_insert(*citizen);
+                });
+            })
+        });
+        group.bench_function(BenchmarkId::new("spatial_index", count), |b| {
+            b.iter(|| {
+                index.clear();
+                citizens.iter().for_each(|(point, citizen)| {
+                    index.get_or_insert(*point, *citizen);
+                });
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, neighbours, rebuild);
+criterion_main!(benches);
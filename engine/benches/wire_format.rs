@@ -0,0 +1,66 @@
+/*
+ * EpiRust
+ * Copyright (c) 2020  ThoughtWorks, Inc.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ */
+
+use common::transport::wire_format::{self, WireFormat};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use engine::bench::{self, CommutersByRegion, MigratorsByRegion};
+
+const FORMATS: [(&str, WireFormat); 2] = [("json", WireFormat::Json), ("binary", WireFormat::Binary)];
+const TRAVELLERS: [usize; 2] = [100, 10_000];
+
+fn commuters(c: &mut Criterion) {
+    let mut group = c.benchmark_group("commuters");
+    for count in TRAVELLERS {
+        let commuters = bench::commuters("engine2", count);
+        group.throughput(Throughput::Elements(count as u64));
+        for (name, format) in FORMATS {
+            let payload = wire_format::encode(format, &commuters);
+            println!("{} commuters as {}: {} bytes", count, name, payload.len());
+            group.bench_with_input(BenchmarkId::new(format!("encode_{name}"), count), &commuters, |b, commuters| {
+                b.iter(|| wire_format::encode(format, commuters))
+            });
+            group.bench_with_input(BenchmarkId::new(format!("decode_{name}"), count), &payload, |b, payload| {
+                b.iter(|| wire_format::decode::<CommutersByRegion>(payload).unwrap())
+            });
+        }
+    }
+    group.finish();
+}
+
+fn migrators(c: &mut Criterion) {
+    let mut group = c.benchmark_group("migrators");
+    for count in TRAVELLERS {
+        let migrators = bench::migrators("engine2", count);
+        group.throughput(Throughput::Elements(count as u64));
+        for (name, format) in FORMATS {
+            let payload = wire_format::encode(format, &migrators);
+            println!("{} migrators as {}: {} bytes", count, name, payload.len());
+            group.bench_with_input(BenchmarkId::new(format!("encode_{name}"), count), &migrators, |b, migrators| {
+                b.iter(|| wire_format::encode(format, migrators))
+            });
+            group.bench_with_input(BenchmarkId::new(format!("decode_{name}"), count), &payload, |b, payload| {
+                b.iter(|| wire_format::decode::<MigratorsByRegion>(payload).unwrap())
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, commuters, migrators);
+criterion_main!(benches);
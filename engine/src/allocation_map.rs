@@ -17,51 +17,51 @@
  *
  */
 
-use std::collections::hash_map::{Iter, IterMut};
+use std::collections::HashMap;
 
 use common::config::{Config, TravelPlanConfig};
 use common::models::custom_types::{CoOrdinate, Count, Hour};
 use common::utils::RandomWrapper;
-use fnv::FnvHashMap;
 
 use rand::seq::IteratorRandom;
 use rand::Rng;
 use rayon::prelude::*;
+use uuid::Uuid;
 
 use crate::citizen::Citizen;
 
 use crate::geography::Point;
-use crate::geography::{Area, Grid};
+use crate::geography::{Area, Grid, SpatialIndex};
 use crate::interventions::vaccination::VaccinateIntervention;
 use crate::interventions::Interventions;
 use crate::listeners::listener::Listeners;
 use crate::models::constants;
-use crate::models::events::Counts;
+use crate::models::events::{Counts, InfectionEvent};
 use crate::state_machine::{DiseaseHandler, State};
 use crate::travel::commute::Commuter;
-use crate::travel::migration::Migrator;
+use crate::travel::migration::{Migrator, Trip};
 
 #[derive(Clone)]
 pub struct CitizenLocationMap {
     pub grid: Grid,
-    current_locations: FnvHashMap<Point, Citizen>,
-    upcoming_locations: FnvHashMap<Point, Citizen>,
+    current_locations: SpatialIndex<Citizen>,
+    upcoming_locations: SpatialIndex<Citizen>,
+    // the dead no longer take part in the simulation, and are kept out of the locations, still taking up the cells
+    // they died on
+    deceased: SpatialIndex<Citizen>,
 }
 
 impl CitizenLocationMap {
     pub fn new(grid: Grid, agent_list: &[Citizen], points: &[Point]) -> Self {
         debug!("{} agents and {} starting points", agent_list.len(), points.len());
-        let mut map: FnvHashMap<Point, Citizen> = FnvHashMap::with_capacity_and_hasher(agent_list.len(), Default::default());
+        let mut map = SpatialIndex::new(grid.grid_size, agent_list.len());
         agent_list.iter().enumerate().for_each(|(i, _)| {
             map.insert(points[i], agent_list[i].clone());
         });
 
-        let capacity = grid.grid_size as usize;
-        CitizenLocationMap {
-            grid,
-            current_locations: map,
-            upcoming_locations: FnvHashMap::with_capacity_and_hasher(capacity, Default::default()),
-        }
+        let upcoming_locations = SpatialIndex::new(grid.grid_size, agent_list.len());
+        let deceased = SpatialIndex::new(grid.grid_size, 0);
+        CitizenLocationMap { grid, current_locations: map, upcoming_locations, deceased }
     }
 
     pub fn simulate<T: DiseaseHandler + Sync>(
@@ -78,29 +78,62 @@ impl CitizenLocationMap {
         region_name: &String,
         disease_handler: &T,
     ) {
+        if Self::is_quiet_hour(simulation_hour) {
+            if publish_citizen_state {
+                self.publish_citizen_states(simulation_hour, listeners);
+            }
+            assert_eq!(csv_record.total(), self.current_population());
+            return;
+        }
         csv_record.clear();
-        let updates: Vec<((Point, Point), Citizen, bool)> = self
+        csv_record.update_deceased(self.deceased.len() as Count);
+        if publish_citizen_state {
+            self.deceased.iter().for_each(|(cell, citizen)| listeners.citizen_state_updated(simulation_hour, citizen, cell));
+        }
+        let stream_seed = rng.stream_seed();
+        let moves: Vec<Move> = self
             .par_iter()
             .map(|(cell, agent)| {
-                let mut rng_thread = RandomWrapper::new();
+                let mut rng_thread = RandomWrapper::for_stream(stream_seed, agent.id.as_u64_pair().0);
                 let mut current_agent = *agent;
-                let infection_status = current_agent.state_machine.is_infected();
+                let was_susceptible = agent.state_machine.is_susceptible();
                 let point =
                     current_agent.perform_operation(*cell, simulation_hour, &self.grid, self, &mut rng_thread, disease_handler);
-                ((*cell, point), current_agent, infection_status)
+                let got_infected = was_susceptible && !current_agent.state_machine.is_susceptible();
+                Move { from: *cell, to: point, agent: current_agent, got_infected }
             })
             .collect();
-        updates.iter().for_each(|pair| {
-            let old_cell = pair.0 .0;
-            let new_cell = pair.0 .1;
-            let agent = pair.1;
-            let mut new_location = &new_cell;
-            let agent_at_new_cell = *self.upcoming_locations.entry(new_cell).or_insert(agent);
-            if agent_at_new_cell.id != agent.id {
-                self.upcoming_locations.insert(old_cell, agent);
-                new_location = &old_cell;
+        let settled_moves = resolve_moves(moves, simulation_hour);
+        let counts = settled_moves
+            .par_iter()
+            .fold(
+                || Counts::new(0, 0, 0),
+                |mut counts, settled| {
+                    counts.update_counts(&settled.agent);
+                    counts
+                },
+            )
+            .reduce(
+                || Counts::new(0, 0, 0),
+                |mut counts, other| {
+                    counts.add(&other);
+                    counts
+                },
+            );
+        csv_record.add(&counts);
+        let (deceased, alive): (Vec<&Move>, Vec<&Move>) =
+            settled_moves.par_iter().partition(|settled| settled.agent.state_machine.is_deceased());
+        self.upcoming_locations.par_fill(alive.par_iter().map(|settled| (settled.to, settled.agent)).collect());
+        deceased.iter().for_each(|settled| {
+            self.deceased.insert(settled.to, settled.agent);
+        });
+        settled_moves.iter().for_each(|settled| {
+            let agent = settled.agent;
+            let new_location = &settled.to;
+            if let (true, Some(infector)) = (settled.got_infected, agent.infected_by) {
+                let area = self.grid.area_type_of(new_location);
+                listeners.citizen_got_infected(&InfectionEvent::new(simulation_hour, infector, agent.id, *new_location, area));
             }
-            Counts::update_counts(csv_record, &agent);
             if let Some(travel_plan) = travel_plan_config {
                 let is_migration_enabled = travel_plan.migration.enabled;
                 let is_commute_enabled = travel_plan.commute.enabled;
@@ -128,6 +161,20 @@ impl CitizenLocationMap {
         assert_eq!(csv_record.total(), self.current_population());
     }
 
+    /// The sleeping hours after the first, in which nobody moves and no disease state changes, so the hour only
+    /// repeats the one before it
+    fn is_quiet_hour(simulation_hour: Hour) -> bool {
+        let hour_of_day = simulation_hour % constants::NUMBER_OF_HOURS;
+        hour_of_day > constants::SLEEP_START_TIME && hour_of_day <= constants::SLEEP_END_TIME
+    }
+
+    fn publish_citizen_states(&self, simulation_hour: Hour, listeners: &mut Listeners) {
+        self.current_locations
+            .iter()
+            .chain(self.deceased.iter())
+            .for_each(|(cell, citizen)| listeners.citizen_state_updated(simulation_hour, citizen, cell));
+    }
+
     fn swap(&mut self) {
         self.current_locations.clear();
         std::mem::swap(&mut self.current_locations, &mut self.upcoming_locations);
@@ -141,11 +188,17 @@ impl CitizenLocationMap {
         }
     }
 
-    pub fn goto_hospital(&self, hospital_area: &Area, cell: Point, citizen: &mut Citizen) -> (bool, Point) {
+    pub fn goto_hospital(
+        &self,
+        hospital_area: &Area,
+        cell: Point,
+        citizen: &mut Citizen,
+        rng: &mut RandomWrapper,
+    ) -> (bool, Point) {
         let vacant_hospital_cell = hospital_area.iter().find(|cell| self.is_cell_vacant(cell));
         match vacant_hospital_cell {
             Some(x) => (true, self.move_agent(cell, x)),
-            None => (false, self.move_agent(cell, citizen.home_location.get_random_point(&mut RandomWrapper::new()))),
+            None => (false, self.move_agent(cell, citizen.home_location.get_random_point(rng))),
         }
     }
 
@@ -159,7 +212,7 @@ impl CitizenLocationMap {
     }
 
     pub fn is_cell_vacant(&self, cell: &Point) -> bool {
-        !self.current_locations.contains_key(cell)
+        !self.current_locations.contains(cell) && !self.deceased.contains(cell)
     }
 
     pub fn remove_migrators(&mut self, outgoing: &Vec<(Point, Migrator)>, counts: &mut Counts) {
@@ -168,18 +221,21 @@ impl CitizenLocationMap {
         }
         debug!("Removing {} outgoing travellers", outgoing.len());
         for (point, migrator) in outgoing {
-            CitizenLocationMap::decrement_counts(&migrator.state_machine.state, counts);
+            CitizenLocationMap::decrement_counts(&migrator.citizen.state_machine.state, counts);
             match self.current_locations.remove(point) {
                 None => {
                     panic!(
                         "Trying to remove citizen {:?} from location {:?}, but no citizen is present at this location!",
-                        migrator.id, point
+                        migrator.citizen.id, point
                     )
                 }
                 Some(citizen) => {
-                    self.grid.remove_house_occupant(&citizen.home_location);
-                    if citizen.is_working() {
-                        self.grid.remove_office_occupant(&citizen.work_location);
+                    // residents away on a trip keep their house and office for when they are back
+                    if !self.is_returning_here(migrator) {
+                        self.grid.remove_house_occupant(&citizen.home_location);
+                        if citizen.is_working() {
+                            self.grid.remove_office_occupant(&citizen.work_location);
+                        }
                     }
                 }
             }
@@ -192,12 +248,12 @@ impl CitizenLocationMap {
         }
         debug!("Removing {} outgoing commuters", outgoing.len());
         for (point, commuter) in outgoing {
-            CitizenLocationMap::decrement_counts(&commuter.state_machine.state, counts);
+            CitizenLocationMap::decrement_counts(&commuter.citizen.state_machine.state, counts);
             match self.current_locations.remove(point) {
                 None => {
                     panic!(
                         "Trying to remove citizen {:?} from location {:?}, but no citizen is present at this location!",
-                        commuter.id, point
+                        commuter.citizen.id, point
                     )
                 }
                 Some(citizen) => {
@@ -219,8 +275,21 @@ impl CitizenLocationMap {
         };
 
         for (migrator, migration_location) in incoming.iter().zip(migration_locations) {
+            if let Some(trip) = migrator.trip.filter(|_| self.is_returning_here(migrator)) {
+                let citizen = Citizen::from_migrator(
+                    migrator,
+                    trip.home_location,
+                    trip.work_location,
+                    migration_location,
+                    self.grid.housing_area,
+                );
+                CitizenLocationMap::increment_counts(&citizen.state_machine.state, counts);
+                let result = self.current_locations.insert(migration_location, citizen);
+                assert!(result.is_none());
+                continue;
+            }
             let house = self.grid.choose_house_with_free_space(rng);
-            let office = if migrator.working { self.grid.choose_office_with_free_space(rng) } else { house.clone() };
+            let office = if migrator.citizen.is_working() { self.grid.choose_office_with_free_space(rng) } else { house };
             let citizen = Citizen::from_migrator(
                 migrator,
                 house.clone(),
@@ -229,7 +298,7 @@ impl CitizenLocationMap {
                 self.grid.housing_area.clone(),
             );
             self.grid.add_house_occupant(&house.clone());
-            if migrator.working {
+            if migrator.citizen.is_working() {
                 self.grid.add_office_occupant(&office.clone())
             }
 
@@ -239,6 +308,24 @@ impl CitizenLocationMap {
         }
     }
 
+    /// The visitors due home that are able to travel, as migrators carrying their trips
+    pub fn returning_migrators(&self, due: &HashMap<Uuid, Trip>) -> Vec<(Point, Migrator)> {
+        if due.is_empty() {
+            return Vec::new();
+        }
+        self.current_locations
+            .iter()
+            .filter(|(_, citizen)| citizen.can_move())
+            .filter_map(|(point, citizen)| {
+                due.get(&citizen.id).map(|trip| (*point, Migrator { trip: Some(*trip), ..Migrator::from(citizen) }))
+            })
+            .collect()
+    }
+
+    fn is_returning_here(&self, migrator: &Migrator) -> bool {
+        migrator.trip.is_some_and(|trip| trip.home_location.location_id == self.grid.housing_area.location_id)
+    }
+
     pub fn assimilate_commuters(
         &mut self,
         incoming: &mut Vec<Commuter>,
@@ -340,14 +427,24 @@ impl CitizenLocationMap {
 
     fn select_starting_points(&self, area: &Area, no_of_incoming: usize, rng: &mut RandomWrapper) -> Vec<Point> {
         let empty_spaces = (area.start_offset.x..area.end_offset.x).flat_map(|x| {
-            (area.start_offset.y..area.end_offset.y)
-                .map(move |y| Point { x, y })
-                .filter(|z| !self.current_locations.contains_key(z))
+            (area.start_offset.y..area.end_offset.y).map(move |y| Point { x, y }).filter(|z| self.is_cell_vacant(z))
         });
 
         empty_spaces.choose_multiple(rng.get(), no_of_incoming)
     }
 
+    /// Isolates the arrivals that entry screening quarantined
+    pub fn quarantine(&mut self, ids: &[Uuid]) {
+        self.iter_mut().filter(|(_, r)| ids.contains(&r.id)).for_each(|(_, r)| r.set_isolation(true));
+    }
+
+    /// Ends the quarantine of these citizens, unless a lockdown isolates them
+    pub fn release_from_quarantine(&mut self, ids: &[Uuid], locked_down: bool) {
+        self.iter_mut()
+            .filter(|(_, r)| ids.contains(&r.id) && (!locked_down || r.is_essential_worker()))
+            .for_each(|(_, r)| r.set_isolation(false));
+    }
+
     pub fn lock_city(&mut self, hr: Hour) {
         info!("Locking the city. Hour: {}", hr);
         self.iter_mut().for_each(|(_, r)| {
@@ -388,25 +485,65 @@ impl CitizenLocationMap {
         });
     }
 
+    /// Everyone in the region, the deceased included
     pub fn current_population(&self) -> Count {
-        self.current_locations.len() as Count
+        (self.current_locations.len() + self.deceased.len()) as Count
     }
 
-    pub fn iter_upcoming_locations(&self) -> Iter<'_, Point, Citizen> {
+    pub fn iter_upcoming_locations(&self) -> impl Iterator<Item = (&Point, &Citizen)> {
         self.upcoming_locations.iter()
     }
 
-    pub fn par_iter(&self) -> rayon::collections::hash_map::Iter<Point, Citizen> {
+    pub fn par_iter(&self) -> impl ParallelIterator<Item = (&Point, &Citizen)> {
         self.current_locations.par_iter()
     }
 
-    pub fn iter_mut(&mut self) -> IterMut<'_, Point, Citizen> {
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&Point, &mut Citizen)> {
         self.current_locations.iter_mut()
     }
 }
 
+/// Where an agent was at the start of the hour, and where it is headed
+struct Move {
+    from: Point,
+    to: Point,
+    agent: Citizen,
+    got_infected: bool,
+}
+
+impl Move {
+    /// Agents staying put come first. The others are ranked by a hash of their id and the hour, so a contested cell
+    /// goes to the same agent whichever thread decided its move first, but no agent is favoured over the others from
+    /// one hour to the next.
+    fn priority(&self, hour: Hour) -> (bool, u64, u128) {
+        let id = self.agent.id.as_u128();
+        let seed = (id as u64) ^ ((id >> 64) as u64) ^ (hour as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+        (self.from != self.to, mix(seed), id)
+    }
+}
+
+/// The splitmix64 finaliser, which spreads similar inputs across the whole range of outputs
+fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Gives every contested cell to the agent with the highest priority, and keeps the other contenders where they
+/// were. The moves come back ordered by the cell each agent asked for, whichever order they were decided in.
+fn resolve_moves(mut moves: Vec<Move>, hour: Hour) -> Vec<Move> {
+    moves.par_sort_unstable_by_key(|m| ((m.to.x, m.to.y), m.priority(hour)));
+    let won: Vec<bool> = (0..moves.len()).into_par_iter().map(|i| i == 0 || moves[i - 1].to != moves[i].to).collect();
+    moves.par_iter_mut().zip(won).filter(|(_, won)| !won).for_each(|(m, _)| m.to = m.from);
+    moves
+}
+
 #[cfg(test)]
 mod tests {
+    use common::disease::Disease;
+    use rayon::ThreadPoolBuilder;
+
+    use crate::bench::Region;
     use crate::citizen::WorkStatus;
     use crate::geography::define_geography;
 
@@ -466,7 +603,7 @@ mod tests {
         let grid = define_geography(5, engine_id.clone());
         let map = CitizenLocationMap::new(grid, &agents, &points);
         let hospital = Area::new(&engine_id, Point::new(2, 2), Point::new(4, 4));
-        let result = map.goto_hospital(&hospital, points[0], &mut citizen1);
+        let result = map.goto_hospital(&hospital, points[0], &mut citizen1, &mut rng);
 
         assert!(result.0);
         assert_eq!(result.1, Point::new(2, 2));
@@ -490,12 +627,48 @@ mod tests {
         let map = CitizenLocationMap::new(grid, &agents, &points);
         let hospital = Area::new(&engine_id, Point::new(0, 0), Point::new(1, 1));
 
-        let result = map.goto_hospital(&hospital, points[0], &mut citizen1.clone());
+        let result = map.goto_hospital(&hospital, points[0], &mut citizen1.clone(), &mut rng);
 
         assert!(!result.0);
         assert!(citizen1.home_location.contains(&result.1));
     }
 
+    #[test]
+    fn should_take_back_residents_returning_from_a_trip() {
+        let mut rng = RandomWrapper::new();
+        let mut map = before_each();
+        let point = Point::new(0, 1);
+        let citizen = *map.get_agent_for(&point).unwrap();
+        let trip = Trip { home_location: citizen.home_location, work_location: citizen.work_location, return_at: 48 };
+        let leaving = vec![(point, Migrator { trip: Some(trip), ..Migrator::from(&citizen) })];
+        let mut counts = Counts::new(2, 0, 0);
+
+        map.remove_migrators(&leaving, &mut counts);
+        assert_eq!(map.current_population(), 1);
+        map.assimilate_migrators(&mut vec![leaving[0].1], &mut counts, &mut rng);
+
+        let (_, returned) = map.iter_mut().find(|(_, returned)| returned.id == citizen.id).unwrap();
+        assert_eq!(returned.home_location, citizen.home_location);
+        assert_eq!(returned.work_location, citizen.work_location);
+        assert_eq!(map.current_population(), 2);
+    }
+
+    #[test]
+    fn should_send_home_the_visitors_that_are_due() {
+        let map = before_each();
+        let citizen = *map.get_agent_for(&Point::new(0, 1)).unwrap();
+        let home = Area::new(&"engine2".to_string(), Point::new(0, 0), Point::new(2, 2));
+        let trip = Trip { home_location: home, work_location: home, return_at: 48 };
+        let due = HashMap::from([(citizen.id, trip)]);
+
+        let returning = map.returning_migrators(&due);
+
+        assert_eq!(returning.len(), 1);
+        assert_eq!(returning[0].1.citizen.id, citizen.id);
+        assert_eq!(returning[0].1.trip, Some(trip));
+        assert!(map.returning_migrators(&HashMap::new()).is_empty());
+    }
+
     #[test]
     fn should_return_true_when_point_is_in_grid() {
         let map = before_each();
@@ -513,4 +686,158 @@ mod tests {
             assert!(!map.is_point_in_grid(&point))
         }
     }
+
+    fn moving(citizen: &Citizen, from: Point, to: Point) -> Move {
+        Move { from, to, agent: *citizen, got_infected: false }
+    }
+
+    #[test]
+    fn should_give_a_contested_cell_to_one_agent_and_keep_the_others_in_place() {
+        let mut rng = RandomWrapper::new();
+        let area = Area::new(&"engine1".to_string(), Point::new(0, 0), Point::new(5, 5));
+        let citizens: Vec<Citizen> =
+            (0..4).map(|_| Citizen::new(area, area, Point::new(0, 0), false, WorkStatus::NA, &mut rng)).collect();
+        let target = Point::new(3, 3);
+        let moves =
+            |order: &[usize]| order.iter().map(|&i| moving(&citizens[i], Point::new(i as i32, 0), target)).collect::<Vec<Move>>();
+
+        let settled = resolve_moves(moves(&[0, 1, 2, 3]), 7);
+        let winners: Vec<Uuid> = settled.iter().filter(|m| m.to == target).map(|m| m.agent.id).collect();
+        assert_eq!(winners.len(), 1);
+        settled.iter().filter(|m| m.to != target).for_each(|m| assert_eq!(m.to, m.from));
+
+        let reordered = resolve_moves(moves(&[3, 1, 0, 2]), 7);
+        let winner = reordered.iter().find(|m| m.to == target).unwrap();
+        assert_eq!(winner.agent.id, winners[0]);
+    }
+
+    #[test]
+    fn should_keep_agents_staying_put_on_their_cell() {
+        let mut rng = RandomWrapper::new();
+        let area = Area::new(&"engine1".to_string(), Point::new(0, 0), Point::new(5, 5));
+        let staying = Citizen::new(area, area, Point::new(0, 0), false, WorkStatus::NA, &mut rng);
+        let cell = Point::new(2, 2);
+        let moves = (0..10)
+            .map(|i| {
+                let citizen = Citizen::new(area, area, Point::new(0, 0), false, WorkStatus::NA, &mut rng);
+                moving(&citizen, Point::new(i, 5), cell)
+            })
+            .chain(std::iter::once(moving(&staying, cell, cell)))
+            .collect();
+
+        let settled = resolve_moves(moves, 3);
+
+        let winner = settled.iter().find(|m| m.to == cell).unwrap();
+        assert_eq!(winner.agent.id, staying.id);
+        assert_eq!(settled.iter().filter(|m| m.to == cell).count(), 1);
+    }
+
+    #[test]
+    fn should_not_favour_the_same_agent_every_hour() {
+        let mut rng = RandomWrapper::new();
+        let area = Area::new(&"engine1".to_string(), Point::new(0, 0), Point::new(5, 5));
+        let citizens: Vec<Citizen> =
+            (0..2).map(|_| Citizen::new(area, area, Point::new(0, 0), false, WorkStatus::NA, &mut rng)).collect();
+        let target = Point::new(1, 1);
+
+        let first_wins = (0..200)
+            .filter(|hour| {
+                let moves = citizens.iter().enumerate().map(|(i, c)| moving(c, Point::new(i as i32, 0), target)).collect();
+                resolve_moves(moves, *hour).iter().any(|m| m.to == target && m.agent.id == citizens[0].id)
+            })
+            .count();
+
+        assert!((50..150).contains(&first_wins), "first agent won {} of 200 hours", first_wins);
+    }
+
+    fn simulate_hour(map: &mut CitizenLocationMap, counts: &mut Counts, hour: Hour) {
+        let disease = Disease::new(5, 10, 40, 9, 12, 0.5, 1.0, 0.02, 0.3, 0.3, 24, 24);
+        let mut listeners = Listeners::from(vec![]);
+        let region = "engine1".to_string();
+        map.simulate(
+            counts,
+            hour,
+            &mut listeners,
+            &mut RandomWrapper::new(),
+            0.0,
+            &mut Vec::new(),
+            &mut Vec::new(),
+            false,
+            None,
+            &region,
+            &disease,
+        );
+    }
+
+    #[test]
+    fn should_keep_counting_the_deceased_outside_the_locations() {
+        let mut map = before_each();
+        let mut counts = Counts::new(2, 0, 0);
+        let (_, dead) = map.iter_mut().find(|(point, _)| **point == Point::new(0, 1)).unwrap();
+        dead.state_machine.state = State::Deceased;
+
+        simulate_hour(&mut map, &mut counts, 8);
+
+        assert!(!map.is_cell_vacant(&Point::new(0, 1)));
+        assert_eq!(map.iter_mut().count(), 1);
+        assert_eq!(map.current_population(), 2);
+        assert_eq!(counts.get_deceased(), 1);
+        assert_eq!(counts.get_susceptible(), 1);
+
+        simulate_hour(&mut map, &mut counts, 9);
+        assert_eq!(counts.get_deceased(), 1);
+        assert_eq!(counts.total(), 2);
+    }
+
+    #[test]
+    fn should_leave_everyone_in_place_in_quiet_hours() {
+        let mut map = before_each();
+        let mut counts = Counts::new(2, 0, 0);
+        simulate_hour(&mut map, &mut counts, 1);
+        let before: Vec<(Point, Uuid)> = map.iter_mut().map(|(point, citizen)| (*point, citizen.id)).collect();
+
+        (2..=6).for_each(|hour| simulate_hour(&mut map, &mut counts, hour));
+
+        let after: Vec<(Point, Uuid)> = map.iter_mut().map(|(point, citizen)| (*point, citizen.id)).collect();
+        assert_eq!(before, after);
+        assert_eq!(counts.get_susceptible(), 2);
+        assert!(!CitizenLocationMap::is_quiet_hour(1));
+        assert!(CitizenLocationMap::is_quiet_hour(24 + 4));
+        assert!(!CitizenLocationMap::is_quiet_hour(7));
+    }
+
+    /// The counts after a day simulated from `region` with `seed`, and where everyone alive ended up
+    fn simulate_day(region: &Region, seed: u64) -> (Counts, Vec<(Uuid, Point)>) {
+        let mut region = region.clone();
+        region.simulate(1..25, &mut RandomWrapper::seeded(seed));
+        let mut positions: Vec<(Uuid, Point)> = region.map.iter_mut().map(|(point, citizen)| (citizen.id, *point)).collect();
+        positions.sort_by_key(|(id, _)| *id);
+        (region.counts, positions)
+    }
+
+    #[test]
+    fn should_simulate_the_same_day_from_the_same_seed() {
+        let region = Region::new(2000, &mut RandomWrapper::seeded(7));
+
+        let first = simulate_day(&region, 11);
+        let second = simulate_day(&region, 11);
+
+        assert_eq!(first.0.get_hour(), 24);
+        assert_eq!(first, second);
+        assert_ne!(first.1, simulate_day(&region, 12).1);
+    }
+
+    #[test]
+    fn should_simulate_the_same_day_on_any_number_of_threads() {
+        let region = Region::new(2000, &mut RandomWrapper::seeded(7));
+        let on_threads = |threads: usize| {
+            let pool = ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+            pool.install(|| simulate_day(&region, 11))
+        };
+
+        let single = on_threads(1);
+
+        assert_eq!(on_threads(4), single);
+        assert_eq!(on_threads(4), single);
+    }
 }
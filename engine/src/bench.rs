@@ -0,0 +1,163 @@
+/*
+ * EpiRust
+ * Copyright (c) 2020  ThoughtWorks, Inc.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ */
+
+//! Fixtures for the benchmarks under `benches/`, not part of the engine's API
+
+use std::ops::Range;
+
+use common::config::{AutoPopulation, StartingInfections};
+use common::disease::Disease;
+use common::models::custom_types::{CoOrdinate, Count, Day, Hour, Size};
+use common::utils::RandomWrapper;
+use rand::seq::index;
+use uuid::Uuid;
+
+use crate::allocation_map::CitizenLocationMap;
+use crate::citizen::{Citizen, TravellingCitizen, WorkStatus};
+use crate::disease_state_machine::DiseaseStateMachine;
+use crate::geography::{define_geography, Area, Grid, Point};
+use crate::listeners::listener::Listeners;
+use crate::models::constants;
+use crate::models::events::Counts;
+use crate::state_machine::{Severity, State};
+use crate::travel::commute::Commuter;
+use crate::travel::migration::Migrator;
+use crate::utils::counts_at_start;
+
+pub use crate::travel::commute::CommutersByRegion;
+pub use crate::travel::migration::MigratorsByRegion;
+
+fn state_machine(i: usize) -> DiseaseStateMachine {
+    let state = match i % 4 {
+        0 => State::Susceptible,
+        1 => State::Exposed { at_hour: i as Hour },
+        2 => State::Infected { infection_day: (i % 20) as Day, severity: Severity::Mild },
+        _ => State::Recovered,
+    };
+    DiseaseStateMachine { state }
+}
+
+fn traveller(i: usize, home_location: Area, work_location: Area) -> TravellingCitizen {
+    TravellingCitizen {
+        id: Uuid::from_u128(i as u128),
+        immunity: (i % 3) as i32,
+        home_location,
+        work_location,
+        vaccinated: i.is_multiple_of(5),
+        uses_public_transport: i.is_multiple_of(2),
+        hospitalized: false,
+        isolated: false,
+        work_status: if !i.is_multiple_of(3) { WorkStatus::Normal } else { WorkStatus::NA },
+        work_quarantined: false,
+        infected_by: None,
+        state_machine: state_machine(i),
+    }
+}
+
+/// `count` commuters on their way home from `to_engine_id`'s offices, in a mix of disease states
+pub fn commuters(to_engine_id: &str, count: usize) -> CommutersByRegion {
+    let commuters = (0..count)
+        .map(|i| {
+            let x = (i % 100) as i32;
+            let home = Area::new(&"engine1".to_string(), Point::new(x, 0), Point::new(x + 1, 1));
+            let work = Area::new(&to_engine_id.to_string(), Point::new(x, 50), Point::new(x + 10, 60));
+            Commuter { citizen: TravellingCitizen { work_status: WorkStatus::Normal, ..traveller(i, home, work) } }
+        })
+        .collect();
+    CommutersByRegion::new(to_engine_id.to_string(), commuters)
+}
+
+/// `count` migrators moving to `to_engine_id`, in a mix of disease states
+pub fn migrators(to_engine_id: &str, count: usize) -> MigratorsByRegion {
+    let area = Area::new(&"engine1".to_string(), Point::new(0, 0), Point::new(100, 100));
+    let migrators = (0..count).map(|i| Migrator { citizen: traveller(i, area, area), trip: None }).collect();
+    MigratorsByRegion::new(to_engine_id, migrators)
+}
+
+/// `count` citizens scattered over distinct cells of a `grid_size` grid
+pub fn citizens_on_grid(grid_size: Size, count: usize) -> Vec<(Point, Citizen)> {
+    let mut rng = RandomWrapper::seeded(count as u64);
+    let side = grid_size as usize;
+    let area = Area::new(&"engine1".to_string(), Point::new(0, 0), Point::new(10, 10));
+    index::sample(rng.get(), side * side, count)
+        .into_iter()
+        .map(|cell| {
+            let point = Point::new((cell % side) as CoOrdinate, (cell / side) as CoOrdinate);
+            (point, Citizen::new(area, area, point, false, WorkStatus::Normal, &mut rng))
+        })
+        .collect()
+}
+
+/// The grid of a region of `number_of_agents`, as crowded as the default config's 10000 agents on a 250 grid
+pub fn grid_for(number_of_agents: Count) -> Grid {
+    let grid_size = (2.5 * (number_of_agents as f64).sqrt()).ceil() as Size;
+    define_geography(grid_size, "engine1".to_string())
+}
+
+/// `number_of_agents` generated the way an engine with an `AutoPopulation` does, with a few of them infected
+pub fn generate_population(grid: &mut Grid, number_of_agents: Count, rng: &mut RandomWrapper) -> (Vec<Point>, Vec<Citizen>) {
+    let auto_population = AutoPopulation { number_of_agents, public_transport_percentage: 0.2, working_percentage: 0.7 };
+    grid.generate_population(&auto_population, &starting_infections(number_of_agents), rng, &None, "engine1".to_string())
+}
+
+fn starting_infections(number_of_agents: Count) -> StartingInfections {
+    StartingInfections::new(0, 0, (number_of_agents / 1000).max(1), 0)
+}
+
+/// A region whose hours can be simulated over and over from the same starting point
+#[derive(Clone)]
+pub struct Region {
+    pub(crate) map: CitizenLocationMap,
+    pub(crate) counts: Counts,
+    disease: Disease,
+}
+
+impl Region {
+    pub fn new(number_of_agents: Count, rng: &mut RandomWrapper) -> Region {
+        let mut grid = grid_for(number_of_agents);
+        let (points, citizens) = generate_population(&mut grid, number_of_agents, rng);
+        grid.resize_hospital(citizens.len() as i32, constants::HOSPITAL_STAFF_PERCENTAGE, 0.003, "engine1".to_string());
+        Region {
+            map: CitizenLocationMap::new(grid, &citizens, &points),
+            counts: counts_at_start(number_of_agents, &starting_infections(number_of_agents)),
+            disease: Disease::new(5, 6, 26, 9, 12, 0.25, 0.25, 0.035, 0.3, 0.3, 48, 48),
+        }
+    }
+
+    pub fn simulate(&mut self, hours: Range<Hour>, rng: &mut RandomWrapper) {
+        let mut listeners = Listeners::from(Vec::new());
+        let region = "engine1".to_string();
+        for hour in hours {
+            self.counts.increment_hour();
+            self.map.simulate(
+                &mut self.counts,
+                hour,
+                &mut listeners,
+                rng,
+                0.0,
+                &mut Vec::new(),
+                &mut Vec::new(),
+                false,
+                None,
+                &region,
+                &self.disease,
+            );
+        }
+    }
+}
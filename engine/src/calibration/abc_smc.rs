@@ -0,0 +1,129 @@
+/*
+ * EpiRust
+ * Copyright (c) 2020  ThoughtWorks, Inc.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ */
+
+use common::utils::RandomWrapper;
+use rand::distributions::WeightedIndex;
+use rand::prelude::Distribution;
+use rand::Rng;
+
+/// Sequential Monte Carlo approximate Bayesian computation over the unit hypercube with a uniform prior.
+/// Every generation proposes `particles` candidates and keeps the closest `acceptance_quantile` fraction,
+/// so the tolerance shrinks adaptively from one generation to the next.
+pub struct AbcSmc {
+    particles: u32,
+    generations: u32,
+    acceptance_quantile: f64,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Particle {
+    pub point: Vec<f64>,
+    pub distance: f64,
+    pub weight: f64,
+}
+
+const MIN_KERNEL_WIDTH: f64 = 1e-3;
+
+impl AbcSmc {
+    pub fn new(particles: u32, generations: u32, acceptance_quantile: f64) -> AbcSmc {
+        assert!(particles > 0, "ABC-SMC needs at least one particle");
+        assert!(acceptance_quantile > 0.0 && acceptance_quantile <= 1.0, "ABC-SMC acceptance quantile should be in (0, 1]");
+        AbcSmc { particles, generations, acceptance_quantile }
+    }
+
+    pub fn run(&self, dimensions: usize, rng: &mut RandomWrapper, mut distance: impl FnMut(&[f64]) -> f64) -> Vec<Particle> {
+        let mut population: Vec<Particle> = Vec::new();
+        for generation in 0..self.generations.max(1) {
+            let mut proposals: Vec<Particle> = (0..self.particles)
+                .map(|_| {
+                    let (point, weight) = if generation == 0 {
+                        ((0..dimensions).map(|_| rng.get().gen::<f64>()).collect(), 1.0)
+                    } else {
+                        self.perturb(&population, rng)
+                    };
+                    let distance = distance(&point);
+                    Particle { point, distance, weight }
+                })
+                .collect();
+
+            proposals.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+            let accepted = ((self.particles as f64 * self.acceptance_quantile).ceil() as usize).max(1);
+            proposals.truncate(accepted);
+            normalise(&mut proposals);
+            debug!(
+                "ABC-SMC generation {} accepted {} particles with tolerance {}",
+                generation,
+                proposals.len(),
+                proposals.last().unwrap().distance
+            );
+            population = proposals;
+        }
+        population
+    }
+
+    fn perturb(&self, population: &[Particle], rng: &mut RandomWrapper) -> (Vec<f64>, f64) {
+        let dimensions = population[0].point.len();
+        let widths: Vec<f64> = (0..dimensions)
+            .map(|i| {
+                let min = population.iter().map(|p| p.point[i]).fold(f64::INFINITY, f64::min);
+                let max = population.iter().map(|p| p.point[i]).fold(f64::NEG_INFINITY, f64::max);
+                ((max - min) / 2.0).max(MIN_KERNEL_WIDTH)
+            })
+            .collect();
+        let index = WeightedIndex::new(population.iter().map(|p| p.weight)).unwrap();
+
+        let point = loop {
+            let parent = &population[index.sample(rng.get())];
+            let candidate: Vec<f64> = parent.point.iter().zip(&widths).map(|(x, w)| x + rng.get().gen_range(-w..=*w)).collect();
+            if candidate.iter().all(|x| (0.0..=1.0).contains(x)) {
+                break candidate;
+            }
+        };
+
+        let kernel_density: f64 = population
+            .iter()
+            .filter(|p| p.point.iter().zip(&point).zip(&widths).all(|((x, y), w)| (x - y).abs() <= *w))
+            .map(|p| p.weight / widths.iter().map(|w| 2.0 * w).product::<f64>())
+            .sum();
+        (point, 1.0 / kernel_density)
+    }
+}
+
+fn normalise(particles: &mut [Particle]) {
+    let total: f64 = particles.iter().map(|p| p.weight).sum();
+    particles.iter_mut().for_each(|p| p.weight /= total);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_concentrate_posterior_around_target() {
+        let abc = AbcSmc::new(200, 5, 0.2);
+        let mut rng = RandomWrapper::new();
+
+        let posterior = abc.run(1, &mut rng, |p| (p[0] - 0.3).abs());
+
+        assert_eq!(posterior.len(), 40);
+        let mean: f64 = posterior.iter().map(|p| p.point[0] * p.weight).sum();
+        assert!((mean - 0.3).abs() < 0.05);
+        assert!((posterior.iter().map(|p| p.weight).sum::<f64>() - 1.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,146 @@
+/*
+ * EpiRust
+ * Copyright (c) 2020  ThoughtWorks, Inc.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ */
+
+use common::models::custom_types::Day;
+
+use crate::calibration::observed_data::ObservedDay;
+use crate::models::constants;
+use crate::models::events::Counts;
+
+/// Simulated values per day, comparable with the reported series. Cases are new exposures during
+/// the day, deaths are new deaths during the day and hospitalisations are the occupancy at the end of the day.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct DailySeries {
+    pub cases: Vec<f64>,
+    pub deaths: Vec<f64>,
+    pub hospitalisations: Vec<f64>,
+}
+
+impl DailySeries {
+    pub fn from_counts(start: Counts, hourly: &[Counts], days: Day) -> DailySeries {
+        let mut series = DailySeries::default();
+        let mut previous = start;
+        let mut iter = hourly.iter().filter(|c| c.get_hour() % constants::HOURS_IN_A_DAY == 0).peekable();
+        for day in 1..=days {
+            // the simulation stops early once there are no active cases, so the last counts hold from then on
+            let current = match iter.peek() {
+                Some(c) if c.get_hour() == day * constants::HOURS_IN_A_DAY => *iter.next().unwrap(),
+                _ => *hourly.last().unwrap_or(&previous),
+            };
+            series.cases.push(previous.get_susceptible().saturating_sub(current.get_susceptible()) as f64);
+            series.deaths.push(current.get_deceased().saturating_sub(previous.get_deceased()) as f64);
+            series.hospitalisations.push(current.get_hospitalized() as f64);
+            previous = current;
+        }
+        series
+    }
+
+    pub fn mean(all: &[DailySeries]) -> DailySeries {
+        let n = all.len() as f64;
+        let average = |select: fn(&DailySeries) -> &Vec<f64>| -> Vec<f64> {
+            let len = all.iter().map(|s| select(s).len()).max().unwrap_or(0);
+            (0..len).map(|i| all.iter().map(|s| select(s)[i]).sum::<f64>() / n).collect()
+        };
+        DailySeries {
+            cases: average(|s| &s.cases),
+            deaths: average(|s| &s.deaths),
+            hospitalisations: average(|s| &s.hospitalisations),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize, Clone, Copy)]
+pub struct SeriesFit {
+    pub rmse: f64,
+    pub mae: f64,
+    pub normalised_rmse: f64,
+}
+
+#[derive(Debug, PartialEq, Serialize, Clone, Copy)]
+pub struct GoodnessOfFit {
+    pub cases: Option<SeriesFit>,
+    pub deaths: Option<SeriesFit>,
+    pub hospitalisations: Option<SeriesFit>,
+    pub distance: f64,
+}
+
+impl GoodnessOfFit {
+    pub fn calculate(observed: &[ObservedDay], simulated: &DailySeries) -> GoodnessOfFit {
+        let cases = Self::series_fit(observed, |d| d.cases, &simulated.cases);
+        let deaths = Self::series_fit(observed, |d| d.deaths, &simulated.deaths);
+        let hospitalisations = Self::series_fit(observed, |d| d.hospitalisations, &simulated.hospitalisations);
+        let distance = [cases, deaths, hospitalisations].iter().flatten().map(|f| f.normalised_rmse).sum();
+        GoodnessOfFit { cases, deaths, hospitalisations, distance }
+    }
+
+    fn series_fit(observed: &[ObservedDay], select: fn(&ObservedDay) -> Option<u32>, simulated: &[f64]) -> Option<SeriesFit> {
+        let pairs: Vec<(f64, f64)> =
+            observed.iter().filter_map(|d| select(d).map(|value| (value as f64, simulated[(d.day - 1) as usize]))).collect();
+        if pairs.is_empty() {
+            return None;
+        }
+        let n = pairs.len() as f64;
+        let rmse = (pairs.iter().map(|(o, s)| (o - s).powi(2)).sum::<f64>() / n).sqrt();
+        let mae = pairs.iter().map(|(o, s)| (o - s).abs()).sum::<f64>() / n;
+        let observed_mean = pairs.iter().map(|(o, _)| o).sum::<f64>() / n;
+        // keeps series of different magnitudes comparable when summed into a single distance
+        let normalised_rmse = rmse / observed_mean.max(1.0);
+        Some(SeriesFit { rmse, mae, normalised_rmse })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_build_daily_series_from_hourly_counts() {
+        let start = Counts::new_test(0, 98, 0, 2, 0, 0, 0);
+        let hourly = vec![
+            Counts::new_test(12, 96, 2, 2, 0, 0, 0),
+            Counts::new_test(24, 95, 3, 2, 0, 0, 0),
+            Counts::new_test(36, 93, 3, 3, 1, 0, 0),
+            Counts::new_test(48, 90, 4, 3, 2, 0, 1),
+        ];
+
+        let series = DailySeries::from_counts(start, &hourly, 3);
+
+        assert_eq!(series.cases, vec![3.0, 5.0, 0.0]);
+        assert_eq!(series.deaths, vec![0.0, 1.0, 0.0]);
+        assert_eq!(series.hospitalisations, vec![0.0, 2.0, 2.0]);
+    }
+
+    #[test]
+    fn should_calculate_goodness_of_fit_only_for_observed_values() {
+        let observed = vec![
+            ObservedDay { day: 1, cases: Some(2), deaths: None, hospitalisations: None },
+            ObservedDay { day: 2, cases: Some(4), deaths: None, hospitalisations: None },
+        ];
+        let simulated = DailySeries { cases: vec![4.0, 4.0], deaths: vec![1.0, 1.0], hospitalisations: vec![0.0, 0.0] };
+
+        let fit = GoodnessOfFit::calculate(&observed, &simulated);
+
+        let cases = fit.cases.unwrap();
+        assert!((cases.rmse - 2.0_f64.sqrt()).abs() < 1e-9);
+        assert_eq!(cases.mae, 1.0);
+        assert!((fit.distance - 2.0_f64.sqrt() / 3.0).abs() < 1e-9);
+        assert_eq!(fit.deaths, None);
+        assert_eq!(fit.hospitalisations, None);
+    }
+}
@@ -0,0 +1,231 @@
+/*
+ * EpiRust
+ * Copyright (c) 2020  ThoughtWorks, Inc.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ */
+
+mod abc_smc;
+mod goodness_of_fit;
+mod nelder_mead;
+mod observed_data;
+
+use std::error::Error;
+use std::fs::File;
+
+use common::config::{CalibrationConfig, CalibrationMethod, Config};
+use common::disease::{Disease, DiseaseParameter};
+use common::models::custom_types::{Day, Percentage};
+use common::utils::RandomWrapper;
+use csv::Writer;
+
+use crate::calibration::abc_smc::{AbcSmc, Particle};
+use crate::calibration::goodness_of_fit::{DailySeries, GoodnessOfFit};
+use crate::calibration::nelder_mead::NelderMead;
+use crate::calibration::observed_data::ObservedDay;
+use crate::engine_app::STANDALONE_SIM_ID;
+use crate::epidemiology_simulation::Epidemiology;
+use crate::listeners::counts_recorder::CountsRecorder;
+use crate::listeners::listener::Listeners;
+use crate::models::constants;
+use crate::run_mode::RunMode;
+use crate::utils::environment;
+use crate::utils::util::output_file_format;
+
+#[derive(Serialize, Debug, Clone, Copy)]
+struct FittedParameter {
+    name: DiseaseParameter,
+    value: Percentage,
+}
+
+#[derive(Serialize)]
+struct CalibrationReport {
+    method: CalibrationMethod,
+    evaluations: u32,
+    best_fit: Vec<FittedParameter>,
+    goodness_of_fit: GoodnessOfFit,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    posterior_mean: Option<Vec<FittedParameter>>,
+}
+
+struct Evaluation {
+    parameters: Vec<FittedParameter>,
+    fit: GoodnessOfFit,
+    series: DailySeries,
+}
+
+/// Fits disease parameters by repeatedly running a standalone simulation and comparing it with observed data
+pub struct Calibration {
+    config: Config,
+    calibration_config: CalibrationConfig,
+    observed: Vec<ObservedDay>,
+    days: Day,
+    threads: u32,
+    evaluations: u32,
+    best: Option<Evaluation>,
+}
+
+impl Calibration {
+    pub fn new(config: Config, calibration_config: CalibrationConfig, threads: u32) -> Result<Calibration, Box<dyn Error>> {
+        let observed = observed_data::read(&calibration_config.observed_data)?;
+        let days = observed.last().unwrap().day;
+        if days * constants::HOURS_IN_A_DAY >= config.get_hours() {
+            return Err(format!(
+                "Observed data covers {} days but the simulation only runs for {} hours",
+                days,
+                config.get_hours()
+            )
+            .into());
+        }
+        Ok(Calibration { config, calibration_config, observed, days, threads, evaluations: 0, best: None })
+    }
+
+    pub fn run(&mut self) -> Result<(), Box<dyn Error>> {
+        let dimensions = self.calibration_config.parameters.len();
+        let posterior = match self.calibration_config.method {
+            CalibrationMethod::NelderMead { max_iterations, tolerance } => {
+                let minimum = NelderMead::new(max_iterations, tolerance).minimize(&vec![0.5; dimensions], |p| self.evaluate(p));
+                info!("Nelder-Mead finished after {} iterations with distance {}", minimum.iterations, minimum.value);
+                None
+            }
+            CalibrationMethod::AbcSmc { particles, generations, acceptance_quantile } => {
+                let mut rng = RandomWrapper::new();
+                let abc = AbcSmc::new(particles, generations, acceptance_quantile);
+                Some(abc.run(dimensions, &mut rng, |p| self.evaluate(p)))
+            }
+        };
+        self.write_reports(posterior)
+    }
+
+    fn evaluate(&mut self, point: &[f64]) -> f64 {
+        let parameters = self.to_parameters(point);
+        let mut disease = self.config.get_disease();
+        parameters.iter().for_each(|p| disease.set_parameter(p.name, p.value));
+
+        let runs: Vec<DailySeries> =
+            (0..self.calibration_config.runs_per_evaluation.max(1)).map(|_| self.simulate(disease)).collect();
+        let series = DailySeries::mean(&runs);
+        let fit = GoodnessOfFit::calculate(&self.observed, &series);
+        self.evaluations += 1;
+        debug!("Calibration evaluation {}: {:?} distance {}", self.evaluations, parameters, fit.distance);
+
+        let distance = fit.distance;
+        if self.best.as_ref().is_none_or(|best| distance < best.fit.distance) {
+            info!("New best fit {:?} with distance {}", parameters, distance);
+            self.best = Some(Evaluation { parameters, fit, series });
+        }
+        distance
+    }
+
+    fn simulate(&self, disease: Disease) -> DailySeries {
+        let mut config = self.config.clone();
+        config.set_disease(disease);
+        let run_mode = RunMode::Standalone;
+        let mut epidemiology = Epidemiology::new(config, None, STANDALONE_SIM_ID.to_string(), &run_mode, disease);
+        epidemiology.set_listeners(Listeners::from(vec![Box::new(CountsRecorder::new())]));
+        let start = epidemiology.get_counts();
+        // a standalone run never waits on anything, so it can be driven to completion in place
+        futures::executor::block_on(epidemiology.run(&run_mode, self.threads));
+
+        let recorder = epidemiology.get_listeners().find::<CountsRecorder>().unwrap();
+        DailySeries::from_counts(start, recorder.get_counts(), self.days)
+    }
+
+    fn to_parameters(&self, point: &[f64]) -> Vec<FittedParameter> {
+        self.calibration_config
+            .parameters
+            .iter()
+            .zip(point)
+            .map(|(p, x)| FittedParameter { name: p.name, value: p.min + x * (p.max - p.min) })
+            .collect()
+    }
+
+    fn write_reports(&self, posterior: Option<Vec<Particle>>) -> Result<(), Box<dyn Error>> {
+        let best = self.best.as_ref().ok_or("Calibration did not evaluate any parameters")?;
+        let prefix = match &self.calibration_config.output_file {
+            Some(name) => name.clone(),
+            None => output_file_format(&self.config, &RunMode::Standalone),
+        };
+
+        let posterior_mean = posterior.as_ref().map(|particles| {
+            let dimensions = self.calibration_config.parameters.len();
+            let mean: Vec<f64> = (0..dimensions).map(|i| particles.iter().map(|p| p.point[i] * p.weight).sum::<f64>()).collect();
+            self.to_parameters(&mean)
+        });
+        let report = CalibrationReport {
+            method: self.calibration_config.method,
+            evaluations: self.evaluations,
+            best_fit: best.parameters.clone(),
+            goodness_of_fit: best.fit,
+            posterior_mean,
+        };
+        let file = File::create(environment::output_dir().join(format!("{prefix}_calibration.json")))?;
+        serde_json::to_writer_pretty(file, &report)?;
+
+        self.write_fit(best, &format!("{prefix}_calibration_fit.csv"))?;
+        if let Some(particles) = posterior {
+            self.write_posterior(&particles, &format!("{prefix}_calibration_posterior.csv"))?;
+        }
+        Ok(())
+    }
+
+    fn write_fit(&self, best: &Evaluation, file_name: &str) -> Result<(), Box<dyn Error>> {
+        let mut wtr = Writer::from_path(environment::output_dir().join(file_name))?;
+        wtr.write_record([
+            "day",
+            "observed_cases",
+            "simulated_cases",
+            "observed_deaths",
+            "simulated_deaths",
+            "observed_hospitalisations",
+            "simulated_hospitalisations",
+        ])?;
+        let optional = |value: Option<u32>| value.map(|v| v.to_string()).unwrap_or_default();
+        for day in 1..=self.days {
+            let observed = self.observed.iter().find(|d| d.day == day);
+            let index = (day - 1) as usize;
+            wtr.write_record([
+                day.to_string(),
+                optional(observed.and_then(|d| d.cases)),
+                best.series.cases[index].to_string(),
+                optional(observed.and_then(|d| d.deaths)),
+                best.series.deaths[index].to_string(),
+                optional(observed.and_then(|d| d.hospitalisations)),
+                best.series.hospitalisations[index].to_string(),
+            ])?;
+        }
+        wtr.flush()?;
+        Ok(())
+    }
+
+    fn write_posterior(&self, particles: &[Particle], file_name: &str) -> Result<(), Box<dyn Error>> {
+        let mut wtr = Writer::from_path(environment::output_dir().join(file_name))?;
+        let mut header: Vec<String> = self
+            .calibration_config
+            .parameters
+            .iter()
+            .map(|p| serde_json::to_value(p.name).unwrap().as_str().unwrap().to_string())
+            .collect();
+        header.extend(["distance".to_string(), "weight".to_string()]);
+        wtr.write_record(&header)?;
+        for particle in particles {
+            let mut row: Vec<String> = self.to_parameters(&particle.point).iter().map(|p| p.value.to_string()).collect();
+            row.extend([particle.distance.to_string(), particle.weight.to_string()]);
+            wtr.write_record(&row)?;
+        }
+        wtr.flush()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,131 @@
+/*
+ * EpiRust
+ * Copyright (c) 2020  ThoughtWorks, Inc.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ */
+
+/// Downhill simplex minimisation over the unit hypercube. Points are clamped to [0, 1] in every dimension,
+/// callers map them onto the actual parameter bounds.
+pub struct NelderMead {
+    max_iterations: u32,
+    tolerance: f64,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Minimum {
+    pub point: Vec<f64>,
+    pub value: f64,
+    pub iterations: u32,
+}
+
+const REFLECTION: f64 = 1.0;
+const EXPANSION: f64 = 2.0;
+const CONTRACTION: f64 = 0.5;
+const SHRINK: f64 = 0.5;
+const INITIAL_STEP: f64 = 0.25;
+
+impl NelderMead {
+    pub fn new(max_iterations: u32, tolerance: f64) -> NelderMead {
+        NelderMead { max_iterations, tolerance }
+    }
+
+    pub fn minimize(&self, start: &[f64], mut f: impl FnMut(&[f64]) -> f64) -> Minimum {
+        let dimensions = start.len();
+        let mut simplex: Vec<(Vec<f64>, f64)> = Vec::with_capacity(dimensions + 1);
+        let start = clamp(start.to_vec());
+        simplex.push((start.clone(), f(&start)));
+        for i in 0..dimensions {
+            let mut vertex = start.clone();
+            vertex[i] = if vertex[i] + INITIAL_STEP <= 1.0 { vertex[i] + INITIAL_STEP } else { vertex[i] - INITIAL_STEP };
+            let value = f(&vertex);
+            simplex.push((vertex, value));
+        }
+
+        let mut iterations = 0;
+        while iterations < self.max_iterations {
+            simplex.sort_by(|a, b| a.1.total_cmp(&b.1));
+            let best = simplex[0].1;
+            let worst = simplex[dimensions].1;
+            if (worst - best).abs() <= self.tolerance {
+                break;
+            }
+            iterations += 1;
+
+            let centroid: Vec<f64> = (0..dimensions)
+                .map(|i| simplex[..dimensions].iter().map(|(p, _)| p[i]).sum::<f64>() / dimensions as f64)
+                .collect();
+            let towards = |coefficient: f64, from: &[f64]| -> Vec<f64> {
+                clamp(centroid.iter().zip(from).map(|(c, p)| c + coefficient * (p - c)).collect())
+            };
+
+            let reflected = towards(-REFLECTION, &simplex[dimensions].0);
+            let reflected_value = f(&reflected);
+            if reflected_value < best {
+                let expanded = towards(-EXPANSION, &simplex[dimensions].0);
+                let expanded_value = f(&expanded);
+                simplex[dimensions] =
+                    if expanded_value < reflected_value { (expanded, expanded_value) } else { (reflected, reflected_value) };
+            } else if reflected_value < simplex[dimensions - 1].1 {
+                simplex[dimensions] = (reflected, reflected_value);
+            } else {
+                let contracted = towards(CONTRACTION, &simplex[dimensions].0);
+                let contracted_value = f(&contracted);
+                if contracted_value < worst {
+                    simplex[dimensions] = (contracted, contracted_value);
+                } else {
+                    let best_point = simplex[0].0.clone();
+                    for (point, value) in simplex.iter_mut().skip(1) {
+                        *point = point.iter().zip(&best_point).map(|(p, b)| b + SHRINK * (p - b)).collect();
+                        *value = f(point);
+                    }
+                }
+            }
+        }
+
+        simplex.sort_by(|a, b| a.1.total_cmp(&b.1));
+        let (point, value) = simplex.swap_remove(0);
+        Minimum { point, value, iterations }
+    }
+}
+
+fn clamp(point: Vec<f64>) -> Vec<f64> {
+    point.into_iter().map(|x| x.clamp(0.0, 1.0)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_find_minimum_of_quadratic() {
+        let nelder_mead = NelderMead::new(200, 1e-10);
+
+        let minimum = nelder_mead.minimize(&[0.5, 0.5], |p| (p[0] - 0.2).powi(2) + (p[1] - 0.7).powi(2));
+
+        assert!((minimum.point[0] - 0.2).abs() < 1e-3);
+        assert!((minimum.point[1] - 0.7).abs() < 1e-3);
+        assert!(minimum.value < 1e-6);
+    }
+
+    #[test]
+    fn should_stay_within_unit_bounds() {
+        let nelder_mead = NelderMead::new(200, 1e-10);
+
+        let minimum = nelder_mead.minimize(&[0.5], |p| -p[0]);
+
+        assert_eq!(minimum.point, vec![1.0]);
+    }
+}
@@ -0,0 +1,66 @@
+/*
+ * EpiRust
+ * Copyright (c) 2020  ThoughtWorks, Inc.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ */
+
+use std::error::Error;
+
+use common::models::custom_types::{Count, Day};
+use csv::Reader;
+
+/// A single day of reported data. Any of the series may be left blank.
+#[derive(Debug, PartialEq, Deserialize, Clone)]
+pub struct ObservedDay {
+    pub day: Day,
+    #[serde(default)]
+    pub cases: Option<Count>,
+    #[serde(default)]
+    pub deaths: Option<Count>,
+    #[serde(default)]
+    pub hospitalisations: Option<Count>,
+}
+
+pub fn read(file_path: &str) -> Result<Vec<ObservedDay>, Box<dyn Error>> {
+    let mut reader = Reader::from_path(file_path)?;
+    let mut observed = Vec::new();
+    for row in reader.deserialize() {
+        let day: ObservedDay = row?;
+        if day.day == 0 {
+            return Err("Observed days are numbered from 1".into());
+        }
+        observed.push(day);
+    }
+    if observed.is_empty() {
+        return Err(format!("No observations found in {file_path}").into());
+    }
+    observed.sort_by_key(|d| d.day);
+    Ok(observed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_read_observed_data_with_missing_values() {
+        let observed = read("config/test/observed_cases.csv").unwrap();
+
+        assert_eq!(observed.len(), 5);
+        assert_eq!(observed[0], ObservedDay { day: 1, cases: Some(2), deaths: Some(0), hospitalisations: Some(0) });
+        assert_eq!(observed[3], ObservedDay { day: 4, cases: Some(6), deaths: None, hospitalisations: Some(1) });
+    }
+}
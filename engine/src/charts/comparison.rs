@@ -0,0 +1,138 @@
+/*
+ * EpiRust
+ * Copyright (c) 2020  ThoughtWorks, Inc.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ */
+
+use std::error::Error;
+use std::path::Path;
+
+use common::config::ChartFormat;
+use csv::Reader;
+use plotters::prelude::*;
+
+use crate::charts::{self, day_of, Band, CountOf, Panel, Series};
+use crate::models::events::Counts;
+
+/// Counts of a single run, labelled by the name of the file they were read from
+pub struct Run {
+    pub label: String,
+    pub counts: Vec<Counts>,
+}
+
+impl Run {
+    pub fn read(file_path: &str) -> Result<Run, Box<dyn Error>> {
+        let mut reader = Reader::from_path(file_path)?;
+        let counts = reader.deserialize().collect::<Result<Vec<Counts>, _>>()?;
+        if counts.is_empty() {
+            return Err(format!("No counts found in {file_path}").into());
+        }
+        let label = Path::new(file_path).file_stem().map_or(file_path.to_string(), |s| s.to_string_lossy().to_string());
+        Ok(Run { label, counts })
+    }
+}
+
+const COMPARED: [(&str, CountOf, RGBColor); 3] = [
+    ("Infected", Counts::get_infected, charts::INFECTED),
+    ("Hospitalized", Counts::get_hospitalized, charts::HOSPITALIZED),
+    ("Deceased", Counts::get_deceased, charts::DECEASED),
+];
+
+/// One line per run for each of the compared states
+fn overlay(runs: &[Run]) -> Vec<Panel> {
+    COMPARED
+        .iter()
+        .map(|(title, value, _)| {
+            let lines = runs
+                .iter()
+                .enumerate()
+                .map(|(i, run)| {
+                    let (r, g, b) = Palette99::pick(i).rgb();
+                    charts::series_of(&run.label, RGBColor(r, g, b), &run.counts, *value)
+                })
+                .collect();
+            Panel::new(title, lines)
+        })
+        .collect()
+}
+
+fn percentile(sorted: &[f64], quantile: f64) -> f64 {
+    sorted[((sorted.len() - 1) as f64 * quantile).round() as usize]
+}
+
+/// The median of all runs with a band from the 5th to the 95th percentile, over the hours every run reached
+fn ensemble(runs: &[Run]) -> Vec<Panel> {
+    let hours = runs.iter().map(|run| run.counts.len()).min().unwrap_or(0);
+    COMPARED
+        .iter()
+        .map(|(title, value, color)| {
+            let (mut lower, mut median, mut upper) = (Vec::new(), Vec::new(), Vec::new());
+            for i in 0..hours {
+                let mut values: Vec<f64> = runs.iter().map(|run| value(&run.counts[i]) as f64).collect();
+                values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+                let day = day_of(runs[0].counts[i].get_hour() as f64);
+                lower.push((day, percentile(&values, 0.05)));
+                median.push((day, percentile(&values, 0.5)));
+                upper.push((day, percentile(&values, 0.95)));
+            }
+            let label = format!("median of {} runs (5-95% band)", runs.len());
+            let mut panel = Panel::new(title, vec![Series { label, color: *color, points: median }]);
+            panel.bands.push(Band { color: *color, lower, upper });
+            panel
+        })
+        .collect()
+}
+
+/// Draws the infected, hospitalized and deceased curves of several runs, either overlaid or as an ensemble band
+pub fn compare(counts_files: &[String], as_ensemble: bool, output_path: &Path) -> Result<(), Box<dyn Error>> {
+    let runs = counts_files.iter().map(|file| Run::read(file)).collect::<Result<Vec<Run>, _>>()?;
+    let panels = if as_ensemble { ensemble(&runs) } else { overlay(&runs) };
+    let format = ChartFormat::of_file(&output_path.to_string_lossy());
+    charts::draw(output_path, format, &panels, &[])
+}
+
+#[cfg(test)]
+mod tests {
+    use common::models::custom_types::Count;
+
+    use super::*;
+
+    fn run(infected: &[Count]) -> Run {
+        let counts = infected.iter().enumerate().map(|(i, inf)| Counts::new_test(i as u32 + 1, 0, 0, *inf, 0, 0, 0)).collect();
+        Run { label: "run".to_string(), counts }
+    }
+
+    #[test]
+    fn should_compute_ensemble_band_over_common_hours() {
+        let runs: Vec<Run> = vec![run(&[1, 10, 7]), run(&[3, 30]), run(&[2, 20, 9])];
+
+        let panels = ensemble(&runs);
+
+        let infected = &panels[0];
+        assert_eq!(infected.lines[0].points, vec![(day_of(1.0), 2.0), (day_of(2.0), 20.0)]);
+        assert_eq!(infected.bands[0].lower, vec![(day_of(1.0), 1.0), (day_of(2.0), 10.0)]);
+        assert_eq!(infected.bands[0].upper, vec![(day_of(1.0), 3.0), (day_of(2.0), 30.0)]);
+    }
+
+    #[test]
+    fn should_overlay_one_line_per_run() {
+        let panels = overlay(&[run(&[1, 2]), run(&[3, 4])]);
+
+        assert_eq!(panels.len(), 3);
+        assert_eq!(panels[0].lines.len(), 2);
+        assert_eq!(panels[0].lines[1].points[1], (day_of(2.0), 4.0));
+    }
+}
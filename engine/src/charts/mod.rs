@@ -0,0 +1,170 @@
+/*
+ * EpiRust
+ * Copyright (c) 2020  ThoughtWorks, Inc.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ */
+
+pub mod comparison;
+
+use std::error::Error;
+use std::path::Path;
+
+use common::config::ChartFormat;
+use common::models::custom_types::Count;
+use plotters::coord::Shift;
+use plotters::prelude::*;
+
+use crate::models::constants;
+use crate::models::events::Counts;
+
+const PANEL_WIDTH: u32 = 1000;
+const PANEL_HEIGHT: u32 = 400;
+
+pub const SUSCEPTIBLE: RGBColor = RGBColor(31, 119, 180);
+pub const EXPOSED: RGBColor = RGBColor(255, 127, 14);
+pub const INFECTED: RGBColor = RGBColor(214, 39, 40);
+pub const HOSPITALIZED: RGBColor = RGBColor(148, 103, 189);
+pub const RECOVERED: RGBColor = RGBColor(44, 160, 44);
+pub const DECEASED: RGBColor = RGBColor(60, 60, 60);
+
+/// Picks one of the disease states out of the counts
+pub type CountOf = fn(&Counts) -> Count;
+
+/// A line plotted against the simulation day
+pub struct Series {
+    pub label: String,
+    pub color: RGBColor,
+    pub points: Vec<(f64, f64)>,
+}
+
+/// A shaded region between two lines, e.g. the spread of an ensemble of runs
+pub struct Band {
+    pub color: RGBColor,
+    pub lower: Vec<(f64, f64)>,
+    pub upper: Vec<(f64, f64)>,
+}
+
+pub struct Panel {
+    pub title: String,
+    pub lines: Vec<Series>,
+    pub bands: Vec<Band>,
+}
+
+/// A vertical line with a label, drawn across every panel
+pub struct Marker {
+    pub day: f64,
+    pub label: String,
+}
+
+impl Panel {
+    pub fn new(title: &str, lines: Vec<Series>) -> Panel {
+        Panel { title: title.to_string(), lines, bands: Vec::new() }
+    }
+
+    fn points(&self) -> impl Iterator<Item = &(f64, f64)> {
+        self.lines.iter().flat_map(|s| s.points.iter()).chain(self.bands.iter().flat_map(|b| b.upper.iter()))
+    }
+}
+
+pub fn day_of(hour: f64) -> f64 {
+    hour / constants::HOURS_IN_A_DAY as f64
+}
+
+pub fn series_of(label: &str, color: RGBColor, counts: &[Counts], value: CountOf) -> Series {
+    let points = counts.iter().map(|c| (day_of(c.get_hour() as f64), value(c) as f64)).collect();
+    Series { label: label.to_string(), color, points }
+}
+
+/// Draws the panels stacked on top of each other, with the image size growing with the number of panels
+pub fn draw(output_path: &Path, format: ChartFormat, panels: &[Panel], markers: &[Marker]) -> Result<(), Box<dyn Error>> {
+    let size = (PANEL_WIDTH, PANEL_HEIGHT * panels.len().max(1) as u32);
+    match format {
+        ChartFormat::Png => draw_panels(BitMapBackend::new(output_path, size).into_drawing_area(), panels, markers),
+        ChartFormat::Svg => draw_panels(SVGBackend::new(output_path, size).into_drawing_area(), panels, markers),
+    }
+}
+
+fn draw_panels<DB: DrawingBackend>(
+    root: DrawingArea<DB, Shift>,
+    panels: &[Panel],
+    markers: &[Marker],
+) -> Result<(), Box<dyn Error>>
+where
+    DB::ErrorType: 'static,
+{
+    root.fill(&WHITE)?;
+    for (area, panel) in root.split_evenly((panels.len().max(1), 1)).iter().zip(panels) {
+        let max_day = panel.points().map(|p| p.0).chain(markers.iter().map(|m| m.day)).fold(1.0, f64::max);
+        let max_value = panel.points().map(|p| p.1).fold(1.0, f64::max) * 1.1;
+        let mut chart = ChartBuilder::on(area)
+            .caption(&panel.title, ("sans-serif", 22))
+            .margin(10)
+            .x_label_area_size(40)
+            .y_label_area_size(70)
+            .build_cartesian_2d(0f64..max_day, 0f64..max_value)?;
+        chart.configure_mesh().x_desc("Day").y_desc("Citizens").draw()?;
+
+        for band in &panel.bands {
+            let outline: Vec<(f64, f64)> = band.upper.iter().chain(band.lower.iter().rev()).copied().collect();
+            chart.draw_series(std::iter::once(Polygon::new(outline, band.color.mix(0.25).filled())))?;
+        }
+        for (i, marker) in markers.iter().enumerate() {
+            let line = vec![(marker.day, 0.0), (marker.day, max_value)];
+            chart.draw_series(LineSeries::new(line, BLACK.mix(0.4).stroke_width(1)))?;
+            // labels of interventions close to each other are staggered so they don't overlap
+            let label_height = max_value * (0.97 - 0.06 * (i % 3) as f64);
+            let label = Text::new(marker.label.clone(), (marker.day, label_height), ("sans-serif", 14).into_font());
+            chart.draw_series(std::iter::once(label))?;
+        }
+        for series in &panel.lines {
+            let color = series.color;
+            chart
+                .draw_series(LineSeries::new(series.points.iter().copied(), color.stroke_width(2)))?
+                .label(&series.label)
+                .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color.stroke_width(2)));
+        }
+        if !panel.lines.is_empty() {
+            chart
+                .configure_series_labels()
+                .position(SeriesLabelPosition::UpperRight)
+                .border_style(BLACK)
+                .background_style(WHITE.mix(0.8))
+                .draw()?;
+        }
+    }
+    root.present()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sinks::test_utils::temp_prefix;
+
+    #[test]
+    fn should_draw_png_and_svg_charts() {
+        let counts = vec![Counts::new_test(24, 9, 1, 0, 0, 0, 0), Counts::new_test(48, 7, 1, 2, 0, 0, 0)];
+        let panels = vec![Panel::new("Infections", vec![series_of("Infected", INFECTED, &counts, Counts::get_infected)])];
+        let markers = vec![Marker { day: 1.5, label: "lockdown start".to_string() }];
+
+        for format in [ChartFormat::Png, ChartFormat::Svg] {
+            let file_name = format!("{}.{}", temp_prefix("chart"), format.extension());
+            draw(Path::new(&file_name), format, &panels, &markers).unwrap();
+            assert!(std::fs::metadata(&file_name).unwrap().len() > 0);
+            std::fs::remove_file(file_name).unwrap();
+        }
+    }
+}
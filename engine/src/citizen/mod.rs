@@ -20,11 +20,13 @@
 mod citizen_data;
 mod citizen_factory;
 mod population_record;
+mod travelling_citizen;
 mod work_status;
 
 pub use citizen_data::CitizensData;
 pub use citizen_factory::{citizen_factory, set_starting_infections};
 pub use population_record::PopulationRecord;
+pub use travelling_citizen::TravellingCitizen;
 pub use work_status::WorkStatus;
 
 use common::config::TravelPlanConfig;
@@ -60,6 +62,8 @@ pub struct Citizen {
     pub current_area: Area,
     work_status: WorkStatus,
     pub work_quarantined: bool,
+    #[serde(default)]
+    pub infected_by: Option<Uuid>,
 }
 
 impl Citizen {
@@ -72,7 +76,7 @@ impl Citizen {
         rng: &mut RandomWrapper,
     ) -> Citizen {
         Citizen::new_with_id(
-            Uuid::new_v4(),
+            Citizen::generate_id(rng),
             home_location,
             work_location,
             transport_location,
@@ -107,6 +111,7 @@ impl Citizen {
             current_area: home_location,
             work_status,
             work_quarantined: false,
+            infected_by: None,
         }
     }
 
@@ -117,39 +122,12 @@ impl Citizen {
         transport_location: Point,
         current_area: Area,
     ) -> Citizen {
-        Citizen {
-            id: migrator.id,
-            immunity: migrator.immunity,
-            home_location,
-            work_location,
-            vaccinated: migrator.vaccinated,
-            uses_public_transport: migrator.uses_public_transport,
-            hospitalized: false,
-            transport_location,
-            state_machine: migrator.state_machine,
-            isolated: false,
-            current_area,
-            work_status: WorkStatus::NA,
-            work_quarantined: false,
-        }
+        migrator.citizen.arrive(home_location, work_location, transport_location, current_area)
     }
 
     pub fn from_commuter(commuter: &Commuter, transport_location: Point, current_area: Area, work_area: Option<Area>) -> Citizen {
-        Citizen {
-            id: commuter.id,
-            immunity: commuter.immunity,
-            home_location: commuter.home_location.clone(),
-            work_location: if work_area == None { commuter.work_location.clone() } else { work_area.unwrap() },
-            vaccinated: commuter.vaccinated,
-            uses_public_transport: commuter.uses_public_transport,
-            hospitalized: false,
-            transport_location,
-            state_machine: commuter.state_machine,
-            isolated: false,
-            current_area,
-            work_status: WorkStatus::Normal,
-            work_quarantined: false,
-        }
+        let work_location = work_area.unwrap_or(commuter.citizen.work_location);
+        commuter.citizen.arrive(commuter.citizen.home_location, work_location, transport_location, current_area)
     }
 
     pub fn from_record(
@@ -163,7 +141,7 @@ impl Citizen {
         let work_status = Citizen::derive_work_status(record.working, rng);
 
         Citizen {
-            id: Uuid::new_v4(),
+            id: Citizen::generate_id(rng),
             immunity: disease_randomness_factor,
             home_location: home_location.clone(),
             work_location,
@@ -176,6 +154,7 @@ impl Citizen {
             current_area: home_location,
             work_status,
             work_quarantined: false,
+            infected_by: None,
         }
     }
 
@@ -204,7 +183,16 @@ impl Citizen {
         rng: &mut RandomWrapper,
         disease_handler: &T,
     ) {
-        self.state_machine.state = self.state_machine.next(sim_hr, cell, self, map, rng, disease_handler);
+        let (state, infector) = self.state_machine.next(sim_hr, cell, self, map, rng, disease_handler);
+        self.state_machine.state = state;
+        if infector.is_some() {
+            self.infected_by = infector;
+        }
+    }
+
+    /// A version 4 id drawn from `rng`, so a seeded generator gives the same ids every run
+    fn generate_id(rng: &mut RandomWrapper) -> Uuid {
+        uuid::Builder::from_random_bytes(rng.get().gen()).into_uuid()
     }
 
     fn generate_disease_randomness_factor(rng: &mut RandomWrapper) -> i32 {
@@ -239,7 +227,7 @@ impl Citizen {
         match current_hour {
             constants::ROUTINE_START_TIME => {
                 self.state_machine.increment_infection_day();
-                new_cell = self.hospitalize(cell, &grid.hospital_area, map, disease_handler);
+                new_cell = self.hospitalize(cell, &grid.hospital_area, map, rng, disease_handler);
             }
             constants::SLEEP_START_TIME..=constants::SLEEP_END_TIME => {
                 if !self.is_hospital_staff() {
@@ -353,11 +341,12 @@ impl Citizen {
         cell: Point,
         hospital: &Area,
         map: &CitizenLocationMap,
+        rng: &mut RandomWrapper,
         disease_handler: &T,
     ) -> Point {
         let mut new_cell = cell;
         if !self.hospitalized && self.state_machine.is_to_be_hospitalized(self.immunity, disease_handler) {
-            let (is_hospitalized, new_location) = CitizenLocationMap::goto_hospital(map, hospital, cell, self);
+            let (is_hospitalized, new_location) = CitizenLocationMap::goto_hospital(map, hospital, cell, self, rng);
             new_cell = new_location;
             self.hospitalized = is_hospitalized;
         }
@@ -449,7 +438,7 @@ impl Citizen {
         WorkStatus::NA
     }
 
-    fn can_move(&self) -> bool {
+    pub fn can_move(&self) -> bool {
         !(self.state_machine.is_symptomatic() || self.hospitalized || self.state_machine.is_deceased() || self.isolated)
     }
 
@@ -0,0 +1,122 @@
+/*
+ * EpiRust
+ * Copyright (c) 2020  ThoughtWorks, Inc.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ */
+
+use uuid::Uuid;
+
+use crate::citizen::{Citizen, WorkStatus};
+use crate::disease_state_machine::DiseaseStateMachine;
+use crate::geography::{Area, Point};
+
+/// Everything about a citizen that goes with it to another engine. Only where it is in the region it arrives in
+/// (`transport_location` and `current_area`) is left behind, and it is given a new home and workplace there unless
+/// it arrives where they are.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+pub struct TravellingCitizen {
+    pub id: Uuid,
+    pub immunity: i32,
+    pub home_location: Area,
+    pub work_location: Area,
+    pub vaccinated: bool,
+    pub uses_public_transport: bool,
+    pub hospitalized: bool,
+    pub isolated: bool,
+    pub work_status: WorkStatus,
+    pub work_quarantined: bool,
+    pub infected_by: Option<Uuid>,
+    pub state_machine: DiseaseStateMachine,
+}
+
+impl TravellingCitizen {
+    pub fn is_working(&self) -> bool {
+        !matches!(self.work_status, WorkStatus::NA)
+    }
+
+    /// The citizen back in a region, living and working in the given areas
+    pub fn arrive(&self, home_location: Area, work_location: Area, transport_location: Point, current_area: Area) -> Citizen {
+        Citizen {
+            id: self.id,
+            immunity: self.immunity,
+            home_location,
+            work_location,
+            vaccinated: self.vaccinated,
+            uses_public_transport: self.uses_public_transport,
+            hospitalized: self.hospitalized,
+            transport_location,
+            state_machine: self.state_machine,
+            isolated: self.isolated,
+            current_area,
+            work_status: self.work_status,
+            work_quarantined: self.work_quarantined,
+            infected_by: self.infected_by,
+        }
+    }
+}
+
+impl From<&Citizen> for TravellingCitizen {
+    fn from(citizen: &Citizen) -> Self {
+        TravellingCitizen {
+            id: citizen.id,
+            immunity: citizen.immunity,
+            home_location: citizen.home_location,
+            work_location: citizen.work_location,
+            vaccinated: citizen.vaccinated,
+            uses_public_transport: citizen.uses_public_transport,
+            hospitalized: citizen.hospitalized,
+            isolated: citizen.isolated,
+            work_status: citizen.work_status,
+            work_quarantined: citizen.work_quarantined,
+            infected_by: citizen.infected_by,
+            state_machine: citizen.state_machine,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::transport::wire_format::{self, WireFormat};
+    use common::utils::RandomWrapper;
+
+    use super::*;
+
+    #[test]
+    fn should_keep_every_attribute_across_engines() {
+        let mut rng = RandomWrapper::new();
+        let home = Area::new(&"engine1".to_string(), Point::new(0, 0), Point::new(2, 2));
+        let work = Area::new(&"engine1".to_string(), Point::new(5, 0), Point::new(6, 2));
+        let mut citizen = Citizen::new(home, work, Point::new(1, 1), true, WorkStatus::Essential, &mut rng);
+        citizen.set_vaccination(true);
+        citizen.work_quarantined = true;
+        citizen.infected_by = Some(Uuid::new_v4());
+
+        for format in [WireFormat::Json, WireFormat::Binary] {
+            let payload = wire_format::encode(format, &TravellingCitizen::from(&citizen));
+            let traveller: TravellingCitizen = wire_format::decode(&payload).unwrap();
+            let arrived = traveller.arrive(home, work, Point::new(3, 3), home);
+
+            assert_eq!(arrived.id, citizen.id);
+            assert_eq!(arrived.get_immunity(), citizen.get_immunity());
+            assert!(arrived.is_vaccinated());
+            assert!(arrived.is_essential_worker());
+            assert!(arrived.work_quarantined);
+            assert_eq!(arrived.infected_by, citizen.infected_by);
+            assert_eq!(arrived.state_machine, citizen.state_machine);
+            assert_eq!(arrived.transport_location, Point::new(3, 3));
+        }
+    }
+}
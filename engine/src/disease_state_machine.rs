@@ -18,6 +18,7 @@
  */
 use common::models::custom_types::{Day, Hour};
 use common::utils::RandomWrapper;
+use uuid::Uuid;
 
 use crate::allocation_map::CitizenLocationMap;
 use crate::citizen::Citizen;
@@ -58,14 +59,17 @@ impl DiseaseStateMachine {
         map: &CitizenLocationMap,
         rng: &mut RandomWrapper,
         disease_handler: &T,
-    ) -> State {
+    ) -> (State, Option<Uuid>) {
         match self.state {
-            State::Susceptible => disease_handler.on_susceptible(sim_hr, cell, citizen, map, rng).unwrap_or(self.state),
-            State::Exposed { at_hour } => disease_handler.on_exposed(at_hour, sim_hr, rng).unwrap_or(self.state),
+            State::Susceptible => match disease_handler.on_susceptible(sim_hr, cell, citizen, map, rng) {
+                Some((state, infector)) => (state, Some(infector)),
+                None => (self.state, None),
+            },
+            State::Exposed { at_hour } => (disease_handler.on_exposed(at_hour, sim_hr, rng).unwrap_or(self.state), None),
             State::Infected { infection_day, severity } => {
-                disease_handler.on_infected(sim_hr, infection_day, severity, rng).unwrap_or(self.state)
+                (disease_handler.on_infected(sim_hr, infection_day, severity, rng).unwrap_or(self.state), None)
             }
-            state => state,
+            state => (state, None),
         }
     }
 
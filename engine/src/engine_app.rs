@@ -17,11 +17,19 @@
  *
  */
 
+use std::path::PathBuf;
+
+use crate::calibration::Calibration;
+use crate::charts::comparison;
 use crate::epidemiology_simulation::Epidemiology;
 use crate::kafka::kafka_consumer::KafkaConsumer;
+use crate::run_control::RunControl;
 use crate::run_mode::RunMode;
 use crate::state_machine::DiseaseHandler;
-use common::config::Config;
+use crate::utils::environment;
+use common::config::request::Request;
+use common::config::{CalibrationConfig, Config, TravelPlanConfig};
+use common::transport::{EngineTransport, TcpEngineTransport};
 
 pub const STANDALONE_SIM_ID: &str = "0";
 
@@ -51,4 +59,69 @@ impl EngineApp {
         }
         info!("Done");
     }
+
+    /// Runs a standalone simulation on the calling thread, reporting to and stopping on `control`
+    pub fn start_controlled(config: Config, control: RunControl, threads: u32) {
+        let run_mode = RunMode::Standalone;
+        let disease = config.get_disease();
+        let mut epidemiology = Epidemiology::new(config, None, STANDALONE_SIM_ID.to_string(), &run_mode, disease);
+        epidemiology.set_run_control(control);
+        futures::executor::block_on(epidemiology.run(&run_mode, threads));
+        info!("Done");
+    }
+
+    /// Runs one engine of a multi-engine simulation on the calling thread, exchanging ticks and travellers through `transport`
+    pub fn start_with_transport<E: EngineTransport>(
+        engine_id: &str,
+        config: Config,
+        travel_plan_config: TravelPlanConfig,
+        transport: &mut E,
+        threads: u32,
+    ) {
+        let engine_id = engine_id.to_string();
+        let run_mode = RunMode::MultiEngine { engine_id: engine_id.clone() };
+        let disease = config.get_disease();
+        let mut epidemiology = Epidemiology::new(config, Some(travel_plan_config), engine_id.clone(), &run_mode, disease);
+        futures::executor::block_on(epidemiology.run_with_transport(&engine_id, transport, threads));
+        info!("{}: Done", engine_id);
+    }
+
+    /// Runs one engine of a multi-engine simulation that registers with the orchestrator at `orchestrator` and
+    /// exchanges travellers with the other engines directly over TCP, listening for them on `listen`
+    pub async fn start_over_tcp(engine_id: &str, orchestrator: &str, listen: &str, threads: u32) {
+        let (mut transport, request) =
+            TcpEngineTransport::connect(engine_id, orchestrator, listen).await.expect("Failed to join the simulation");
+        let request = match serde_json::from_slice(&request) {
+            Ok(Request::MultiSimRequest(request)) => request,
+            Ok(Request::SimulationRequest(_)) => panic!("Expected a multi-engine simulation request from the orchestrator"),
+            Err(e) => panic!("Failed to parse the simulation request: {}", e),
+        };
+        let engine_request = match request.engine_configs.into_iter().find(|c| c.engine_id == engine_id) {
+            Some(engine_request) => engine_request,
+            None => panic!("Couldn't find any work for engine_id: {}", engine_id),
+        };
+
+        let engine_id = engine_id.to_string();
+        let run_mode = RunMode::MultiEngine { engine_id: engine_id.clone() };
+        let config = engine_request.config.config;
+        let disease = config.get_disease();
+        let mut epidemiology = Epidemiology::new(config, Some(request.travel_plan), engine_id.clone(), &run_mode, disease);
+        epidemiology.run_with_transport(&engine_id, &mut transport, threads).await;
+        transport.shutdown().await;
+        info!("{}: Done", engine_id);
+    }
+
+    pub fn start_calibration(config: Config, calibration_config: CalibrationConfig, threads: u32) {
+        info!("Started in calibration mode");
+        let mut calibration = Calibration::new(config, calibration_config, threads).expect("Failed to set up calibration");
+        calibration.run().expect("Failed to write calibration report");
+        info!("Done");
+    }
+
+    pub fn compare_runs(counts_files: &[String], as_ensemble: bool, output_file: &str) {
+        let mut output_path: PathBuf = environment::output_dir();
+        output_path.push(output_file);
+        comparison::compare(counts_files, as_ensemble, &output_path).expect("Failed to draw comparison of runs");
+        info!("Comparison of {} runs written to {}", counts_files.len(), output_path.display());
+    }
 }
@@ -19,10 +19,13 @@
 
 use core::borrow::BorrowMut;
 use std::borrow::Borrow;
+use std::collections::HashMap;
 use std::time::Instant;
 
-use common::config::{Config, Population, TravelPlanConfig};
+use common::config::{Config, OutputStream, Population, TravelPlanConfig};
 use common::models::CommutePlan;
+use common::transport::wire_format::{self, WireFormat};
+use common::transport::EngineTransport;
 use common::utils::RandomWrapper;
 use futures::join;
 use opentelemetry::trace::{FutureExt, Span, TraceContextExt, Tracer};
@@ -31,28 +34,38 @@ use opentelemetry::{global, Context, KeyValue};
 use crate::allocation_map::CitizenLocationMap;
 use crate::geography;
 use crate::geography::Point;
+use crate::interventions::entry_screening::EntryScreening;
 use crate::interventions::hospital::BuildNewHospital;
 use crate::interventions::lockdown::LockdownIntervention;
 use crate::interventions::vaccination::VaccinateIntervention;
 use crate::interventions::Interventions;
-use crate::kafka::kafka_producer::{KafkaProducer, COMMUTE_TOPIC, MIGRATION_TOPIC};
-use crate::kafka::{ticks_consumer, travel_consumer};
+use crate::kafka::kafka_transport::KafkaTransport;
+use crate::listeners::citizen_states_writer::CitizenStatesWriter;
 use crate::listeners::csv_service::CsvListener;
 use crate::listeners::disease_tracker::Hotspot;
+use crate::listeners::epidemic_curves::EpidemicCurves;
+use crate::listeners::epidemic_metrics::EpidemicMetrics;
 use crate::listeners::events_kafka_producer::EventsKafkaProducer;
 use crate::listeners::intervention_reporter::InterventionReporter;
 use crate::listeners::listener::{Listener, Listeners};
+use crate::listeners::output_writer::OutputWriter;
+use crate::listeners::transmission_tree::TransmissionTree;
 use crate::listeners::travel_counter::TravelCounter;
+use crate::listeners::visualisation::Visualisation;
 use crate::models::constants;
 use crate::models::events::Counts;
+use crate::models::events::Outgoing;
 use crate::models::events::Tick;
+use crate::run_control::RunControl;
 use crate::run_mode::RunMode;
 use crate::state_machine::DiseaseHandler;
-use crate::tick::{receive_tick, send_ack};
+use crate::tick::{receive_tick, send_ack, AckReport, Heartbeats};
 use crate::travel::commute;
 use crate::travel::commute::Commuter;
 use crate::travel::commute::CommutersByRegion;
 use crate::travel::migration::{EngineMigrationPlan, Migrator, MigratorsByRegion};
+use crate::travel::{Hub, Restrictions};
+use crate::utils::environment;
 use crate::utils::util::{counts_at_start, output_file_format};
 
 pub struct Epidemiology<T: DiseaseHandler + Sync> {
@@ -65,6 +78,7 @@ pub struct Epidemiology<T: DiseaseHandler + Sync> {
     interventions: Interventions,
     rng: RandomWrapper,
     disease_handler: T,
+    run_control: Option<RunControl>,
 }
 
 impl<T: DiseaseHandler + Sync> Epidemiology<T> {
@@ -92,6 +106,14 @@ impl<T: DiseaseHandler + Sync> Epidemiology<T> {
             sim_id.clone(),
         );
 
+        if config.draw_grid() {
+            let mut output_path = environment::output_dir();
+            output_path.push(format!("{}_grid.png", output_file_format(&config, run_mode)));
+            if let Err(e) = grid.draw(&start_locations, &output_path) {
+                error!("Failed to draw the grid: {}", e);
+            }
+        }
+
         let mut citizen_location_map = CitizenLocationMap::new(grid, &agent_list, &start_locations);
 
         info!("Initialization completed in {} seconds", start.elapsed().as_secs_f32());
@@ -111,41 +133,82 @@ impl<T: DiseaseHandler + Sync> Epidemiology<T> {
             sim_id,
             rng,
             disease_handler,
+            run_control: None,
         }
     }
 
     fn create_listeners(engine_id: &str, current_pop: usize, run_mode: &RunMode, config: &Config) -> Listeners {
         let output_file_format = output_file_format(config, run_mode);
-        let counts_file_name = format!("{output_file_format}.csv");
 
-        let csv_listener = CsvListener::new(counts_file_name);
+        let hotspot_tracker = Hotspot::new(output_file_format.clone(), config.enable_hotspot_snapshots());
+        let epidemic_metrics = EpidemicMetrics::new(output_file_format.clone());
+        let mut listeners_vec: Vec<Box<dyn Listener>> = vec![Box::new(hotspot_tracker), Box::new(epidemic_metrics)];
+
+        match config.get_output_config() {
+            Some(output_config) => listeners_vec.push(Box::new(OutputWriter::new(&output_file_format, output_config))),
+            None => {
+                let csv_listener = CsvListener::new(format!("{output_file_format}.csv"));
+                let intervention_reporter = InterventionReporter::new(format!("{output_file_format}_interventions.json"));
+                listeners_vec.push(Box::new(csv_listener));
+                listeners_vec.push(Box::new(intervention_reporter));
+                if let RunMode::MultiEngine { .. } = run_mode {
+                    let travel_counter = TravelCounter::new(format!("{output_file_format}_outgoing_travels.csv"));
+                    listeners_vec.push(Box::new(travel_counter));
+                }
+            }
+        }
 
-        let hotspot_tracker = Hotspot::new();
-        let intervention_reporter = InterventionReporter::new(format!("{output_file_format}_interventions.json"));
-        let mut listeners_vec: Vec<Box<dyn Listener>> =
-            vec![Box::new(csv_listener), Box::new(hotspot_tracker), Box::new(intervention_reporter)];
+        if let Some(format) = config.get_transmission_tree_format() {
+            listeners_vec.push(Box::new(TransmissionTree::new(output_file_format.clone(), format)));
+        }
 
-        match run_mode {
-            RunMode::Standalone => {}
-            RunMode::SingleDaemon => {
-                let kafka_listener =
-                    EventsKafkaProducer::new(engine_id.to_string(), current_pop, config.enable_citizen_state_messages());
-                listeners_vec.push(Box::new(kafka_listener));
-            }
-            RunMode::MultiEngine { .. } => {
-                let travels_file_name = format!("{output_file_format}_outgoing_travels.csv");
-                let travel_counter = TravelCounter::new(travels_file_name);
-                listeners_vec.push(Box::new(travel_counter));
+        if let Some(format) = config.get_epidemic_curves_format() {
+            listeners_vec.push(Box::new(EpidemicCurves::new(output_file_format.clone(), format)));
+        }
 
-                let kafka_listener =
-                    EventsKafkaProducer::new(engine_id.to_string(), current_pop, config.enable_citizen_state_messages());
-                listeners_vec.push(Box::new(kafka_listener));
-            }
+        if let Some(visualisation) = config.get_visualisation() {
+            listeners_vec.push(Box::new(Visualisation::new(output_file_format.clone(), visualisation)));
+        }
+
+        if let Some(export) = config.get_citizen_states_export() {
+            listeners_vec.push(Box::new(CitizenStatesWriter::new(&output_file_format, current_pop, export)));
+        }
+
+        // multi-engine runs only publish events when their engines talk through Kafka, see `run`
+        if let RunMode::SingleDaemon = run_mode {
+            let kafka_listener =
+                EventsKafkaProducer::new(engine_id.to_string(), current_pop, config.enable_citizen_state_messages());
+            listeners_vec.push(Box::new(kafka_listener));
         }
 
         Listeners::from(listeners_vec)
     }
 
+    fn publish_citizen_states(config: &Config) -> bool {
+        config.enable_citizen_state_messages()
+            || config.get_citizen_states_export().is_some()
+            || config.get_visualisation().is_some()
+            || config.get_output_config().is_some_and(|output| output.includes(OutputStream::CitizenStates))
+    }
+
+    pub fn set_listeners(&mut self, listeners: Listeners) {
+        self.listeners = listeners;
+    }
+
+    /// Reports the progress of the run to `control` and stops it when `control` is cancelled
+    pub fn set_run_control(&mut self, control: RunControl) {
+        self.listeners.push(Box::new(control.clone()));
+        self.run_control = Some(control);
+    }
+
+    pub fn get_listeners(&self) -> &Listeners {
+        &self.listeners
+    }
+
+    pub fn get_counts(&self) -> Counts {
+        self.counts_at_hr
+    }
+
     fn init_interventions(
         config: &Config,
         citizen_location_map: &mut CitizenLocationMap,
@@ -159,26 +222,54 @@ impl<T: DiseaseHandler + Sync> Epidemiology<T> {
         citizen_location_map.iter_mut().for_each(|r| {
             (*r.1).assign_essential_worker(essential_workers_population, rng);
         });
-        Interventions { vaccinate: vaccinations, lockdown: lock_down_details, build_new_hospital: hospital_intervention }
+        Interventions {
+            vaccinate: vaccinations,
+            lockdown: lock_down_details,
+            build_new_hospital: hospital_intervention,
+            entry_screening: EntryScreening::init(config),
+        }
     }
 
-    pub async fn run(&mut self, run_mode: &RunMode, threads: u32) {
-        rayon::ThreadPoolBuilder::new().num_threads(threads as usize).build_global().unwrap();
+    fn init_run(&mut self, threads: u32) {
+        // the global pool can only be built once per process, later runs (e.g. calibration or
+        // engines sharing a process) reuse it
+        if let Err(e) = rayon::ThreadPoolBuilder::new().num_threads(threads as usize).build_global() {
+            debug!("Reusing existing thread pool: {}", e);
+        }
 
         self.listeners.grid_updated(&self.citizen_location_map.grid);
+    }
+
+    pub async fn run(&mut self, run_mode: &RunMode, threads: u32) {
         match run_mode {
             RunMode::MultiEngine { engine_id } => {
-                let tracer = global::tracer("epirust-trace");
-                let mut span = tracer.start(format!("multi-engine - {engine_id}"));
-                span.set_attribute(KeyValue::new("mode", "multi-engine"));
-                span.set_attribute(KeyValue::new("engine_id", engine_id.to_string()));
-                let cx = Context::current_with_span(span);
-                self.run_multi_engine(engine_id).with_context(cx).await
+                let kafka_listener = EventsKafkaProducer::new(
+                    self.sim_id.clone(),
+                    self.citizen_location_map.current_population() as usize,
+                    self.config.enable_citizen_state_messages(),
+                );
+                self.listeners.push(Box::new(kafka_listener));
+                let mut transport = KafkaTransport::new(engine_id);
+                self.run_with_transport(engine_id, &mut transport, threads).await
+            }
+            _ => {
+                self.init_run(threads);
+                self.run_single_engine(run_mode).await
             }
-            _ => self.run_single_engine(run_mode).await,
         }
     }
 
+    /// Runs as one engine of a multi-engine simulation, exchanging ticks and travellers through `transport`
+    pub async fn run_with_transport<E: EngineTransport>(&mut self, engine_id: &String, transport: &mut E, threads: u32) {
+        self.init_run(threads);
+        let tracer = global::tracer("epirust-trace");
+        let mut span = tracer.start(format!("multi-engine - {engine_id}"));
+        span.set_attribute(KeyValue::new("mode", "multi-engine"));
+        span.set_attribute(KeyValue::new("engine_id", engine_id.to_string()));
+        let cx = Context::current_with_span(span);
+        self.run_multi_engine(engine_id, transport).with_context(cx).await
+    }
+
     pub async fn run_single_engine(&mut self, run_mode: &RunMode) {
         let start_time = Instant::now();
         let mut outgoing_migrators = Vec::new();
@@ -192,6 +283,10 @@ impl<T: DiseaseHandler + Sync> Epidemiology<T> {
         counts_at_hr.log();
         let listeners = self.listeners.borrow_mut();
         for simulation_hour in 1..self.config.get_hours() {
+            if self.run_control.as_ref().is_some_and(RunControl::is_cancelled) {
+                info!("Simulation cancelled at hour {}", simulation_hour);
+                break;
+            }
             counts_at_hr.increment_hour();
 
             let population_before_travel = self.citizen_location_map.current_population();
@@ -208,7 +303,7 @@ impl<T: DiseaseHandler + Sync> Epidemiology<T> {
                 percent_outgoing,
                 &mut outgoing_migrators,
                 &mut outgoing_commuters,
-                self.config.enable_citizen_state_messages(),
+                Self::publish_citizen_states(&self.config),
                 None,
                 &self.sim_id,
                 &self.disease_handler,
@@ -244,37 +339,29 @@ impl<T: DiseaseHandler + Sync> Epidemiology<T> {
         listeners.simulation_ended();
     }
 
-    pub async fn run_multi_engine(&mut self, engine_id: &String) {
+    async fn run_multi_engine<E: EngineTransport>(&mut self, engine_id: &String, transport: &mut E) {
         let start_time = Instant::now();
-        let mut producer = KafkaProducer::new();
 
         let travel_plan_config = self.travel_plan_config.as_ref().unwrap();
 
         debug!("{}: Start Multi Engine Simulation", engine_id);
         let is_commute_enabled = travel_plan_config.commute.enabled;
         let is_migration_enabled = travel_plan_config.migration.enabled;
+        let wire_format = travel_plan_config.get_wire_format();
 
         let migration_plan = if is_migration_enabled { Some(travel_plan_config.migration_plan()) } else { None };
 
         let mut engine_migration_plan =
             EngineMigrationPlan::new(engine_id.clone(), migration_plan, self.citizen_location_map.current_population());
-
-        debug!("{}: Start Migrator Consumer", engine_id);
-        let migrators_consumer = travel_consumer::start(engine_id, &[&*format!("{MIGRATION_TOPIC}{engine_id}")], "migrate");
-        let mut migration_stream = migrators_consumer.stream();
+        engine_migration_plan.set_stay_length(travel_plan_config.get_migration_stay_length());
+        let mut hub = Hub::for_engine(travel_plan_config, engine_id);
 
         let commute_plan = if is_commute_enabled {
             travel_plan_config.commute_plan()
         } else {
             CommutePlan { regions: Vec::new(), matrix: Vec::new() }
         };
-
-        debug!("{}: Start Commuter Consumer", engine_id);
-        let commute_consumer = travel_consumer::start(engine_id, &[&*format!("{COMMUTE_TOPIC}{engine_id}")], "commute");
-        let mut commute_stream = commute_consumer.stream();
-
-        let ticks_consumer = ticks_consumer::start(engine_id);
-        let mut ticks_stream = ticks_consumer.stream();
+        let commute_regions = commute_plan.connected_regions(engine_id);
 
         let mut n_incoming = 0;
         let mut n_outgoing = 0;
@@ -292,15 +379,25 @@ impl<T: DiseaseHandler + Sync> Epidemiology<T> {
         let mut total_send_commuters_time = 0;
         let mut total_send_migrator_time = 0;
         let run_mode = RunMode::MultiEngine { engine_id: engine_id.to_string() };
+        let mut heartbeats = Heartbeats::start();
+        let mut outgoing_since_ack: HashMap<String, Outgoing> = HashMap::new();
+        let mut is_commute_active = false;
+        let mut is_migration_active = false;
 
         let hours = self.config.get_hours();
         let config = &self.config;
         for simulation_hour in 1..hours {
             let start_time = Instant::now();
             let tracer = global::tracer("epirust-trace");
-            let tick =
-                receive_tick(&run_mode, &mut ticks_stream, simulation_hour, is_commute_enabled, is_migration_enabled).await;
-            if let Some(t) = tick {
+            heartbeats.beat(&run_mode, transport, simulation_hour);
+            let day = simulation_hour / constants::HOURS_IN_A_DAY;
+            if simulation_hour == 1 || simulation_hour % constants::HOURS_IN_A_DAY == 0 {
+                // the travel phases only change from one day to the next
+                is_commute_active = travel_plan_config.is_commute_active(day);
+                is_migration_active = travel_plan_config.is_migration_active(day);
+            }
+            let tick = receive_tick(&run_mode, transport, simulation_hour, is_commute_active).await;
+            if let Some(t) = &tick {
                 total_tick_sync_time += start_time.elapsed().as_millis();
                 info!("total tick sync time as hour {} - is {}", simulation_hour, total_tick_sync_time);
                 if t.terminate() {
@@ -316,26 +413,32 @@ impl<T: DiseaseHandler + Sync> Epidemiology<T> {
             if population_before_travel == 0 {
                 panic!("No citizens!");
             }
-            if is_migration_enabled {
+            if is_migration_active {
                 engine_migration_plan.set_current_population(population_before_travel);
             }
 
+            let released = interventions.entry_screening.release(simulation_hour);
+            if !released.is_empty() {
+                self.citizen_location_map.release_from_quarantine(&released, interventions.lockdown.is_locked_down());
+            }
+
             let mut percent_outgoing = 0.0;
             let mut outgoing: Vec<(Point, Migrator)> = Vec::new();
 
-            if simulation_hour % 24 == 0 && is_migration_enabled {
+            if simulation_hour % 24 == 0 && is_migration_active {
+                engine_migration_plan.set_migration_plan(travel_plan_config.migration_plan_on(day));
                 percent_outgoing = engine_migration_plan.percent_outgoing();
+                if interventions.lockdown.is_locked_down() {
+                    percent_outgoing *= travel_plan_config.get_migration_lockdown_scale();
+                }
             }
             let mut actual_outgoing: Vec<(Point, Migrator)> = Vec::new();
 
-            let received_migrators = if is_migration_enabled {
-                debug!("{}: Received Migrators | Simulation hour: {}", engine_id, simulation_hour);
-                Some(engine_migration_plan.receive_migrators(tick, &mut migration_stream))
-            } else {
-                None
-            };
-
+            let is_commute_departure = is_commute_active && simulation_hour % 24 == constants::ROUTINE_TRAVEL_START_TIME;
+            let commute_scale =
+                if interventions.lockdown.is_locked_down() { travel_plan_config.get_commute_lockdown_scale() } else { 1.0 };
             let mut outgoing_commuters: Vec<(Point, Commuter)> = Vec::new();
+            let restrictions = Restrictions::for_tick(tick.as_ref(), engine_id);
             let location_map = self.citizen_location_map.borrow_mut();
             let listeners = self.listeners.borrow_mut();
             let sim = async {
@@ -348,41 +451,82 @@ impl<T: DiseaseHandler + Sync> Epidemiology<T> {
                     percent_outgoing,
                     &mut outgoing,
                     &mut outgoing_commuters,
-                    config.enable_citizen_state_messages(),
+                    Self::publish_citizen_states(config),
                     Some(travel_plan_config),
                     engine_id,
                     disease_handler,
                 );
                 debug!("{}: Simulation finished for hour: {}", engine_id, simulation_hour);
 
-                let (outgoing_migrators_by_region, actual_total_outgoing) = if is_migration_enabled {
+                if is_commute_departure {
+                    let today = travel_plan_config.commute_plan_on(day);
+                    commute::select_departures(&mut outgoing_commuters, &commute_plan, &today, engine_id, commute_scale, rng);
+                    for (region, count) in commute::hold_back(&mut outgoing_commuters, &restrictions, rng) {
+                        outgoing_since_ack.entry(region).or_default().blocked_commuters += count;
+                    }
+                }
+
+                let returning = if simulation_hour % 24 == 0 && is_migration_active {
+                    let due = engine_migration_plan.due_returns(simulation_hour);
+                    outgoing.retain(|(_, migrator)| !due.contains_key(&migrator.citizen.id));
+                    engine_migration_plan.plan_trips(&mut outgoing, location_map, simulation_hour, rng);
+                    location_map.returning_migrators(&due)
+                } else {
+                    Vec::new()
+                };
+
+                let (mut outgoing_migrators_by_region, mut actual_total_outgoing) = if is_migration_active {
                     engine_migration_plan.alloc_outgoing_to_regions(&outgoing)
                 } else {
                     (Vec::new(), Vec::new())
                 };
+                let held_back = engine_migration_plan.hold_back(
+                    &mut outgoing_migrators_by_region,
+                    &mut actual_total_outgoing,
+                    &restrictions,
+                    rng,
+                );
+                for (region, count) in held_back {
+                    outgoing_since_ack.entry(region).or_default().blocked_migrators += count;
+                }
+                // restrictions only hold back departures, visitors can always go home
+                engine_migration_plan.send_home(&mut outgoing_migrators_by_region, &returning);
+                actual_total_outgoing.extend(returning);
 
                 actual_outgoing = actual_total_outgoing;
 
-                if simulation_hour % 24 == 0 && is_migration_enabled {
+                if simulation_hour % 24 == 0 && is_migration_active {
                     listeners.outgoing_migrators_added(simulation_hour, &outgoing_migrators_by_region);
                 }
 
-                let outgoing_commuters_by_region = if is_commute_enabled {
-                    CommutersByRegion::get_commuters_by_region(&commute_plan.regions, &outgoing_commuters, simulation_hour)
+                let outgoing_commuters_by_region = if is_commute_active {
+                    CommutersByRegion::get_commuters_by_region(&commute_regions, &outgoing_commuters, simulation_hour)
                 } else {
                     Vec::new()
                 };
 
-                if is_migration_enabled {
+                if is_migration_active {
                     debug!("{}: Send Migrators", engine_id);
                     let send_migrator_start_time = Instant::now();
-                    Self::send_migrators(tick, &mut producer, outgoing_migrators_by_region);
+                    Self::send_migrators(
+                        tick.as_ref(),
+                        transport,
+                        wire_format,
+                        outgoing_migrators_by_region,
+                        &mut outgoing_since_ack,
+                    );
                     total_send_migrator_time += send_migrator_start_time.elapsed().as_millis();
                 }
-                if is_commute_enabled {
+                if is_commute_active {
                     debug!("{}: Send Commuters", engine_id);
                     let send_commuter_start_time = Instant::now();
-                    Self::send_commuters(tick, &mut producer, outgoing_commuters_by_region);
+                    Self::send_commuters(
+                        tick.as_ref(),
+                        transport,
+                        wire_format,
+                        outgoing_commuters_by_region,
+                        &mut outgoing_since_ack,
+                    );
                     total_send_commuters_time += send_commuter_start_time.elapsed().as_millis();
                 }
             };
@@ -392,30 +536,70 @@ impl<T: DiseaseHandler + Sync> Epidemiology<T> {
             let cx1 = Context::current_with_span(span1);
             let _ = join!(sim).with_context(cx1);
 
-            if is_commute_enabled {
+            let mut incoming_commuters = Vec::new();
+            if is_commute_active {
                 let commute_start_time = Instant::now();
                 let mut span2 = tracer.start("receive_commuters");
                 span2.set_attribute(KeyValue::new("hour", simulation_hour.to_string()));
                 let cx2 = Context::current_with_span(span2);
-                let received_commuters = commute::receive_commuters(&commute_plan, tick, &mut commute_stream, engine_id);
-                let mut incoming_commuters = received_commuters.with_context(cx2).await;
+                let mut waiting = heartbeats.while_waiting(&run_mode, transport, simulation_hour);
+                let received_commuters = commute::receive_commuters(&commute_plan, tick.as_ref(), &mut waiting, engine_id);
+                incoming_commuters = received_commuters.with_context(cx2).await;
                 total_receive_commute_sync_time += commute_start_time.elapsed().as_millis();
                 info!("total commute sync time as hour {} - is {}", simulation_hour, total_receive_commute_sync_time);
                 n_incoming += incoming_commuters.len();
                 n_outgoing += outgoing_commuters.len();
-                self.citizen_location_map.remove_commuters(&outgoing_commuters, counts_at_hr);
-                self.citizen_location_map.assimilate_commuters(&mut incoming_commuters, counts_at_hr, rng, simulation_hour);
-                debug!("{}: assimilated the commuters", engine_id);
             }
 
-            if is_migration_enabled {
+            let mut incoming_migrators = Vec::new();
+            if is_migration_active {
                 let migration_start_time = Instant::now();
-                let (mut incoming,) = join!(received_migrators.unwrap());
+                debug!("{}: Receive Migrators | Simulation hour: {}", engine_id, simulation_hour);
+                let mut waiting = heartbeats.while_waiting(&run_mode, transport, simulation_hour);
+                incoming_migrators = engine_migration_plan.receive_migrators(tick.as_ref(), &mut waiting).await;
                 total_receive_migration_sync_time += migration_start_time.elapsed().as_millis();
-                n_incoming += incoming.len();
+                n_incoming += incoming_migrators.len();
                 n_outgoing += outgoing.len();
+            }
+
+            if let Some(hub) = hub.as_mut() {
+                // commuters and migrators arriving in the same hour pass through the hub together
+                let mut arrivals: Vec<_> = incoming_commuters
+                    .iter_mut()
+                    .map(|commuter| &mut commuter.citizen)
+                    .chain(incoming_migrators.iter_mut().map(|migrator| &mut migrator.citizen))
+                    .collect();
+                hub.pass_through(&mut arrivals, simulation_hour, disease_handler, rng);
+            }
+
+            if is_commute_active {
+                let quarantined = if simulation_hour % 24 == constants::ROUTINE_TRAVEL_START_TIME {
+                    // commuters stay in quarantine for the working day, and go home with the others
+                    let until = simulation_hour + constants::ROUTINE_TRAVEL_END_TIME - constants::ROUTINE_TRAVEL_START_TIME;
+                    let arrivals =
+                        incoming_commuters.iter().map(|commuter| (commuter.citizen.id, &commuter.citizen.state_machine));
+                    interventions.entry_screening.screen(arrivals, until, rng)
+                } else {
+                    Vec::new()
+                };
+                self.citizen_location_map.remove_commuters(&outgoing_commuters, counts_at_hr);
+                self.citizen_location_map.assimilate_commuters(&mut incoming_commuters, counts_at_hr, rng, simulation_hour);
+                if !quarantined.is_empty() {
+                    self.citizen_location_map.quarantine(&quarantined);
+                }
+                debug!("{}: assimilated the commuters", engine_id);
+            }
+
+            if is_migration_active {
+                let until = simulation_hour + constants::QUARANTINE_DAYS * constants::HOURS_IN_A_DAY;
+                let arrivals = incoming_migrators.iter().map(|migrator| (migrator.citizen.id, &migrator.citizen.state_machine));
+                let quarantined = interventions.entry_screening.screen(arrivals, until, rng);
+                engine_migration_plan.update_visitors(&actual_outgoing, &incoming_migrators);
                 self.citizen_location_map.remove_migrators(&actual_outgoing, counts_at_hr);
-                self.citizen_location_map.assimilate_migrators(&mut incoming, counts_at_hr, rng);
+                self.citizen_location_map.assimilate_migrators(&mut incoming_migrators, counts_at_hr, rng);
+                if !quarantined.is_empty() {
+                    self.citizen_location_map.quarantine(&quarantined);
+                }
                 debug!("{}: assimilated the migrators", engine_id);
             }
 
@@ -435,12 +619,11 @@ impl<T: DiseaseHandler + Sync> Epidemiology<T> {
 
             send_ack(
                 &run_mode,
-                &mut producer,
-                *counts_at_hr,
+                transport,
                 simulation_hour,
-                &interventions.lockdown,
-                is_commute_enabled,
-                is_migration_enabled,
+                interventions,
+                AckReport { counts: *counts_at_hr, hub: hub.as_mut(), outgoing: &mut outgoing_since_ack },
+                is_commute_active,
             );
 
             if simulation_hour % 100 == 0 {
@@ -469,20 +652,44 @@ impl<T: DiseaseHandler + Sync> Epidemiology<T> {
         info!("total receive migration sync time: {}", total_receive_migration_sync_time);
         info!("total send commuters sync time: {}", total_send_commuters_time);
         info!("total send migrators sync time: {}", total_send_migrator_time);
+        if let Some(hub) = &hub {
+            info!("travellers infected in {}: {}", hub.name(), hub.infections());
+        }
         self.listeners.simulation_ended();
     }
 
-    fn send_migrators(tick: Option<Tick>, producer: &mut KafkaProducer, outgoing: Vec<MigratorsByRegion>) {
-        if tick.is_some() && tick.unwrap().hour() % 24 == 0 {
-            producer.send_migrators(outgoing);
+    fn send_migrators<E: EngineTransport>(
+        tick: Option<&Tick>,
+        transport: &mut E,
+        wire_format: WireFormat,
+        outgoing: Vec<MigratorsByRegion>,
+        sent: &mut HashMap<String, Outgoing>,
+    ) {
+        if tick.is_some_and(|tick| tick.hour() % 24 == 0) {
+            for out_region in outgoing.iter() {
+                let payload = wire_format::encode(wire_format, out_region);
+                trace!("Sending migrators: {} bytes to region: {}", payload.len(), out_region.to_engine_id());
+                transport.send_migrators(out_region.to_engine_id(), payload);
+                sent.entry(out_region.to_engine_id().clone()).or_default().migrators += out_region.get_migrators_slice().len();
+            }
         }
     }
 
-    fn send_commuters(tick_op: Option<Tick>, producer: &mut KafkaProducer, outgoing: Vec<CommutersByRegion>) {
+    fn send_commuters<E: EngineTransport>(
+        tick_op: Option<&Tick>,
+        transport: &mut E,
+        wire_format: WireFormat,
+        outgoing: Vec<CommutersByRegion>,
+        sent: &mut HashMap<String, Outgoing>,
+    ) {
         if let Some(tick) = tick_op {
             let hour = tick.hour() % 24;
             if hour == constants::ROUTINE_TRAVEL_START_TIME || hour == constants::ROUTINE_TRAVEL_END_TIME {
-                producer.send_commuters(outgoing);
+                for out_region in outgoing.iter() {
+                    debug!("Sending commuters: {} to region: {}", out_region.commuters.len(), out_region.to_engine_id());
+                    transport.send_commuters(out_region.to_engine_id(), wire_format::encode(wire_format, out_region));
+                    sent.entry(out_region.to_engine_id().clone()).or_default().commuters += out_region.commuters.len();
+                }
             }
         }
     }
@@ -34,6 +34,16 @@ pub struct Area {
     pub end_offset: Point,
 }
 
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AreaType {
+    Home,
+    Transport,
+    Work,
+    Hospital,
+    Other,
+}
+
 // We need to ignore the iter_index when comparing
 impl PartialEq for Area {
     fn eq(&self, other: &Self) -> bool {
@@ -22,11 +22,13 @@ use common::models::custom_types::{CoOrdinate, Count, Size};
 use common::utils::RandomWrapper;
 use plotters::prelude::*;
 use std::collections::HashMap;
+use std::error::Error;
 use std::fs::File;
+use std::path::Path;
 
 use crate::citizen;
 use crate::citizen::{Citizen, CitizensData, PopulationRecord};
-use crate::geography::{Area, Point};
+use crate::geography::{Area, AreaType, Point};
 use crate::models::constants;
 
 #[derive(Serialize, Clone)]
@@ -81,10 +83,7 @@ impl Grid {
         // info!("agent list - {:?} ", agent_list);
         debug!("Finished creating agent list");
 
-        let (home_loc, agents_in_order) = self.set_start_locations_and_occupancies(rng, &agent_list, &region);
-
-        self.draw(&home_loc, &self.houses, &self.offices);
-        (home_loc, agents_in_order)
+        self.set_start_locations_and_occupancies(rng, &agent_list, &region)
     }
 
     fn set_start_locations_and_occupancies(
@@ -120,46 +119,44 @@ impl Grid {
         (home_loc, agents_in_order)
     }
 
-    pub fn group_agents_by_home_locations(agent_list: &[Citizen]) -> HashMap<&Area, Vec<&Citizen>> {
-        let mut agents_by_home_locations: HashMap<&Area, Vec<&Citizen>> = HashMap::new();
-        agent_list.iter().for_each(|agent| match agents_by_home_locations.get(&agent.home_location) {
+    /// The agents of each house, with the houses in the order their first agent comes in, so that a seeded run
+    /// places everyone the same way
+    pub fn group_agents_by_home_locations(agent_list: &[Citizen]) -> Vec<(&Area, Vec<&Citizen>)> {
+        let mut index_of_home: HashMap<&Area, usize> = HashMap::new();
+        let mut agents_by_home_locations: Vec<(&Area, Vec<&Citizen>)> = Vec::new();
+        agent_list.iter().for_each(|agent| match index_of_home.get(&agent.home_location) {
             None => {
-                agents_by_home_locations.insert(&agent.home_location, vec![agent]);
-            }
-            Some(citizens) => {
-                let mut updated_citizens = citizens.clone();
-                updated_citizens.push(agent);
-                agents_by_home_locations.insert(&agent.home_location, updated_citizens);
+                index_of_home.insert(&agent.home_location, agents_by_home_locations.len());
+                agents_by_home_locations.push((&agent.home_location, vec![agent]));
             }
+            Some(index) => agents_by_home_locations[*index].1.push(agent),
         });
         agents_by_home_locations
     }
 
-    fn draw(&self, home_locations: &Vec<Point>, homes: &Vec<Area>, offices: &Vec<Area>) {
-        let mut draw_backend = BitMapBackend::new("grid.png", (self.grid_size as u32, self.grid_size as u32));
-        Grid::draw_rect(&mut draw_backend, &self.housing_area, &YELLOW);
-        Grid::draw_rect(&mut draw_backend, &self.transport_area, &RGBColor(121, 121, 121));
-        Grid::draw_rect(&mut draw_backend, &self.work_area, &BLUE);
-        Grid::draw_rect(&mut draw_backend, &self.hospital_area, &RED);
-        for home in homes {
-            Grid::draw_rect(&mut draw_backend, home, &RGBColor(204, 153, 0));
+    /// Draws the areas, houses, offices and the home location of every citizen
+    pub fn draw(&self, home_locations: &[Point], output_path: &Path) -> Result<(), Box<dyn Error>> {
+        let mut draw_backend = BitMapBackend::new(output_path, (self.grid_size, self.grid_size));
+        Grid::draw_rect(&mut draw_backend, &self.housing_area, &YELLOW)?;
+        Grid::draw_rect(&mut draw_backend, &self.transport_area, &RGBColor(121, 121, 121))?;
+        Grid::draw_rect(&mut draw_backend, &self.work_area, &BLUE)?;
+        Grid::draw_rect(&mut draw_backend, &self.hospital_area, &RED)?;
+        for home in &self.houses {
+            Grid::draw_rect(&mut draw_backend, home, &RGBColor(204, 153, 0))?;
         }
-        for office in offices {
-            Grid::draw_rect(&mut draw_backend, office, &RGBColor(51, 153, 255));
+        for office in &self.offices {
+            Grid::draw_rect(&mut draw_backend, office, &RGBColor(51, 153, 255))?;
         }
         for home in home_locations {
-            draw_backend.draw_pixel((home.x as i32, home.y as i32), BLACK.to_backend_color()).unwrap();
+            draw_backend.draw_pixel((home.x, home.y), BLACK.to_backend_color())?;
         }
+        draw_backend.present()?;
+        Ok(())
     }
 
-    fn draw_rect(svg: &mut impl DrawingBackend, area: &Area, style: &RGBColor) {
-        svg.draw_rect(
-            (area.start_offset.x as i32, area.start_offset.y as i32),
-            (area.end_offset.x as i32, area.end_offset.y as i32),
-            style,
-            true,
-        )
-        .unwrap();
+    fn draw_rect(svg: &mut BitMapBackend, area: &Area, style: &RGBColor) -> Result<(), Box<dyn Error>> {
+        svg.draw_rect((area.start_offset.x, area.start_offset.y), (area.end_offset.x, area.end_offset.y), style, true)?;
+        Ok(())
     }
 
     pub fn read_population(
@@ -195,11 +192,54 @@ impl Grid {
 
         let (home_loc, mut agents_in_order) = self.set_start_locations_and_occupancies(rng, &citizens, region_name);
         citizen::set_starting_infections(&mut agents_in_order, starting_infections, rng);
-
-        self.draw(&home_loc, &self.houses, &self.offices);
         (home_loc, agents_in_order)
     }
 
+    /// The same areas without the houses, offices and their occupancy, which is all it takes to tell which area or
+    /// building a point is in
+    pub fn layout(&self) -> Grid {
+        Grid {
+            houses: Vec::new(),
+            offices: Vec::new(),
+            houses_occupancy: HashMap::new(),
+            offices_occupancy: HashMap::new(),
+            ..*self
+        }
+    }
+
+    pub fn area_type_of(&self, point: &Point) -> AreaType {
+        if self.housing_area.contains(point) {
+            AreaType::Home
+        } else if self.transport_area.contains(point) {
+            AreaType::Transport
+        } else if self.work_area.contains(point) {
+            AreaType::Work
+        } else if self.hospital_area.contains(point) {
+            AreaType::Hospital
+        } else {
+            AreaType::Other
+        }
+    }
+
+    /// The house or office the point belongs to, found from the regular layout produced by `area_factory`
+    pub fn building_of(&self, point: &Point) -> Option<Area> {
+        let (area, size) = match self.area_type_of(point) {
+            AreaType::Home => (&self.housing_area, constants::HOME_SIZE as CoOrdinate),
+            AreaType::Work => (&self.work_area, constants::OFFICE_SIZE as CoOrdinate),
+            _ => return None,
+        };
+        let start_offset = Point::new(
+            area.start_offset.x + (point.x - area.start_offset.x) / size * size,
+            area.start_offset.y + (point.y - area.start_offset.y) / size * size,
+        );
+        let end_offset = Point::new(start_offset.x + size - 1, start_offset.y + size - 1);
+        if end_offset.x <= area.end_offset.x && end_offset.y <= area.end_offset.y {
+            Some(Area { location_id: area.location_id, start_offset, end_offset })
+        } else {
+            None
+        }
+    }
+
     pub fn increase_hospital_size(&mut self, grid_size: Size, sim_id: String) {
         let start_offset = self.hospital_area.start_offset;
         let end_offset = Point::new(grid_size as CoOrdinate, grid_size as CoOrdinate);
@@ -310,6 +350,42 @@ mod tests {
         }
     }
 
+    #[test]
+    fn should_find_area_type_of_point() {
+        let grid = define_geography(100, "engine1".to_string());
+
+        assert_eq!(grid.area_type_of(&Point::new(10, 5)), AreaType::Home);
+        assert_eq!(grid.area_type_of(&Point::new(45, 5)), AreaType::Transport);
+        assert_eq!(grid.area_type_of(&Point::new(65, 5)), AreaType::Work);
+        assert_eq!(grid.area_type_of(&Point::new(85, 5)), AreaType::Hospital);
+        assert_eq!(grid.area_type_of(&Point::new(95, 5)), AreaType::Other);
+    }
+
+    #[test]
+    fn should_find_building_of_point() {
+        let grid = define_geography(100, "engine1".to_string());
+
+        let house = grid.building_of(&Point::new(5, 6)).unwrap();
+        let office = grid.building_of(&Point::new(61, 13)).unwrap();
+
+        assert!(grid.houses.contains(&house));
+        assert_eq!(house.start_offset, Point::new(4, 6));
+        assert!(grid.offices.contains(&office));
+        assert_eq!(office.start_offset, Point::new(60, 10));
+        assert_eq!(grid.building_of(&Point::new(45, 5)), None);
+    }
+
+    #[test]
+    fn should_place_points_the_same_with_only_the_layout() {
+        let grid = define_geography(100, "engine1".to_string());
+
+        let layout = grid.layout();
+
+        assert!(layout.houses.is_empty() && layout.offices.is_empty());
+        assert_eq!(layout.building_of(&Point::new(61, 13)), grid.building_of(&Point::new(61, 13)));
+        assert_eq!(layout.area_type_of(&Point::new(45, 5)), AreaType::Transport);
+    }
+
     #[test]
     fn should_increase_hospital_size() {
         let mut grid = define_geography(100, "engine1".to_string());
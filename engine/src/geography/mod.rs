@@ -22,10 +22,12 @@ use crate::models::constants;
 mod area;
 mod grid;
 mod point;
+mod spatial_index;
 
-pub use area::Area;
+pub use area::{Area, AreaType};
 pub use grid::Grid;
 pub use point::Point;
+pub use spatial_index::SpatialIndex;
 
 use common::models::custom_types::{CoOrdinate, Size};
 use std::collections::HashMap;
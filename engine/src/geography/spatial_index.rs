@@ -0,0 +1,289 @@
+/*
+ * EpiRust
+ * Copyright (c) 2020  ThoughtWorks, Inc.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ */
+
+use common::models::custom_types::Size;
+use fnv::FnvHashMap;
+use rayon::prelude::*;
+
+use crate::geography::Point;
+
+const CHUNK_BITS: i32 = 5;
+const CHUNK_SIDE: i32 = 1 << CHUNK_BITS;
+const CHUNK_CELLS: usize = (CHUNK_SIDE * CHUNK_SIDE) as usize;
+const VACANT: u32 = u32::MAX;
+
+/// Values on the cells of a square grid. They are kept together in one vector, and found by cell through slot arrays
+/// covering 32x32 cells each, so looking a cell up takes two array reads instead of hashing its point. A chunk's slots
+/// are only allocated once a value lands in it. Points off the grid, e.g. in a hospital grown past its edge, are
+/// looked up in a hash map instead.
+#[derive(Clone)]
+pub struct SpatialIndex<T> {
+    side: i32,
+    chunks_per_side: i32,
+    chunks: Vec<Option<Box<[u32]>>>,
+    outside: FnvHashMap<Point, u32>,
+    entries: Vec<(Point, T)>,
+}
+
+impl<T> SpatialIndex<T> {
+    /// An index for the points with both coordinates from 0 to `grid_size`, which is where the areas of a grid end
+    pub fn new(grid_size: Size, capacity: usize) -> SpatialIndex<T> {
+        let side = grid_size as i32 + 1;
+        let chunks_per_side = (side + CHUNK_SIDE - 1) / CHUNK_SIDE;
+        SpatialIndex {
+            side,
+            chunks_per_side,
+            chunks: vec![None; (chunks_per_side * chunks_per_side) as usize],
+            outside: FnvHashMap::default(),
+            entries: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// The chunk and the slot within it of a point on the grid
+    fn position(&self, point: &Point) -> Option<(usize, usize)> {
+        if point.x < 0 || point.y < 0 || point.x >= self.side || point.y >= self.side {
+            return None;
+        }
+        let chunk = (point.y >> CHUNK_BITS) * self.chunks_per_side + (point.x >> CHUNK_BITS);
+        let slot = ((point.y & (CHUNK_SIDE - 1)) << CHUNK_BITS) | (point.x & (CHUNK_SIDE - 1));
+        Some((chunk as usize, slot as usize))
+    }
+
+    fn index_of(&self, point: &Point) -> Option<usize> {
+        let index = match self.position(point) {
+            Some((chunk, slot)) => self.chunks[chunk].as_ref().map_or(VACANT, |slots| slots[slot]),
+            None => self.outside.get(point).copied().unwrap_or(VACANT),
+        };
+        (index != VACANT).then_some(index as usize)
+    }
+
+    fn set_index(&mut self, point: Point, index: u32) {
+        match self.position(&point) {
+            Some((chunk, slot)) => {
+                self.chunks[chunk].get_or_insert_with(|| vec![VACANT; CHUNK_CELLS].into_boxed_slice())[slot] = index
+            }
+            None if index == VACANT => {
+                self.outside.remove(&point);
+            }
+            None => {
+                self.outside.insert(point, index);
+            }
+        }
+    }
+
+    pub fn get(&self, point: &Point) -> Option<&T> {
+        self.index_of(point).map(|index| &self.entries[index].1)
+    }
+
+    pub fn contains(&self, point: &Point) -> bool {
+        self.index_of(point).is_some()
+    }
+
+    /// Puts the value on the point, and returns the value that was there
+    pub fn insert(&mut self, point: Point, value: T) -> Option<T> {
+        match self.index_of(&point) {
+            Some(index) => Some(std::mem::replace(&mut self.entries[index].1, value)),
+            None => {
+                self.set_index(point, self.entries.len() as u32);
+                self.entries.push((point, value));
+                None
+            }
+        }
+    }
+
+    /// The value on the point, after putting this one there if it was vacant
+    pub fn get_or_insert(&mut self, point: Point, value: T) -> &T {
+        let index = match self.index_of(&point) {
+            Some(index) => index,
+            None => {
+                self.set_index(point, self.entries.len() as u32);
+                self.entries.push((point, value));
+                self.entries.len() - 1
+            }
+        };
+        &self.entries[index].1
+    }
+
+    pub fn remove(&mut self, point: &Point) -> Option<T> {
+        let index = self.index_of(point)?;
+        self.set_index(*point, VACANT);
+        let (_, value) = self.entries.swap_remove(index);
+        if let Some((moved, _)) = self.entries.get(index) {
+            self.set_index(*moved, index as u32);
+        }
+        Some(value)
+    }
+
+    /// Empties the index, keeping its chunks for the values to come
+    pub fn clear(&mut self) {
+        let entries = std::mem::take(&mut self.entries);
+        for (point, _) in &entries {
+            self.set_index(*point, VACANT);
+        }
+        self.entries = entries;
+        self.entries.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Point, &T)> {
+        self.entries.iter().map(|(point, value)| (point, value))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&Point, &mut T)> {
+        self.entries.iter_mut().map(|(point, value)| (&*point, value))
+    }
+}
+
+impl<T: Sync> SpatialIndex<T> {
+    pub fn par_iter(&self) -> impl ParallelIterator<Item = (&Point, &T)> {
+        self.entries.par_iter().map(|(point, value)| (point, value))
+    }
+}
+
+impl<T: Send + Sync> SpatialIndex<T> {
+    /// Fills an empty index with values on distinct points. The slots are grouped by chunk and every chunk is filled
+    /// on its own thread, so this is the same as inserting the values one by one, only in parallel.
+    pub fn par_fill(&mut self, values: Vec<(Point, T)>) {
+        assert!(self.is_empty(), "only an empty index can be filled");
+        let mut on_grid: Vec<(usize, usize, u32)> = Vec::with_capacity(values.len());
+        for (index, (point, _)) in values.iter().enumerate() {
+            match self.position(point) {
+                Some((chunk, slot)) => on_grid.push((chunk, slot, index as u32)),
+                None => {
+                    let previous = self.outside.insert(*point, index as u32);
+                    debug_assert!(previous.is_none(), "two values on {:?}", point);
+                }
+            }
+        }
+        on_grid.par_sort_unstable();
+        self.chunks.par_iter_mut().enumerate().for_each(|(chunk, slots)| {
+            let start = on_grid.partition_point(|(of, _, _)| *of < chunk);
+            let end = on_grid.partition_point(|(of, _, _)| *of <= chunk);
+            if start == end {
+                return;
+            }
+            let slots = slots.get_or_insert_with(|| vec![VACANT; CHUNK_CELLS].into_boxed_slice());
+            for (_, slot, index) in &on_grid[start..end] {
+                debug_assert_eq!(slots[*slot], VACANT, "two values on one point");
+                slots[*slot] = *index;
+            }
+        });
+        self.entries.par_extend(values);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_find_values_by_point() {
+        let mut index = SpatialIndex::new(100, 10);
+        assert_eq!(index.insert(Point::new(3, 4), 'a'), None);
+        assert_eq!(index.insert(Point::new(40, 99), 'b'), None);
+        assert_eq!(index.insert(Point::new(100, 100), 'c'), None);
+
+        assert_eq!(index.get(&Point::new(3, 4)), Some(&'a'));
+        assert_eq!(index.get(&Point::new(40, 99)), Some(&'b'));
+        assert_eq!(index.get(&Point::new(100, 100)), Some(&'c'));
+        assert_eq!(index.get(&Point::new(4, 3)), None);
+        assert!(!index.contains(&Point::new(99, 40)));
+        assert_eq!(index.len(), 3);
+
+        assert_eq!(index.insert(Point::new(3, 4), 'd'), Some('a'));
+        assert_eq!(index.get_or_insert(Point::new(3, 4), 'e'), &'d');
+        assert_eq!(index.get_or_insert(Point::new(5, 5), 'e'), &'e');
+        assert_eq!(index.len(), 4);
+    }
+
+    #[test]
+    fn should_keep_points_off_the_grid() {
+        let mut index = SpatialIndex::new(10, 10);
+        index.insert(Point::new(-1, 5), 'a');
+        index.insert(Point::new(12, 3), 'b');
+
+        assert_eq!(index.get(&Point::new(-1, 5)), Some(&'a'));
+        assert_eq!(index.remove(&Point::new(12, 3)), Some('b'));
+        assert_eq!(index.get(&Point::new(12, 3)), None);
+        assert_eq!(index.len(), 1);
+    }
+
+    #[test]
+    fn should_keep_finding_values_after_removals() {
+        let mut index = SpatialIndex::new(100, 10);
+        let points: Vec<Point> = (0..50).map(|i| Point::new(i * 2, 100 - i)).collect();
+        for (i, point) in points.iter().enumerate() {
+            index.insert(*point, i);
+        }
+
+        for point in points.iter().step_by(3) {
+            assert!(index.remove(point).is_some());
+        }
+        assert_eq!(index.remove(&points[0]), None);
+
+        for (i, point) in points.iter().enumerate() {
+            let expected = if i % 3 == 0 { None } else { Some(&i) };
+            assert_eq!(index.get(point), expected);
+        }
+        assert_eq!(index.len(), 33);
+        assert_eq!(index.iter().count(), 33);
+    }
+
+    #[test]
+    fn should_find_values_filled_in_parallel() {
+        let mut index = SpatialIndex::new(100, 10);
+        let values: Vec<(Point, usize)> = (0..50).map(|i| (Point::new(i * 2, 100 - i), i as usize)).collect();
+        index.par_fill(values.clone());
+        index.insert(Point::new(-3, 7), 50);
+
+        for (point, i) in &values {
+            assert_eq!(index.get(point), Some(i));
+        }
+        assert_eq!(index.get(&Point::new(-3, 7)), Some(&50));
+        assert_eq!(index.get(&Point::new(1, 1)), None);
+        assert_eq!(index.len(), 51);
+
+        index.clear();
+        index.par_fill(vec![(Point::new(1, 1), 7), (Point::new(200, 1), 8)]);
+        assert_eq!(index.get(&Point::new(1, 1)), Some(&7));
+        assert_eq!(index.get(&Point::new(200, 1)), Some(&8));
+        assert_eq!(index.get(&Point::new(2, 99)), None);
+    }
+
+    #[test]
+    fn should_forget_every_value_when_cleared() {
+        let mut index = SpatialIndex::new(100, 10);
+        index.insert(Point::new(1, 1), 1);
+        index.insert(Point::new(-5, 1), 2);
+        index.clear();
+
+        assert!(index.is_empty());
+        assert_eq!(index.get(&Point::new(1, 1)), None);
+        assert_eq!(index.get(&Point::new(-5, 1)), None);
+        index.insert(Point::new(1, 1), 3);
+        assert_eq!(index.get(&Point::new(1, 1)), Some(&3));
+    }
+}
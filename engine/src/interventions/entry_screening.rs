@@ -0,0 +1,138 @@
+/*
+ * EpiRust
+ * Copyright (c) 2020  ThoughtWorks, Inc.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ */
+
+use common::config::intervention_config::{EntryScreeningConfig, InterventionConfig, ScreeningAction};
+use common::config::Config;
+use common::models::custom_types::Hour;
+use common::utils::RandomWrapper;
+use rand::Rng;
+use uuid::Uuid;
+
+use crate::disease_state_machine::DiseaseStateMachine;
+
+/// Quarantines the infected travellers that screening detects on arrival. Screening that rejects travellers is
+/// applied by the regions sending them, with the restrictions the orchestrator sends along with the ticks.
+pub struct EntryScreening {
+    intervention: Option<EntryScreeningConfig>,
+    quarantined: Vec<(Hour, Uuid)>,
+    quarantined_since_ack: usize,
+}
+
+impl EntryScreening {
+    pub fn get_entry_screening_intervention(config: &Config) -> Option<EntryScreeningConfig> {
+        config
+            .get_interventions()
+            .iter()
+            .filter_map(|i| match i {
+                InterventionConfig::EntryScreening(x) => Some(x),
+                _ => None,
+            })
+            .next()
+            .copied()
+    }
+
+    pub fn init(config: &Config) -> EntryScreening {
+        EntryScreening {
+            intervention: EntryScreening::get_entry_screening_intervention(config),
+            quarantined: Vec::new(),
+            quarantined_since_ack: 0,
+        }
+    }
+
+    /// Screens the arrivals and returns those to quarantine until `until`
+    pub fn screen<'a>(
+        &mut self,
+        arrivals: impl Iterator<Item = (Uuid, &'a DiseaseStateMachine)>,
+        until: Hour,
+        rng: &mut RandomWrapper,
+    ) -> Vec<Uuid> {
+        let detection_rate = match self.intervention {
+            Some(EntryScreeningConfig { detection_rate, action: ScreeningAction::Quarantine }) => detection_rate,
+            _ => return Vec::new(),
+        };
+        let detected: Vec<Uuid> = arrivals
+            .filter(|(_, state_machine)| state_machine.is_infected() && rng.get().gen_bool(detection_rate))
+            .map(|(id, _)| id)
+            .collect();
+        self.quarantined.extend(detected.iter().map(|id| (until, *id)));
+        self.quarantined_since_ack += detected.len();
+        detected
+    }
+
+    /// The quarantined arrivals to release at `hour`
+    pub fn release(&mut self, hour: Hour) -> Vec<Uuid> {
+        if self.quarantined.is_empty() {
+            return Vec::new();
+        }
+        let (released, quarantined) = self.quarantined.drain(..).partition(|(until, _)| *until <= hour);
+        self.quarantined = quarantined;
+        released.into_iter().map(|(_, id)| id).collect()
+    }
+
+    /// Number of arrivals quarantined since this was last called
+    pub fn take_quarantined_count(&mut self) -> usize {
+        std::mem::take(&mut self.quarantined_since_ack)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn screening(action: ScreeningAction) -> EntryScreening {
+        EntryScreening {
+            intervention: Some(EntryScreeningConfig { detection_rate: 1.0, action }),
+            quarantined: Vec::new(),
+            quarantined_since_ack: 0,
+        }
+    }
+
+    fn infected() -> DiseaseStateMachine {
+        let mut state_machine = DiseaseStateMachine::new();
+        state_machine.set_mild_asymptomatic();
+        state_machine
+    }
+
+    #[test]
+    fn should_quarantine_detected_arrivals_until_release() {
+        let mut screening = screening(ScreeningAction::Quarantine);
+        let (sick, healthy) = (Uuid::new_v4(), Uuid::new_v4());
+        let (sick_state, healthy_state) = (infected(), DiseaseStateMachine::new());
+
+        let quarantined =
+            screening.screen(vec![(sick, &sick_state), (healthy, &healthy_state)].into_iter(), 17, &mut RandomWrapper::new());
+
+        assert_eq!(quarantined, vec![sick]);
+        assert_eq!(screening.take_quarantined_count(), 1);
+        assert_eq!(screening.take_quarantined_count(), 0);
+        assert!(screening.release(16).is_empty());
+        assert_eq!(screening.release(17), vec![sick]);
+        assert!(screening.release(18).is_empty());
+    }
+
+    #[test]
+    fn should_leave_rejecting_arrivals_to_the_sending_regions() {
+        let mut screening = screening(ScreeningAction::Reject);
+        let sick_state = infected();
+
+        let quarantined = screening.screen(vec![(Uuid::new_v4(), &sick_state)].into_iter(), 17, &mut RandomWrapper::new());
+
+        assert!(quarantined.is_empty());
+    }
+}
@@ -17,10 +17,12 @@
  *
  */
 
+use crate::interventions::entry_screening::EntryScreening;
 use crate::interventions::hospital::BuildNewHospital;
 use crate::interventions::lockdown::LockdownIntervention;
 use crate::interventions::vaccination::VaccinateIntervention;
 
+pub mod entry_screening;
 pub mod hospital;
 pub mod intervention_type;
 pub mod lockdown;
@@ -30,4 +32,5 @@ pub struct Interventions {
     pub vaccinate: VaccinateIntervention,
     pub lockdown: LockdownIntervention,
     pub build_new_hospital: BuildNewHospital,
+    pub entry_screening: EntryScreening,
 }
@@ -21,8 +21,6 @@ use rdkafka::producer::{BaseRecord, DefaultProducerContext, ThreadedProducer};
 use rdkafka::ClientConfig;
 use std::time::Instant;
 
-use crate::travel::commute::CommutersByRegion;
-use crate::travel::migration::MigratorsByRegion;
 use crate::utils::{environment, SendRecord, SendResult};
 
 const TICK_ACKS_TOPIC: &str = "ticks_ack";
@@ -46,35 +44,26 @@ impl KafkaProducer {
         }
     }
 
-    pub fn send_ack<'a>(&mut self, tick_string: &'a String) -> SendResult<'a> {
-        let record: BaseRecord<String, String> = BaseRecord::to(TICK_ACKS_TOPIC).payload(tick_string);
+    pub fn send_ack<'a>(&mut self, ack: &'a Vec<u8>) -> SendResult<'a, Vec<u8>> {
+        let record: BaseRecord<String, Vec<u8>> = BaseRecord::to(TICK_ACKS_TOPIC).payload(ack);
         self.producer.send(record)
     }
 
-    pub fn send_migrators(&mut self, outgoing: Vec<MigratorsByRegion>) {
-        for out_region in outgoing.iter() {
-            let payload = serde_json::to_string(out_region).unwrap();
-            trace!("Sending migrators: {} to region: {}", payload, out_region.to_engine_id());
-            let topic = &*format!("{}{}", MIGRATION_TOPIC, out_region.to_engine_id());
-            let record: BaseRecord<String, String> = BaseRecord::to(topic).payload(&payload);
-            debug!("sending migrators");
-            let start_time = Instant::now();
-            self.producer.send_record(record);
-            debug!("sent migrators: {}", start_time.elapsed().as_millis());
-        }
+    pub fn send_migrators(&mut self, to_engine_id: &str, payload: &Vec<u8>) {
+        let topic = &*format!("{}{}", MIGRATION_TOPIC, to_engine_id);
+        let record: BaseRecord<String, Vec<u8>> = BaseRecord::to(topic).payload(payload);
+        debug!("sending migrators to region: {}", to_engine_id);
+        let start_time = Instant::now();
+        self.producer.send_record(record);
+        debug!("sent migrators: {}", start_time.elapsed().as_millis());
     }
 
-    pub fn send_commuters(&mut self, outgoing: Vec<CommutersByRegion>) {
-        for out_region in outgoing.iter() {
-            let payload = serde_json::to_string(out_region).unwrap();
-            trace!("Sending commuters: {} to region: {}", payload, out_region.to_engine_id());
-            debug!("Sending commuters: {} to region: {}", out_region.commuters.len(), out_region.to_engine_id());
-            let topic = &*format!("{}{}", COMMUTE_TOPIC, out_region.to_engine_id());
-            let record: BaseRecord<String, String> = BaseRecord::to(topic).payload(&payload);
-            debug!("sending commuters");
-            let start_time = Instant::now();
-            self.producer.send_record(record);
-            debug!("sent commuters: {}", start_time.elapsed().as_millis());
-        }
+    pub fn send_commuters(&mut self, to_engine_id: &str, payload: &Vec<u8>) {
+        let topic = &*format!("{}{}", COMMUTE_TOPIC, to_engine_id);
+        let record: BaseRecord<String, Vec<u8>> = BaseRecord::to(topic).payload(payload);
+        debug!("sending commuters to region: {}", to_engine_id);
+        let start_time = Instant::now();
+        self.producer.send_record(record);
+        debug!("sent commuters: {}", start_time.elapsed().as_millis());
     }
 }
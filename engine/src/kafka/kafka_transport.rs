@@ -0,0 +1,84 @@
+/*
+ * EpiRust
+ * Copyright (c) 2020  ThoughtWorks, Inc.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ */
+
+use common::transport::{EngineTransport, Payload};
+use rdkafka::consumer::StreamConsumer;
+use rdkafka::Message;
+
+use crate::kafka::kafka_producer::{KafkaProducer, COMMUTE_TOPIC, MIGRATION_TOPIC};
+use crate::kafka::{ticks_consumer, travel_consumer};
+
+/// Connects an engine to the orchestrator and the other engines through Kafka topics
+pub struct KafkaTransport {
+    producer: KafkaProducer,
+    ticks_consumer: StreamConsumer,
+    commute_consumer: StreamConsumer,
+    migrators_consumer: StreamConsumer,
+}
+
+impl KafkaTransport {
+    pub fn new(engine_id: &str) -> KafkaTransport {
+        debug!("{}: Start Migrator Consumer", engine_id);
+        let migrators_consumer = travel_consumer::start(engine_id, &[&*format!("{MIGRATION_TOPIC}{engine_id}")], "migrate");
+        debug!("{}: Start Commuter Consumer", engine_id);
+        let commute_consumer = travel_consumer::start(engine_id, &[&*format!("{COMMUTE_TOPIC}{engine_id}")], "commute");
+        let ticks_consumer = ticks_consumer::start(engine_id);
+        KafkaTransport { producer: KafkaProducer::new(), ticks_consumer, commute_consumer, migrators_consumer }
+    }
+
+    async fn receive(consumer: &StreamConsumer) -> Option<Payload> {
+        loop {
+            match consumer.recv().await {
+                Err(e) => debug!("error occured: {}", e),
+                Ok(message) => match message.payload() {
+                    None => debug!("Received an empty message on {}", message.topic()),
+                    Some(payload) => return Some(payload.to_vec()),
+                },
+            }
+        }
+    }
+}
+
+impl EngineTransport for KafkaTransport {
+    async fn receive_tick(&mut self) -> Option<Payload> {
+        Self::receive(&self.ticks_consumer).await
+    }
+
+    fn send_ack(&mut self, ack: Payload) {
+        if let Err(e) = self.producer.send_ack(&ack) {
+            panic!("Failed while sending acknowledgement: {:?}", e.0);
+        }
+    }
+
+    fn send_commuters(&mut self, to_engine_id: &str, commuters: Payload) {
+        self.producer.send_commuters(to_engine_id, &commuters);
+    }
+
+    fn send_migrators(&mut self, to_engine_id: &str, migrators: Payload) {
+        self.producer.send_migrators(to_engine_id, &migrators);
+    }
+
+    async fn receive_commuters(&mut self) -> Option<Payload> {
+        Self::receive(&self.commute_consumer).await
+    }
+
+    async fn receive_migrators(&mut self) -> Option<Payload> {
+        Self::receive(&self.migrators_consumer).await
+    }
+}
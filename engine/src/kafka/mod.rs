@@ -19,5 +19,6 @@
 
 pub mod kafka_consumer;
 pub mod kafka_producer;
+pub mod kafka_transport;
 pub mod ticks_consumer;
 pub mod travel_consumer;
@@ -18,9 +18,7 @@
  */
 
 use rdkafka::consumer::{Consumer, StreamConsumer};
-use rdkafka::error::KafkaResult;
-use rdkafka::message::BorrowedMessage;
-use rdkafka::{ClientConfig, Message};
+use rdkafka::ClientConfig;
 
 use crate::models::events::Tick;
 use crate::utils::environment;
@@ -43,22 +41,8 @@ pub fn start(engine_id: &str) -> StreamConsumer {
     consumer
 }
 
-pub fn read(msg: Option<KafkaResult<BorrowedMessage>>) -> Option<Tick> {
-    match msg {
-        None => {
-            debug!("End of tick stream");
-            None
-        }
-        Some(m) => match m {
-            Err(e) => {
-                debug!("error occured: {}", e);
-                None
-            }
-            Ok(borrowed_message) => {
-                let str_message = borrowed_message.payload_view::<str>().unwrap().unwrap();
-                debug!("Tick Data: {}", str_message);
-                Some(Tick::parse_tick(str_message))
-            }
-        },
-    }
+pub fn read(payload: &[u8]) -> Tick {
+    let str_message = std::str::from_utf8(payload).expect("Tick is not valid UTF-8");
+    debug!("Tick Data: {}", str_message);
+    Tick::parse_tick(str_message)
 }
@@ -17,10 +17,9 @@
  *
  */
 
+use common::transport::wire_format;
 use rdkafka::consumer::{Consumer, StreamConsumer};
-use rdkafka::error::KafkaResult;
-use rdkafka::message::BorrowedMessage;
-use rdkafka::{ClientConfig, Message};
+use rdkafka::ClientConfig;
 
 use crate::travel::commute::CommutersByRegion;
 use crate::travel::migration::MigratorsByRegion;
@@ -45,44 +44,12 @@ pub fn start(engine_id: &str, topics: &[&str], prefix: &str) -> StreamConsumer {
     consumer
 }
 
-pub fn read_commuters(message: Option<KafkaResult<BorrowedMessage>>) -> Option<CommutersByRegion> {
-    match message {
-        None => None,
-        Some(msg) => match msg {
-            Err(e) => {
-                error!("error occurred: {}", e);
-                None
-            }
-            Ok(borrowed_message) => {
-                let str_message = borrowed_message.payload_view::<str>().unwrap().unwrap();
-                trace!("Reading Commute Data: {}", str_message);
-                Some(parse_commuters(str_message))
-            }
-        },
-    }
+pub fn read_commuters(payload: &[u8]) -> CommutersByRegion {
+    trace!("Reading Commute Data: {} bytes", payload.len());
+    wire_format::decode(payload).expect("Could not parse commuters")
 }
 
-fn parse_commuters(message: &str) -> CommutersByRegion {
-    serde_json::from_str(message).expect("Could not parse commuters")
-}
-
-pub fn read_migrators(message: Option<KafkaResult<BorrowedMessage>>) -> Option<MigratorsByRegion> {
-    match message {
-        None => None,
-        Some(msg) => match msg {
-            Err(e) => {
-                debug!("error occured: {}", e);
-                None
-            }
-            Ok(borrowed_message) => {
-                let str_message = borrowed_message.payload_view::<str>().unwrap().unwrap();
-                trace!("Reading Migration Data: {}", str_message);
-                Some(parse_migrators(str_message))
-            }
-        },
-    }
-}
-
-fn parse_migrators(message: &str) -> MigratorsByRegion {
-    serde_json::from_str(message).expect("Could not parse migrators")
+pub fn read_migrators(payload: &[u8]) -> MigratorsByRegion {
+    trace!("Reading Migration Data: {} bytes", payload.len());
+    wire_format::decode(payload).expect("Could not parse migrators")
 }
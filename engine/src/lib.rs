@@ -23,6 +23,8 @@ extern crate serde_derive;
 extern crate log;
 
 mod allocation_map;
+mod calibration;
+mod charts;
 mod citizen;
 mod disease_state_machine;
 mod engine_app;
@@ -33,7 +35,9 @@ mod kafka;
 mod listeners;
 mod models;
 mod population;
+mod run_control;
 mod run_mode;
+mod sinks;
 mod state_machine;
 mod tick;
 mod travel;
@@ -41,6 +45,13 @@ mod utils;
 
 pub mod geography;
 
+#[cfg(any(test, feature = "bench"))]
+#[doc(hidden)]
+pub mod bench;
+
 pub use engine_app::EngineApp;
+pub use models::events::Counts;
+pub use run_control::{RunControl, RunEvent, RunProgress};
 pub use run_mode::RunMode;
 pub use state_machine::*;
+pub use utils::environment::output_dir;
@@ -0,0 +1,209 @@
+/*
+ * EpiRust
+ * Copyright (c) 2020  ThoughtWorks, Inc.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ */
+
+use std::any::Any;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+use common::config::{CitizenStatesExport, CitizenStatesExportMode};
+use common::models::custom_types::{Hour, Percentage};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use fnv::FnvHashMap;
+use uuid::Uuid;
+
+use crate::citizen::Citizen;
+use crate::geography::Point;
+use crate::listeners::listener::Listener;
+use crate::models::events::{CitizenState, CitizenStatesAtHr};
+use crate::utils::environment;
+
+/// Writes the states of a sample of citizens as gzip compressed JSON lines, one line per hour, in the same
+/// shape as the citizen states published to Kafka
+pub struct CitizenStatesWriter {
+    mode: CitizenStatesExportMode,
+    sampling_rate: Percentage,
+    snapshot_interval: Hour,
+    buffer: CitizenStatesAtHr,
+    last_written_states: FnvHashMap<Uuid, String>,
+    encoder: GzEncoder<BufWriter<File>>,
+}
+
+impl CitizenStatesWriter {
+    pub fn new(output_file_prefix: &str, population_size: usize, export: CitizenStatesExport) -> CitizenStatesWriter {
+        if !(0.0..=1.0).contains(&export.sampling_rate) || export.snapshot_interval == 0 {
+            panic!("Invalid citizen states export: {:?}", export);
+        }
+        let output_path = Self::output_path(output_file_prefix);
+        let file = File::create(&output_path).unwrap_or_else(|e| panic!("Failed to create {:?}: {}", output_path, e));
+        let sampled_size = (population_size as f64 * export.sampling_rate) as usize;
+        CitizenStatesWriter {
+            mode: export.mode,
+            sampling_rate: export.sampling_rate,
+            snapshot_interval: export.snapshot_interval,
+            buffer: CitizenStatesAtHr::init(sampled_size),
+            last_written_states: FnvHashMap::default(),
+            encoder: GzEncoder::new(BufWriter::new(file), Compression::default()),
+        }
+    }
+
+    fn output_path(output_file_prefix: &str) -> PathBuf {
+        let mut output_path = environment::output_dir();
+        output_path.push(format!("{}_citizen_states.jsonl.gz", output_file_prefix));
+        output_path
+    }
+
+    // uuids are random, so the same citizens are followed for the whole simulation
+    fn is_sampled(&self, id: &Uuid) -> bool {
+        (id.as_u128() % 10_000) as f64 / 10_000.0 < self.sampling_rate
+    }
+
+    fn flush_buffer(&mut self) {
+        if !self.buffer.citizen_states.is_empty() {
+            let line = serde_json::to_string(&self.buffer).expect("Failed to serialize citizen states");
+            if let Err(e) = writeln!(self.encoder, "{}", line) {
+                error!("Failed to write citizen states: {}", e);
+            }
+        }
+        self.buffer.citizen_states.clear();
+    }
+}
+
+impl Listener for CitizenStatesWriter {
+    fn simulation_ended(&mut self) {
+        self.flush_buffer();
+        if let Err(e) = self.encoder.try_finish().and_then(|_| self.encoder.get_mut().flush()) {
+            error!("Failed to finish writing citizen states: {}", e);
+        }
+    }
+
+    fn citizen_state_updated(&mut self, hr: Hour, citizen: &Citizen, location: &Point) {
+        if !self.is_sampled(&citizen.id) {
+            return;
+        }
+        if self.buffer.hr < hr {
+            self.flush_buffer();
+            self.buffer.hr = hr;
+        }
+        match self.mode {
+            CitizenStatesExportMode::Snapshot => {
+                if hr.is_multiple_of(self.snapshot_interval) {
+                    self.buffer.update(citizen, location);
+                }
+            }
+            CitizenStatesExportMode::Delta => {
+                let state = CitizenState::state_str(citizen.state_machine.state);
+                if self.last_written_states.get(&citizen.id) != Some(&state) {
+                    self.last_written_states.insert(citizen.id, state);
+                    self.buffer.update(citizen, location);
+                }
+            }
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use common::utils::RandomWrapper;
+    use flate2::read::GzDecoder;
+
+    use super::*;
+    use crate::citizen::WorkStatus;
+    use crate::geography::Area;
+    use crate::sinks::test_utils::temp_prefix;
+
+    fn citizen() -> Citizen {
+        let area = Area::new(&"sim_id".to_string(), Point::new(0, 0), Point::new(1, 1));
+        Citizen::new(area, area, Point::new(2, 2), true, WorkStatus::Normal, &mut RandomWrapper::new())
+    }
+
+    fn read_lines(prefix: &str) -> Vec<serde_json::Value> {
+        // the prefix is an absolute path in the temp dir, which takes the place of the output dir
+        let file_name = CitizenStatesWriter::output_path(prefix);
+        let mut contents = String::new();
+        GzDecoder::new(File::open(&file_name).unwrap()).read_to_string(&mut contents).unwrap();
+        std::fs::remove_file(file_name).unwrap();
+        contents.lines().map(|line| serde_json::from_str(line).unwrap()).collect()
+    }
+
+    #[test]
+    fn should_write_only_state_changes_in_delta_mode() {
+        let prefix = temp_prefix("citizen_states_delta");
+        let export = CitizenStatesExport { mode: CitizenStatesExportMode::Delta, sampling_rate: 1.0, snapshot_interval: 1 };
+        let mut writer = CitizenStatesWriter::new(&prefix, 2, export);
+        let (first, mut second) = (citizen(), citizen());
+
+        writer.citizen_state_updated(1, &first, &Point::new(1, 1));
+        writer.citizen_state_updated(1, &second, &Point::new(2, 2));
+        writer.citizen_state_updated(2, &first, &Point::new(1, 2));
+        second.state_machine.expose(2);
+        writer.citizen_state_updated(2, &second, &Point::new(2, 3));
+        writer.simulation_ended();
+
+        let lines = read_lines(&prefix);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["hr"], 1);
+        assert_eq!(lines[0]["citizen_states"].as_array().unwrap().len(), 2);
+        assert_eq!(lines[1]["hr"], 2);
+        assert_eq!(lines[1]["citizen_states"][0]["citizen_id"], second.id.to_string());
+        assert_eq!(lines[1]["citizen_states"][0]["state"], "e");
+    }
+
+    #[test]
+    fn should_write_snapshots_at_interval() {
+        let prefix = temp_prefix("citizen_states_snapshot");
+        let export = CitizenStatesExport { mode: CitizenStatesExportMode::Snapshot, sampling_rate: 1.0, snapshot_interval: 2 };
+        let mut writer = CitizenStatesWriter::new(&prefix, 1, export);
+        let citizen = citizen();
+
+        for hr in 1..=4 {
+            writer.citizen_state_updated(hr, &citizen, &Point::new(1, 1));
+        }
+        writer.simulation_ended();
+
+        let hours: Vec<u64> = read_lines(&prefix).iter().map(|line| line["hr"].as_u64().unwrap()).collect();
+        assert_eq!(hours, vec![2, 4]);
+    }
+
+    #[test]
+    fn should_not_write_unsampled_citizens() {
+        let prefix = temp_prefix("citizen_states_sampling");
+        let export = CitizenStatesExport { mode: CitizenStatesExportMode::Snapshot, sampling_rate: 0.0, snapshot_interval: 1 };
+        let mut writer = CitizenStatesWriter::new(&prefix, 1, export);
+
+        writer.citizen_state_updated(1, &citizen(), &Point::new(1, 1));
+        writer.simulation_ended();
+
+        assert!(read_lines(&prefix).is_empty());
+    }
+
+    #[test]
+    fn should_write_to_the_output_dir() {
+        let output_path = CitizenStatesWriter::output_path("run");
+
+        assert_eq!(output_path, environment::output_dir().join("run_citizen_states.jsonl.gz"));
+    }
+}
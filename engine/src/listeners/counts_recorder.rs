@@ -0,0 +1,48 @@
+/*
+ * EpiRust
+ * Copyright (c) 2020  ThoughtWorks, Inc.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ */
+
+use std::any::Any;
+
+use crate::listeners::listener::Listener;
+use crate::models::events::Counts;
+
+/// Keeps the hourly counts in memory without writing anything, for runs whose output is consumed in-process
+pub struct CountsRecorder {
+    counts: Vec<Counts>,
+}
+
+impl CountsRecorder {
+    pub fn new() -> CountsRecorder {
+        CountsRecorder { counts: Vec::new() }
+    }
+
+    pub fn get_counts(&self) -> &Vec<Counts> {
+        &self.counts
+    }
+}
+
+impl Listener for CountsRecorder {
+    fn counts_updated(&mut self, counts: Counts) {
+        self.counts.push(counts);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
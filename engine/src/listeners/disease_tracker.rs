@@ -18,31 +18,198 @@
  */
 
 use fnv::FnvHashMap;
+use plotters::prelude::*;
 use std::any::Any;
+use std::error::Error;
+use std::path::PathBuf;
 
-use crate::geography::Point;
+use common::models::custom_types::{CoOrdinate, Day, Hour};
+
+use crate::geography::{Area, AreaType, Grid, Point};
+use crate::listeners::csv_service;
 use crate::listeners::listener::Listener;
-use crate::models::events::Counts;
+use crate::listeners::visualisation;
+use crate::models::constants;
+use crate::models::events::{Counts, InfectionEvent};
+use crate::utils::environment;
+
+#[derive(Serialize, Debug, PartialEq)]
+struct CellInfections {
+    x: CoOrdinate,
+    y: CoOrdinate,
+    area: AreaType,
+    infections: i32,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+struct BuildingInfections {
+    building: AreaType,
+    start_x: CoOrdinate,
+    start_y: CoOrdinate,
+    end_x: CoOrdinate,
+    end_y: CoOrdinate,
+    infections: i32,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+struct AreaInfections {
+    area: AreaType,
+    infections: i32,
+}
 
 pub struct Hotspot {
+    output_file_prefix: String,
+    daily_snapshots: bool,
+    // only the layout of the areas, to place infections in buildings and areas
+    grid: Option<Grid>,
     disease_hotspot_tracker: FnvHashMap<Point, i32>,
+    infections_today: FnvHashMap<Point, i32>,
+    last_hour: Hour,
 }
 
 impl Hotspot {
-    pub fn new() -> Hotspot {
+    pub fn new(output_file_prefix: String, daily_snapshots: bool) -> Hotspot {
         let disease_hotspot_tracker = FnvHashMap::default();
-        Hotspot { disease_hotspot_tracker }
+        Hotspot {
+            output_file_prefix,
+            daily_snapshots,
+            grid: None,
+            disease_hotspot_tracker,
+            infections_today: FnvHashMap::default(),
+            last_hour: 0,
+        }
+    }
+
+    fn output_path(&self, suffix: &str) -> PathBuf {
+        let mut output_path = environment::output_dir();
+        output_path.push(format!("{}_{}", self.output_file_prefix, suffix));
+        output_path
+    }
+
+    fn area_type_of(&self, point: &Point) -> AreaType {
+        self.grid.as_ref().map_or(AreaType::Other, |grid| grid.area_type_of(point))
+    }
+
+    fn by_cell(&self, tracker: &FnvHashMap<Point, i32>) -> Vec<CellInfections> {
+        let mut cells: Vec<CellInfections> = tracker
+            .iter()
+            .map(|(point, infections)| CellInfections {
+                x: point.x,
+                y: point.y,
+                area: self.area_type_of(point),
+                infections: *infections,
+            })
+            .collect();
+        cells.sort_by_key(|c| (c.x, c.y));
+        cells
+    }
+
+    fn by_building(&self, grid: &Grid) -> Vec<BuildingInfections> {
+        let mut buildings: FnvHashMap<Area, i32> = FnvHashMap::default();
+        for (point, infections) in &self.disease_hotspot_tracker {
+            if let Some(building) = grid.building_of(point) {
+                *buildings.entry(building).or_insert(0) += infections;
+            }
+        }
+        let mut buildings: Vec<BuildingInfections> = buildings
+            .into_iter()
+            .map(|(building, infections)| BuildingInfections {
+                building: grid.area_type_of(&building.start_offset),
+                start_x: building.start_offset.x,
+                start_y: building.start_offset.y,
+                end_x: building.end_offset.x,
+                end_y: building.end_offset.y,
+                infections,
+            })
+            .collect();
+        buildings.sort_by_key(|b| (-b.infections, b.start_x, b.start_y));
+        buildings
+    }
+
+    fn by_area(&self, grid: &Grid) -> Vec<AreaInfections> {
+        [AreaType::Home, AreaType::Transport, AreaType::Work, AreaType::Hospital, AreaType::Other]
+            .iter()
+            .map(|area| AreaInfections {
+                area: *area,
+                infections: self
+                    .disease_hotspot_tracker
+                    .iter()
+                    .filter(|(point, _)| grid.area_type_of(point) == *area)
+                    .map(|(_, infections)| infections)
+                    .sum(),
+            })
+            .collect()
+    }
+
+    fn draw_heatmap(&self, grid: &Grid, output_path: &PathBuf) -> Result<(), Box<dyn Error>> {
+        let scale = visualisation::scale_of(grid);
+        let size = grid.grid_size * scale as u32;
+        let mut backend = BitMapBackend::new(output_path, (size, size));
+        visualisation::draw_areas(&mut backend, grid, scale)?;
+
+        let max = self.disease_hotspot_tracker.values().copied().max().unwrap_or(1) as f64;
+        for (point, infections) in &self.disease_hotspot_tracker {
+            let intensity = *infections as f64 / max;
+            let color = RGBColor(255, (220.0 * (1.0 - intensity)) as u8, 0);
+            let cell = Area { location_id: grid.housing_area.location_id, start_offset: *point, end_offset: *point };
+            visualisation::fill_cells(&mut backend, &cell, scale, &color)?;
+        }
+        backend.present()?;
+        Ok(())
+    }
+
+    fn write_snapshot(&mut self, day: Day) {
+        let snapshot = self.by_cell(&self.infections_today);
+        let output_path = self.output_path(&format!("hotspots_day_{day}.csv"));
+        if let Err(e) = csv_service::write(&output_path, &snapshot) {
+            error!("Failed to write hotspot snapshot for day {}: {}", day, e);
+        }
+        self.infections_today.clear();
     }
 }
 
 impl Listener for Hotspot {
-    fn counts_updated(&mut self, _counts: Counts) {}
+    fn counts_updated(&mut self, counts: Counts) {
+        self.last_hour = counts.get_hour();
+        if self.daily_snapshots && counts.get_hour().is_multiple_of(constants::HOURS_IN_A_DAY) {
+            self.write_snapshot(counts.get_hour() / constants::HOURS_IN_A_DAY);
+        }
+    }
+
+    fn simulation_ended(&mut self) {
+        // a simulation ending partway through a day still gets a snapshot of it
+        if self.daily_snapshots && !self.last_hour.is_multiple_of(constants::HOURS_IN_A_DAY) {
+            self.write_snapshot(self.last_hour.div_ceil(constants::HOURS_IN_A_DAY));
+        }
 
-    fn simulation_ended(&mut self) {}
+        let cells = self.by_cell(&self.disease_hotspot_tracker);
+        if let Err(e) = csv_service::write(&self.output_path("hotspots.csv"), &cells) {
+            error!("Failed to write hotspots: {}", e);
+        }
+
+        if let Some(grid) = &self.grid {
+            if let Err(e) = csv_service::write(&self.output_path("hotspots_by_building.csv"), &self.by_building(grid)) {
+                error!("Failed to write hotspots by building: {}", e);
+            }
+            if let Err(e) = csv_service::write(&self.output_path("hotspots_by_area.csv"), &self.by_area(grid)) {
+                error!("Failed to write hotspots by area: {}", e);
+            }
+            if let Err(e) = self.draw_heatmap(grid, &self.output_path("hotspots.png")) {
+                error!("Failed to draw hotspot heatmap: {}", e);
+            }
+        }
+    }
 
-    fn citizen_got_infected(&mut self, cell: &Point) {
-        let counter = self.disease_hotspot_tracker.entry(*cell).or_insert(0);
+    fn citizen_got_infected(&mut self, infection: &InfectionEvent) {
+        let counter = self.disease_hotspot_tracker.entry(infection.location).or_insert(0);
         *counter += 1;
+        if self.daily_snapshots {
+            *self.infections_today.entry(infection.location).or_insert(0) += 1;
+        }
+    }
+
+    fn grid_updated(&mut self, grid: &Grid) {
+        self.grid = Some(grid.layout());
     }
 
     fn as_any(&self) -> &dyn Any {
@@ -52,34 +219,85 @@ impl Listener for Hotspot {
 
 #[cfg(test)]
 mod tests {
-    use crate::geography::Point;
-    use crate::listeners::disease_tracker::Hotspot;
+    use crate::geography::{define_geography, AreaType, Point};
+    use crate::listeners::disease_tracker::{AreaInfections, Hotspot};
     use crate::listeners::listener::Listener;
+    use crate::models::events::{Counts, InfectionEvent};
+    use uuid::Uuid;
+
+    fn infection_at(location: Point) -> InfectionEvent {
+        InfectionEvent::new(1, Uuid::new_v4(), Uuid::new_v4(), location, AreaType::Home)
+    }
 
     #[test]
     fn should_initialize() {
-        let tracker = Hotspot::new();
+        let tracker = Hotspot::new("test".to_string(), false);
         assert_eq!(tracker.disease_hotspot_tracker.len(), 0);
     }
 
     #[test]
     fn should_add_new_entry() {
-        let mut tracker = Hotspot::new();
+        let mut tracker = Hotspot::new("test".to_string(), false);
         let current_point = Point::new(0, 1);
 
-        tracker.citizen_got_infected(&current_point);
+        tracker.citizen_got_infected(&infection_at(current_point));
 
         assert_eq!(*tracker.disease_hotspot_tracker.get(&current_point).unwrap(), 1);
     }
 
     #[test]
     fn should_update_tracker() {
-        let mut tracker = Hotspot::new();
+        let mut tracker = Hotspot::new("test".to_string(), false);
         let current_point = Point::new(0, 1);
 
-        tracker.citizen_got_infected(&current_point);
-        tracker.citizen_got_infected(&current_point);
+        tracker.citizen_got_infected(&infection_at(current_point));
+        tracker.citizen_got_infected(&infection_at(current_point));
 
         assert_eq!(*tracker.disease_hotspot_tracker.get(&current_point).unwrap(), 2);
     }
+
+    #[test]
+    fn should_write_the_last_partial_day_as_a_snapshot() {
+        let prefix = std::env::temp_dir().join(format!("hotspot_snapshot_test_{}", std::process::id()));
+        let prefix = prefix.to_str().unwrap().to_string();
+        let mut tracker = Hotspot::new(prefix.clone(), true);
+
+        tracker.citizen_got_infected(&infection_at(Point::new(2, 3)));
+        tracker.counts_updated(Counts::new_test(24, 99, 1, 0, 0, 0, 0));
+        tracker.citizen_got_infected(&infection_at(Point::new(4, 5)));
+        tracker.counts_updated(Counts::new_test(30, 98, 2, 0, 0, 0, 0));
+        tracker.simulation_ended();
+
+        let day_1 = std::fs::read_to_string(format!("{}_hotspots_day_1.csv", prefix)).unwrap();
+        let day_2 = std::fs::read_to_string(format!("{}_hotspots_day_2.csv", prefix)).unwrap();
+        assert!(day_1.contains("2,3,"));
+        assert!(day_2.contains("4,5,") && !day_2.contains("2,3,"));
+        assert!(tracker.infections_today.is_empty());
+        for suffix in ["hotspots_day_1.csv", "hotspots_day_2.csv", "hotspots.csv"] {
+            std::fs::remove_file(format!("{}_{}", prefix, suffix)).unwrap();
+        }
+    }
+
+    #[test]
+    fn should_aggregate_infections_by_building_and_area() {
+        let grid = define_geography(100, "engine1".to_string());
+        let mut tracker = Hotspot::new("test".to_string(), false);
+        tracker.grid_updated(&grid);
+
+        tracker.citizen_got_infected(&infection_at(Point::new(4, 6)));
+        tracker.citizen_got_infected(&infection_at(Point::new(5, 7)));
+        tracker.citizen_got_infected(&infection_at(Point::new(61, 13)));
+        tracker.citizen_got_infected(&infection_at(Point::new(45, 5)));
+
+        let buildings = tracker.by_building(&grid);
+        assert_eq!(buildings.len(), 2);
+        assert_eq!((buildings[0].building, buildings[0].start_x, buildings[0].start_y), (AreaType::Home, 4, 6));
+        assert_eq!(buildings[0].infections, 2);
+        assert_eq!((buildings[1].building, buildings[1].infections), (AreaType::Work, 1));
+
+        let areas = tracker.by_area(&grid);
+        assert_eq!(areas[0], AreaInfections { area: AreaType::Home, infections: 2 });
+        assert_eq!(areas[1], AreaInfections { area: AreaType::Transport, infections: 1 });
+        assert_eq!(areas[2], AreaInfections { area: AreaType::Work, infections: 1 });
+    }
 }
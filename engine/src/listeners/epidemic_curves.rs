@@ -0,0 +1,132 @@
+/*
+ * EpiRust
+ * Copyright (c) 2020  ThoughtWorks, Inc.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ */
+
+use std::any::Any;
+
+use common::config::ChartFormat;
+use common::models::custom_types::Hour;
+
+use crate::charts::{self, day_of, series_of, Marker, Panel};
+use crate::interventions::intervention_type::InterventionType;
+use crate::listeners::listener::Listener;
+use crate::models::events::Counts;
+use crate::utils::environment;
+
+/// Draws the disease state curves at the end of the run, marking when interventions started and ended
+pub struct EpidemicCurves {
+    output_file_prefix: String,
+    format: ChartFormat,
+    counts: Vec<Counts>,
+    markers: Vec<Marker>,
+}
+
+impl EpidemicCurves {
+    pub fn new(output_file_prefix: String, format: ChartFormat) -> EpidemicCurves {
+        EpidemicCurves { output_file_prefix, format, counts: Vec::new(), markers: Vec::new() }
+    }
+
+    fn marker_label(intervention: &dyn InterventionType) -> String {
+        let data: serde_json::Value = serde_json::from_str(&intervention.json_data()).unwrap_or_default();
+        match data["status"].as_str() {
+            Some("locked_down") => format!("{} start", intervention.name()),
+            Some("lockdown_revoked") => format!("{} end", intervention.name()),
+            _ => intervention.name(),
+        }
+    }
+
+    fn panels(&self) -> Vec<Panel> {
+        vec![
+            Panel::new(
+                "Disease states",
+                vec![
+                    series_of("Susceptible", charts::SUSCEPTIBLE, &self.counts, Counts::get_susceptible),
+                    series_of("Exposed", charts::EXPOSED, &self.counts, Counts::get_exposed),
+                    series_of("Infected", charts::INFECTED, &self.counts, Counts::get_infected),
+                    series_of("Recovered", charts::RECOVERED, &self.counts, Counts::get_recovered),
+                ],
+            ),
+            Panel::new(
+                "Hospitalisations and deaths",
+                vec![
+                    series_of("Hospitalized", charts::HOSPITALIZED, &self.counts, Counts::get_hospitalized),
+                    series_of("Deceased", charts::DECEASED, &self.counts, Counts::get_deceased),
+                ],
+            ),
+        ]
+    }
+}
+
+impl Listener for EpidemicCurves {
+    fn counts_updated(&mut self, counts: Counts) {
+        self.counts.push(counts);
+    }
+
+    fn simulation_ended(&mut self) {
+        let mut output_path = environment::output_dir();
+        output_path.push(format!("{}_curves.{}", self.output_file_prefix, self.format.extension()));
+        if let Err(e) = charts::draw(&output_path, self.format, &self.panels(), &self.markers) {
+            error!("Failed to draw epidemic curves: {}", e);
+        }
+    }
+
+    fn intervention_applied(&mut self, at_hour: Hour, intervention: &dyn InterventionType) {
+        self.markers.push(Marker { day: day_of(at_hour as f64), label: Self::marker_label(intervention) });
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sinks::test_utils::temp_prefix;
+
+    struct Lockdown(&'static str);
+
+    impl InterventionType for Lockdown {
+        fn name(&self) -> String {
+            "lockdown".to_string()
+        }
+
+        fn json_data(&self) -> String {
+            format!(r#"{{"status": "{}"}}"#, self.0)
+        }
+    }
+
+    #[test]
+    fn should_mark_lockdown_start_and_end() {
+        let prefix = temp_prefix("curves");
+        let mut curves = EpidemicCurves::new(prefix.clone(), ChartFormat::Png);
+
+        curves.counts_updated(Counts::new_test(1, 9, 1, 0, 0, 0, 0));
+        curves.intervention_applied(48, &Lockdown("locked_down"));
+        curves.intervention_applied(96, &Lockdown("lockdown_revoked"));
+        curves.counts_updated(Counts::new_test(2, 8, 1, 1, 0, 0, 0));
+        curves.simulation_ended();
+
+        let labels: Vec<&str> = curves.markers.iter().map(|m| m.label.as_str()).collect();
+        assert_eq!(labels, vec!["lockdown start", "lockdown end"]);
+        assert_eq!(curves.markers[1].day, 4.0);
+        let file_name = format!("{prefix}_curves.png");
+        assert!(std::path::Path::new(&file_name).exists());
+        std::fs::remove_file(file_name).unwrap();
+    }
+}
@@ -0,0 +1,245 @@
+/*
+ * EpiRust
+ * Copyright (c) 2020  ThoughtWorks, Inc.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ */
+
+use std::any::Any;
+use std::fs::File;
+
+use common::models::custom_types::{Count, Day, Hour};
+use fnv::FnvHashMap;
+use uuid::Uuid;
+
+use crate::listeners::csv_service;
+use crate::listeners::listener::Listener;
+use crate::models::constants;
+use crate::models::events::{Counts, InfectionEvent};
+use crate::utils::environment;
+
+#[derive(Serialize, Debug, PartialEq)]
+struct DailyMetrics {
+    day: Day,
+    incidence: Count,
+    cumulative_incidence: Count,
+    attack_rate: f64,
+    rt: Option<f64>,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+struct EpidemicSummary {
+    total_infections: Count,
+    attack_rate: f64,
+    peak_incidence_day: Option<Day>,
+    peak_incidence: Count,
+    peak_prevalence_hour: Hour,
+    peak_prevalence: Count,
+    doubling_time_days: Option<f64>,
+}
+
+#[derive(Default, Clone, Copy)]
+struct DayTally {
+    incidence: Count,
+    attack_rate: f64,
+}
+
+/// Derives incidence, attack rate and the reproduction number by infection cohort from infection events.
+/// Rt of the most recent cohorts is biased low, as their infectors may not have finished spreading yet.
+pub struct EpidemicMetrics {
+    output_file_prefix: String,
+    days: Vec<DayTally>,
+    infection_day: FnvHashMap<Uuid, Day>,
+    secondary_infections: FnvHashMap<Uuid, Count>,
+    peak_prevalence: (Hour, Count),
+}
+
+impl EpidemicMetrics {
+    pub fn new(output_file_prefix: String) -> EpidemicMetrics {
+        EpidemicMetrics {
+            output_file_prefix,
+            days: Vec::new(),
+            infection_day: FnvHashMap::default(),
+            secondary_infections: FnvHashMap::default(),
+            peak_prevalence: (0, 0),
+        }
+    }
+
+    fn day_of(hour: Hour) -> Day {
+        (hour.max(1) - 1) / constants::HOURS_IN_A_DAY + 1
+    }
+
+    fn tally_for(&mut self, day: Day) -> &mut DayTally {
+        if self.days.len() < day as usize {
+            let last = self.days.last().copied().unwrap_or_default();
+            self.days.resize(day as usize, DayTally { incidence: 0, attack_rate: last.attack_rate });
+        }
+        &mut self.days[day as usize - 1]
+    }
+
+    fn daily_metrics(&self) -> Vec<DailyMetrics> {
+        let mut cohort_sizes = vec![0; self.days.len()];
+        let mut cohort_secondary = vec![0; self.days.len()];
+        for (infectee, day) in &self.infection_day {
+            let index = *day as usize - 1;
+            cohort_sizes[index] += 1;
+            cohort_secondary[index] += self.secondary_infections.get(infectee).copied().unwrap_or(0);
+        }
+
+        let mut cumulative_incidence = 0;
+        self.days
+            .iter()
+            .enumerate()
+            .map(|(index, tally)| {
+                cumulative_incidence += tally.incidence;
+                let rt = match cohort_sizes[index] {
+                    0 => None,
+                    size => Some(cohort_secondary[index] as f64 / size as f64),
+                };
+                DailyMetrics {
+                    day: index as Day + 1,
+                    incidence: tally.incidence,
+                    cumulative_incidence,
+                    attack_rate: tally.attack_rate,
+                    rt,
+                }
+            })
+            .collect()
+    }
+
+    fn summary(&self, daily: &[DailyMetrics]) -> EpidemicSummary {
+        let peak = daily.iter().filter(|d| d.incidence > 0).max_by_key(|d| (d.incidence, std::cmp::Reverse(d.day)));
+        EpidemicSummary {
+            total_infections: daily.last().map_or(0, |d| d.cumulative_incidence),
+            attack_rate: daily.last().map_or(0.0, |d| d.attack_rate),
+            peak_incidence_day: peak.map(|d| d.day),
+            peak_incidence: peak.map_or(0, |d| d.incidence),
+            peak_prevalence_hour: self.peak_prevalence.0,
+            peak_prevalence: self.peak_prevalence.1,
+            doubling_time_days: peak.and_then(|p| doubling_time(&daily[..p.day as usize])),
+        }
+    }
+}
+
+/// Log-linear least squares fit of daily incidence over the growth phase
+fn doubling_time(growth_phase: &[DailyMetrics]) -> Option<f64> {
+    let points: Vec<(f64, f64)> =
+        growth_phase.iter().filter(|d| d.incidence > 0).map(|d| (d.day as f64, (d.incidence as f64).ln())).collect();
+    if points.len() < 2 {
+        return None;
+    }
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|p| p.0).sum::<f64>() / n;
+    let mean_y = points.iter().map(|p| p.1).sum::<f64>() / n;
+    let covariance: f64 = points.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+    let variance: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    let growth_rate = covariance / variance;
+    if growth_rate > 0.0 {
+        Some(std::f64::consts::LN_2 / growth_rate)
+    } else {
+        None
+    }
+}
+
+impl Listener for EpidemicMetrics {
+    fn counts_updated(&mut self, counts: Counts) {
+        let population = counts.total();
+        let attack_rate = if population == 0 { 0.0 } else { 1.0 - counts.get_susceptible() as f64 / population as f64 };
+        self.tally_for(Self::day_of(counts.get_hour())).attack_rate = attack_rate;
+
+        let prevalence = counts.get_infected() + counts.get_hospitalized();
+        if prevalence > self.peak_prevalence.1 {
+            self.peak_prevalence = (counts.get_hour(), prevalence);
+        }
+    }
+
+    fn simulation_ended(&mut self) {
+        let daily = self.daily_metrics();
+        let summary = self.summary(&daily);
+
+        let mut output_path = environment::output_dir();
+        output_path.push(format!("{}_metrics.csv", self.output_file_prefix));
+        if let Err(e) = csv_service::write(&output_path, &daily) {
+            error!("Failed to write epidemic metrics: {}", e);
+        }
+
+        let mut output_path = environment::output_dir();
+        output_path.push(format!("{}_summary.json", self.output_file_prefix));
+        let file = File::create(output_path).expect("Failed to create epidemic summary file");
+        serde_json::to_writer_pretty(file, &summary).expect("Failed to serialize epidemic summary");
+    }
+
+    fn citizen_got_infected(&mut self, infection: &InfectionEvent) {
+        let day = Self::day_of(infection.hour);
+        self.tally_for(day).incidence += 1;
+        self.infection_day.insert(infection.infectee, day);
+        *self.secondary_infections.entry(infection.infector).or_insert(0) += 1;
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geography::{AreaType, Point};
+
+    fn infect(metrics: &mut EpidemicMetrics, hour: Hour, infector: Uuid) -> Uuid {
+        let infectee = Uuid::new_v4();
+        metrics.citizen_got_infected(&InfectionEvent::new(hour, infector, infectee, Point::new(0, 0), AreaType::Home));
+        infectee
+    }
+
+    #[test]
+    fn should_calculate_incidence_and_rt_by_cohort() {
+        let mut metrics = EpidemicMetrics::new("test".to_string());
+        let seed = Uuid::new_v4();
+        let first = infect(&mut metrics, 5, seed);
+        let second = infect(&mut metrics, 24, seed);
+        metrics.counts_updated(Counts::new_test(24, 6, 2, 0, 0, 0, 0));
+        infect(&mut metrics, 30, first);
+        infect(&mut metrics, 31, first);
+        metrics.counts_updated(Counts::new_test(48, 4, 2, 2, 0, 0, 0));
+        infect(&mut metrics, 50, second);
+
+        let daily = metrics.daily_metrics();
+
+        assert_eq!(daily.len(), 3);
+        assert_eq!(daily[0], DailyMetrics { day: 1, incidence: 2, cumulative_incidence: 2, attack_rate: 0.25, rt: Some(1.5) });
+        assert_eq!(daily[1], DailyMetrics { day: 2, incidence: 2, cumulative_incidence: 4, attack_rate: 0.5, rt: Some(0.0) });
+        assert_eq!(daily[2].rt, Some(0.0));
+        assert_eq!(daily[2].attack_rate, 0.5);
+    }
+
+    #[test]
+    fn should_calculate_doubling_time_until_peak() {
+        let mut metrics = EpidemicMetrics::new("test".to_string());
+        for (day, incidence) in [1, 2, 4, 8, 3].iter().enumerate() {
+            for _ in 0..*incidence {
+                infect(&mut metrics, day as Hour * 24 + 1, Uuid::new_v4());
+            }
+        }
+
+        let daily = metrics.daily_metrics();
+        let summary = metrics.summary(&daily);
+
+        assert_eq!(summary.peak_incidence_day, Some(4));
+        assert_eq!(summary.peak_incidence, 8);
+        assert_eq!(summary.total_infections, 18);
+        assert!((summary.doubling_time_days.unwrap() - 1.0).abs() < 1e-9);
+    }
+}
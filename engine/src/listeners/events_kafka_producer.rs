@@ -98,7 +98,7 @@ impl Listener for EventsKafkaProducer {
         self.citizen_states_buffer.update(citizen, location);
     }
 
-    fn grid_updated(&self, grid: &Grid) {
+    fn grid_updated(&mut self, grid: &Grid) {
         if self.enable_citizen_state_messages {
             let message = serde_json::to_string(grid);
             match message {
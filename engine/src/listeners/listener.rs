@@ -23,15 +23,15 @@ use std::any::Any;
 use crate::citizen::Citizen;
 use crate::geography::{Grid, Point};
 use crate::interventions::intervention_type::InterventionType;
-use crate::models::events::Counts;
+use crate::models::events::{Counts, InfectionEvent};
 use crate::travel::migration::MigratorsByRegion;
 
 pub trait Listener {
     fn counts_updated(&mut self, _counts: Counts) {}
     fn simulation_ended(&mut self) {}
-    fn citizen_got_infected(&mut self, _cell: &Point) {}
+    fn citizen_got_infected(&mut self, _infection: &InfectionEvent) {}
     fn citizen_state_updated(&mut self, _hr: Hour, _citizen: &Citizen, _location: &Point) {}
-    fn grid_updated(&self, _grid: &Grid) {}
+    fn grid_updated(&mut self, _grid: &Grid) {}
     fn intervention_applied(&mut self, _at_hour: Hour, _intervention: &dyn InterventionType) {}
     fn outgoing_migrators_added(&mut self, _hr: Hour, _travellers: &[MigratorsByRegion]) {}
     fn as_any(&self) -> &dyn Any;
@@ -45,6 +45,14 @@ impl Listeners {
     pub fn from(listeners: Vec<Box<dyn Listener>>) -> Listeners {
         Listeners { listeners }
     }
+
+    pub fn push(&mut self, listener: Box<dyn Listener>) {
+        self.listeners.push(listener);
+    }
+
+    pub fn find<L: Listener + 'static>(&self) -> Option<&L> {
+        self.listeners.iter().find_map(|listener| listener.as_any().downcast_ref::<L>())
+    }
 }
 
 impl Listeners {
@@ -56,8 +64,8 @@ impl Listeners {
         self.listeners.iter_mut().for_each(|listener| listener.simulation_ended());
     }
 
-    pub fn citizen_got_infected(&mut self, cell: &Point) {
-        self.listeners.iter_mut().for_each(|listener| listener.citizen_got_infected(cell));
+    pub fn citizen_got_infected(&mut self, infection: &InfectionEvent) {
+        self.listeners.iter_mut().for_each(|listener| listener.citizen_got_infected(infection));
     }
 
     pub fn citizen_state_updated(&mut self, hr: Hour, citizen: &Citizen, location: &Point) {
@@ -66,8 +74,8 @@ impl Listeners {
         })
     }
 
-    pub fn grid_updated(&self, grid: &Grid) {
-        self.listeners.iter().for_each(|l| l.grid_updated(grid))
+    pub fn grid_updated(&mut self, grid: &Grid) {
+        self.listeners.iter_mut().for_each(|l| l.grid_updated(grid))
     }
 
     pub fn intervention_applied(&mut self, _at_hour: Hour, _intervention: &dyn InterventionType) {
@@ -83,9 +91,10 @@ impl Listeners {
 mod tests {
     use std::any::Any;
 
-    use crate::geography::Point;
+    use crate::geography::{AreaType, Point};
     use crate::listeners::listener::{Listener, Listeners};
-    use crate::models::events::Counts;
+    use crate::models::events::{Counts, InfectionEvent};
+    use uuid::Uuid;
 
     struct MockListener {
         calls_counts_updated: u32,
@@ -108,7 +117,7 @@ mod tests {
             self.calls_simulation_ended += 1;
         }
 
-        fn citizen_got_infected(&mut self, _cell: &Point) {
+        fn citizen_got_infected(&mut self, _infection: &InfectionEvent) {
             self.calls_citizen_got_infected += 1;
         }
 
@@ -126,7 +135,7 @@ mod tests {
         let mut listeners = Listeners::from(mocks);
 
         listeners.counts_updated(Counts::new(10, 1, 0));
-        listeners.citizen_got_infected(&Point::new(1, 1));
+        listeners.citizen_got_infected(&InfectionEvent::new(1, Uuid::new_v4(), Uuid::new_v4(), Point::new(1, 1), AreaType::Home));
         listeners.simulation_ended();
 
         for i in 0..=1 {
@@ -17,9 +17,16 @@
  *
  */
 
+pub mod citizen_states_writer;
+pub mod counts_recorder;
 pub mod csv_service;
 pub mod disease_tracker;
+pub mod epidemic_curves;
+pub mod epidemic_metrics;
 pub mod events_kafka_producer;
 pub mod intervention_reporter;
 pub mod listener;
+pub mod output_writer;
+pub mod transmission_tree;
 pub mod travel_counter;
+pub mod visualisation;
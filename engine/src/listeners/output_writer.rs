@@ -0,0 +1,145 @@
+/*
+ * EpiRust
+ * Copyright (c) 2020  ThoughtWorks, Inc.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ */
+
+use std::any::Any;
+
+use common::config::{OutputConfig, OutputStream};
+use common::models::custom_types::Hour;
+
+use crate::citizen::Citizen;
+use crate::geography::Point;
+use crate::interventions::intervention_type::InterventionType;
+use crate::listeners::listener::Listener;
+use crate::listeners::travel_counter::CountsByRegion;
+use crate::models::events::{CitizenState, Counts};
+use crate::sinks::{open_sink, Sink, Value};
+use crate::travel::migration::MigratorsByRegion;
+
+/// Streams the configured outputs to every configured sink as the simulation progresses
+pub struct OutputWriter {
+    streams: Vec<OutputStream>,
+    sinks: Vec<Box<dyn Sink>>,
+}
+
+impl OutputWriter {
+    pub fn new(output_file_prefix: &str, config: &OutputConfig) -> OutputWriter {
+        let sinks = config
+            .sinks
+            .iter()
+            .map(|sink| {
+                open_sink(*sink, output_file_prefix, &config.streams)
+                    .unwrap_or_else(|e| panic!("Failed to open {:?} output: {}", sink, e))
+            })
+            .collect();
+        OutputWriter { streams: config.streams.clone(), sinks }
+    }
+
+    fn emit(&mut self, stream: OutputStream, row: &[Value]) {
+        if !self.streams.contains(&stream) {
+            return;
+        }
+        for sink in self.sinks.iter_mut() {
+            if let Err(e) = sink.write(stream, row) {
+                error!("Failed to write {} output: {}", stream.name(), e);
+            }
+        }
+    }
+}
+
+impl Listener for OutputWriter {
+    fn counts_updated(&mut self, counts: Counts) {
+        let row = [
+            Value::Int(counts.get_hour() as i64),
+            Value::Int(counts.get_susceptible() as i64),
+            Value::Int(counts.get_exposed() as i64),
+            Value::Int(counts.get_infected() as i64),
+            Value::Int(counts.get_hospitalized() as i64),
+            Value::Int(counts.get_recovered() as i64),
+            Value::Int(counts.get_deceased() as i64),
+        ];
+        self.emit(OutputStream::Counts, &row);
+    }
+
+    fn simulation_ended(&mut self) {
+        for sink in self.sinks.iter_mut() {
+            if let Err(e) = sink.finish() {
+                error!("Failed to finish writing output: {}", e);
+            }
+        }
+    }
+
+    fn citizen_state_updated(&mut self, hr: Hour, citizen: &Citizen, location: &Point) {
+        let row = [
+            Value::Int(hr as i64),
+            Value::Text(citizen.id.to_string()),
+            Value::Text(CitizenState::state_str(citizen.state_machine.state)),
+            Value::Int(location.x as i64),
+            Value::Int(location.y as i64),
+        ];
+        self.emit(OutputStream::CitizenStates, &row);
+    }
+
+    fn intervention_applied(&mut self, at_hour: Hour, intervention: &dyn InterventionType) {
+        let row = [Value::Int(at_hour as i64), Value::Text(intervention.name()), Value::Text(intervention.json_data())];
+        self.emit(OutputStream::Interventions, &row);
+    }
+
+    fn outgoing_migrators_added(&mut self, hr: Hour, travellers: &[MigratorsByRegion]) {
+        for travellers_by_region in travellers {
+            let counts = CountsByRegion::create_from(hr, travellers_by_region);
+            let row = [
+                Value::Int(counts.hr as i64),
+                Value::Text(counts.destination),
+                Value::Int(counts.susceptible as i64),
+                Value::Int(counts.exposed as i64),
+                Value::Int(counts.infected as i64),
+                Value::Int(counts.recovered as i64),
+            ];
+            self.emit(OutputStream::Travel, &row);
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sinks::test_utils::temp_prefix;
+    use common::config::OutputSink;
+
+    #[test]
+    fn should_stream_only_configured_streams() {
+        let prefix = temp_prefix("output_writer");
+        let config = OutputConfig { sinks: vec![OutputSink::JsonLines], streams: vec![OutputStream::Counts] };
+        let mut writer = OutputWriter::new(&prefix, &config);
+
+        writer.counts_updated(Counts::new_test(1, 9, 1, 0, 0, 0, 0));
+        writer.outgoing_migrators_added(24, &[MigratorsByRegion::create("engine2")]);
+        writer.simulation_ended();
+
+        let file_name = format!("{prefix}_counts.jsonl");
+        let contents = std::fs::read_to_string(&file_name).unwrap();
+        assert_eq!(contents.lines().count(), 1);
+        assert!(!std::path::Path::new(&format!("{prefix}_travel.jsonl")).exists());
+        std::fs::remove_file(file_name).unwrap();
+    }
+}
@@ -0,0 +1,137 @@
+/*
+ * EpiRust
+ * Copyright (c) 2020  ThoughtWorks, Inc.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ */
+
+use std::any::Any;
+use std::fs::File;
+
+use common::config::TransmissionTreeFormat;
+use common::models::custom_types::{CoOrdinate, Hour};
+use uuid::Uuid;
+
+use crate::geography::AreaType;
+use crate::listeners::csv_service;
+use crate::listeners::listener::Listener;
+use crate::models::events::InfectionEvent;
+use crate::utils::environment;
+
+#[derive(Serialize, Debug, PartialEq)]
+struct TransmissionRecord {
+    hour: Hour,
+    infector: Uuid,
+    infectee: Uuid,
+    x: CoOrdinate,
+    y: CoOrdinate,
+    area: AreaType,
+}
+
+/// Column oriented layout of the transmission tree, one array per field
+#[derive(Serialize, Default, Debug, PartialEq)]
+struct TransmissionColumns {
+    hour: Vec<Hour>,
+    infector: Vec<Uuid>,
+    infectee: Vec<Uuid>,
+    x: Vec<CoOrdinate>,
+    y: Vec<CoOrdinate>,
+    area: Vec<AreaType>,
+}
+
+impl TransmissionColumns {
+    fn from(records: &[TransmissionRecord]) -> TransmissionColumns {
+        let mut columns = TransmissionColumns::default();
+        for record in records {
+            columns.hour.push(record.hour);
+            columns.infector.push(record.infector);
+            columns.infectee.push(record.infectee);
+            columns.x.push(record.x);
+            columns.y.push(record.y);
+            columns.area.push(record.area);
+        }
+        columns
+    }
+}
+
+/// Records every infection with its infector. Infectors that never appear as an infectee are the index cases.
+pub struct TransmissionTree {
+    output_file_prefix: String,
+    format: TransmissionTreeFormat,
+    records: Vec<TransmissionRecord>,
+}
+
+impl TransmissionTree {
+    pub fn new(output_file_prefix: String, format: TransmissionTreeFormat) -> TransmissionTree {
+        TransmissionTree { output_file_prefix, format, records: Vec::new() }
+    }
+}
+
+impl Listener for TransmissionTree {
+    fn simulation_ended(&mut self) {
+        let mut output_path = environment::output_dir();
+        match self.format {
+            TransmissionTreeFormat::Csv => {
+                output_path.push(format!("{}_transmission_tree.csv", self.output_file_prefix));
+                if let Err(e) = csv_service::write(&output_path, &self.records) {
+                    error!("Failed to write transmission tree: {}", e);
+                }
+            }
+            TransmissionTreeFormat::Columnar => {
+                output_path.push(format!("{}_transmission_tree.json", self.output_file_prefix));
+                let file = File::create(output_path).expect("Failed to create transmission tree file");
+                serde_json::to_writer(file, &TransmissionColumns::from(&self.records))
+                    .expect("Failed to serialize transmission tree");
+            }
+        }
+    }
+
+    fn citizen_got_infected(&mut self, infection: &InfectionEvent) {
+        self.records.push(TransmissionRecord {
+            hour: infection.hour,
+            infector: infection.infector,
+            infectee: infection.infectee,
+            x: infection.location.x,
+            y: infection.location.y,
+            area: infection.area,
+        });
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geography::Point;
+
+    #[test]
+    fn should_record_infections_as_columns() {
+        let mut tree = TransmissionTree::new("test".to_string(), TransmissionTreeFormat::Columnar);
+        let (a, b, c) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+
+        tree.citizen_got_infected(&InfectionEvent::new(3, a, b, Point::new(1, 2), AreaType::Home));
+        tree.citizen_got_infected(&InfectionEvent::new(9, b, c, Point::new(60, 4), AreaType::Work));
+
+        let columns = TransmissionColumns::from(&tree.records);
+        assert_eq!(columns.hour, vec![3, 9]);
+        assert_eq!(columns.infector, vec![a, b]);
+        assert_eq!(columns.infectee, vec![b, c]);
+        assert_eq!(columns.x, vec![1, 60]);
+        assert_eq!(columns.area, vec![AreaType::Home, AreaType::Work]);
+    }
+}
@@ -25,22 +25,22 @@ use crate::travel::migration::MigratorsByRegion;
 use crate::utils::environment;
 
 #[derive(Serialize, Debug, PartialEq)]
-struct CountsByRegion {
-    hr: Hour,
-    destination: String,
-    susceptible: i32,
-    exposed: i32,
-    infected: i32,
-    recovered: i32,
+pub(crate) struct CountsByRegion {
+    pub(crate) hr: Hour,
+    pub(crate) destination: String,
+    pub(crate) susceptible: i32,
+    pub(crate) exposed: i32,
+    pub(crate) infected: i32,
+    pub(crate) recovered: i32,
 }
 
 impl CountsByRegion {
-    fn create_from(hr: Hour, travellers_by_region: &MigratorsByRegion) -> CountsByRegion {
+    pub(crate) fn create_from(hr: Hour, travellers_by_region: &MigratorsByRegion) -> CountsByRegion {
         let mut susceptible = 0;
         let mut exposed = 0;
         let mut infected = 0;
         let mut recovered = 0;
-        travellers_by_region.get_migrators_slice().iter().for_each(|traveller| match traveller.state_machine.state {
+        travellers_by_region.get_migrators_slice().iter().for_each(|traveller| match traveller.citizen.state_machine.state {
             State::Susceptible { .. } => susceptible += 1,
             State::Exposed { .. } => exposed += 1,
             State::Infected { .. } => infected += 1,
@@ -127,19 +127,19 @@ mod tests {
         let mut travellers = MigratorsByRegion::create(&region.to_string());
         for _i in 0..2 {
             let mut s = Migrator::new();
-            s.state_machine.state = State::Susceptible;
+            s.citizen.state_machine.state = State::Susceptible;
             travellers.alloc_citizen(s);
 
             let mut e = Migrator::new();
-            e.state_machine.state = State::expose(10);
+            e.citizen.state_machine.state = State::expose(10);
             travellers.alloc_citizen(e);
 
             let mut i = Migrator::new();
-            i.state_machine.state = State::mild_infected(0);
+            i.citizen.state_machine.state = State::mild_infected(0);
             travellers.alloc_citizen(i);
 
             let mut r = Migrator::new();
-            r.state_machine.state = State::Recovered;
+            r.citizen.state_machine.state = State::Recovered;
             travellers.alloc_citizen(r);
         }
 
@@ -0,0 +1,227 @@
+/*
+ * EpiRust
+ * Copyright (c) 2020  ThoughtWorks, Inc.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ */
+
+use std::any::Any;
+use std::error::Error;
+use std::path::PathBuf;
+
+use common::config::{VisualisationConfig, VisualisationFormat};
+use common::models::custom_types::Hour;
+use plotters::prelude::*;
+
+use crate::citizen::Citizen;
+use crate::geography::{Area, Grid, Point};
+use crate::listeners::listener::Listener;
+use crate::state_machine::State;
+use crate::utils::environment;
+
+const MIN_IMAGE_SIZE: u32 = 500;
+
+/// Number of pixels per grid cell, so that small grids are still readable
+pub(crate) fn scale_of(grid: &Grid) -> i32 {
+    (MIN_IMAGE_SIZE / grid.grid_size.max(1)).max(1) as i32
+}
+
+pub(crate) fn fill_cells(backend: &mut BitMapBackend, area: &Area, scale: i32, color: &RGBColor) -> Result<(), Box<dyn Error>> {
+    backend.draw_rect(
+        (area.start_offset.x * scale, area.start_offset.y * scale),
+        ((area.end_offset.x + 1) * scale - 1, (area.end_offset.y + 1) * scale - 1),
+        color,
+        true,
+    )?;
+    Ok(())
+}
+
+/// Paints a white background with the housing, transport, work and hospital areas in light colours
+pub(crate) fn draw_areas(backend: &mut BitMapBackend, grid: &Grid, scale: i32) -> Result<(), Box<dyn Error>> {
+    let (width, height) = backend.get_size();
+    backend.draw_rect((0, 0), (width as i32 - 1, height as i32 - 1), &WHITE, true)?;
+    fill_cells(backend, &grid.housing_area, scale, &RGBColor(255, 250, 220))?;
+    fill_cells(backend, &grid.transport_area, scale, &RGBColor(235, 235, 235))?;
+    fill_cells(backend, &grid.work_area, scale, &RGBColor(220, 235, 255))?;
+    fill_cells(backend, &grid.hospital_area, scale, &RGBColor(255, 220, 220))?;
+    Ok(())
+}
+
+fn color_of(state: State) -> RGBColor {
+    match state {
+        State::Susceptible => RGBColor(150, 150, 150),
+        State::Exposed { .. } => RGBColor(255, 165, 0),
+        State::Infected { .. } => RGBColor(220, 0, 0),
+        State::Recovered => RGBColor(0, 160, 0),
+        State::Deceased => BLACK,
+    }
+}
+
+/// Draws a frame of citizen locations coloured by disease state every `frame_interval` hours, either into an
+/// animated gif or as a numbered png sequence
+pub struct Visualisation {
+    output_file_prefix: String,
+    config: VisualisationConfig,
+    grid: Option<Grid>,
+    frame_hour: Hour,
+    citizens: Vec<(Point, RGBColor)>,
+    frames_drawn: u32,
+    gif: Option<BitMapBackend<'static>>,
+}
+
+impl Visualisation {
+    pub fn new(output_file_prefix: String, config: VisualisationConfig) -> Visualisation {
+        if config.frame_interval == 0 {
+            panic!("Visualisation frame interval should be at least one hour");
+        }
+        Visualisation { output_file_prefix, config, grid: None, frame_hour: 0, citizens: Vec::new(), frames_drawn: 0, gif: None }
+    }
+
+    fn output_path(&self, suffix: &str) -> PathBuf {
+        let mut output_path = environment::output_dir();
+        output_path.push(format!("{}_{}", self.output_file_prefix, suffix));
+        output_path
+    }
+
+    fn draw_citizens(&self, backend: &mut BitMapBackend, grid: &Grid, scale: i32) -> Result<(), Box<dyn Error>> {
+        draw_areas(backend, grid, scale)?;
+        for (location, color) in &self.citizens {
+            let cell = Area { location_id: grid.housing_area.location_id, start_offset: *location, end_offset: *location };
+            fill_cells(backend, &cell, scale, color)?;
+        }
+        backend.present()?;
+        Ok(())
+    }
+
+    fn draw_frame(&mut self) -> Result<(), Box<dyn Error>> {
+        let grid = match self.grid.take() {
+            Some(grid) => grid,
+            None => return Err("grid is not known yet".into()),
+        };
+        let scale = scale_of(&grid);
+        let size = grid.grid_size * scale as u32;
+        let result = match self.config.format {
+            VisualisationFormat::Gif => {
+                let mut gif = match self.gif.take() {
+                    Some(gif) => gif,
+                    None => BitMapBackend::gif(self.output_path("visualisation.gif"), (size, size), self.config.frame_delay_ms)?,
+                };
+                let result = self.draw_citizens(&mut gif, &grid, scale);
+                self.gif = Some(gif);
+                result
+            }
+            VisualisationFormat::Png => {
+                let png_path = self.output_path(&format!("frame_{:04}.png", self.frames_drawn));
+                let mut png = BitMapBackend::new(&png_path, (size, size));
+                self.draw_citizens(&mut png, &grid, scale)
+            }
+        };
+        self.grid = Some(grid);
+        result?;
+        self.frames_drawn += 1;
+        Ok(())
+    }
+
+    fn finish_frame(&mut self) {
+        if self.citizens.is_empty() {
+            return;
+        }
+        if let Err(e) = self.draw_frame() {
+            error!("Failed to draw visualisation frame for hour {}: {}", self.frame_hour, e);
+        }
+        self.citizens.clear();
+    }
+}
+
+impl Listener for Visualisation {
+    fn simulation_ended(&mut self) {
+        self.finish_frame();
+        self.gif = None;
+        info!("Drew {} visualisation frames", self.frames_drawn);
+    }
+
+    fn citizen_state_updated(&mut self, hr: Hour, citizen: &Citizen, location: &Point) {
+        if !hr.is_multiple_of(self.config.frame_interval) {
+            return;
+        }
+        if self.frame_hour != hr {
+            self.finish_frame();
+            self.frame_hour = hr;
+        }
+        self.citizens.push((*location, color_of(citizen.state_machine.state)));
+    }
+
+    fn grid_updated(&mut self, grid: &Grid) {
+        self.grid = Some(grid.clone());
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::utils::RandomWrapper;
+
+    use super::*;
+    use crate::citizen::WorkStatus;
+    use crate::geography::define_geography;
+    use crate::sinks::test_utils::temp_prefix;
+
+    fn citizen() -> Citizen {
+        let area = Area::new(&"engine1".to_string(), Point::new(0, 0), Point::new(1, 1));
+        Citizen::new(area, area, Point::new(2, 2), true, WorkStatus::Normal, &mut RandomWrapper::new())
+    }
+
+    fn draw(config: VisualisationConfig, prefix: &str) -> Visualisation {
+        let mut visualisation = Visualisation::new(prefix.to_string(), config);
+        visualisation.grid_updated(&define_geography(100, "engine1".to_string()));
+        let citizen = citizen();
+        for hr in 1..=6 {
+            visualisation.citizen_state_updated(hr, &citizen, &Point::new(4, 6));
+        }
+        visualisation.simulation_ended();
+        visualisation
+    }
+
+    #[test]
+    fn should_draw_numbered_png_frames_at_interval() {
+        let prefix = temp_prefix("visualisation_png");
+        let config = VisualisationConfig { format: VisualisationFormat::Png, frame_interval: 2, frame_delay_ms: 100 };
+
+        let visualisation = draw(config, &prefix);
+
+        assert_eq!(visualisation.frames_drawn, 3);
+        for frame in 0..3 {
+            let file_name = format!("{prefix}_frame_{frame:04}.png");
+            assert!(std::path::Path::new(&file_name).exists());
+            std::fs::remove_file(file_name).unwrap();
+        }
+    }
+
+    #[test]
+    fn should_draw_animated_gif() {
+        let prefix = temp_prefix("visualisation_gif");
+        let config = VisualisationConfig { format: VisualisationFormat::Gif, frame_interval: 3, frame_delay_ms: 100 };
+
+        let visualisation = draw(config, &prefix);
+
+        assert_eq!(visualisation.frames_drawn, 2);
+        let file_name = format!("{prefix}_visualisation.gif");
+        assert!(std::fs::metadata(&file_name).unwrap().len() > 0);
+        std::fs::remove_file(file_name).unwrap();
+    }
+}
@@ -40,6 +40,9 @@ pub const QUARANTINE_DAYS: Day = 14;
 pub const IMMUNITY_RANGE: [i32; 5] = [-2, -1, 0, 1, 2];
 pub const RANGE_FOR_EXPOSED: [i32; 3] = [-1, 0, 1];
 
+/// How often an engine in a multi-engine simulation tells the orchestrator it is alive
+pub const HEARTBEAT_INTERVAL_SECONDS: u64 = 5;
+
 pub const HOSPITAL_STAFF_PERCENTAGE: Percentage = 0.002;
 
 pub const HOME_SIZE: Size = 2;
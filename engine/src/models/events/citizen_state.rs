@@ -31,7 +31,7 @@ pub struct CitizenState {
 }
 
 impl CitizenState {
-    fn state_str(state: State) -> String {
+    pub(crate) fn state_str(state: State) -> String {
         match state {
             State::Susceptible { .. } => "s".to_string(),
             State::Exposed { .. } => "e".to_string(),
@@ -139,6 +139,16 @@ impl Counts {
         }
     }
 
+    /// Adds the citizens counted in `other`, leaving the hour as it is
+    pub fn add(&mut self, other: &Counts) {
+        self.susceptible += other.susceptible;
+        self.exposed += other.exposed;
+        self.infected += other.infected;
+        self.hospitalized += other.hospitalized;
+        self.recovered += other.recovered;
+        self.deceased += other.deceased;
+    }
+
     pub fn clear(&mut self) {
         self.susceptible = 0;
         self.exposed = 0;
@@ -181,6 +191,13 @@ mod tests {
         assert_eq!(counts.hour, 0);
     }
 
+    #[test]
+    fn should_add_counts_keeping_the_hour() {
+        let mut counts = Counts::new_test(5, 10, 1, 2, 3, 4, 5);
+        counts.add(&Counts::new_test(9, 1, 1, 1, 1, 1, 1));
+        assert_eq!(counts, Counts::new_test(5, 11, 2, 3, 4, 5, 6));
+    }
+
     #[test]
     fn should_update_susceptible() {
         let mut counts = Counts::new(100, 1, 2);
@@ -0,0 +1,38 @@
+/*
+ * EpiRust
+ * Copyright (c) 2020  ThoughtWorks, Inc.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ */
+
+use common::models::custom_types::Hour;
+use uuid::Uuid;
+
+use crate::geography::{AreaType, Point};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct InfectionEvent {
+    pub hour: Hour,
+    pub infector: Uuid,
+    pub infectee: Uuid,
+    pub location: Point,
+    pub area: AreaType,
+}
+
+impl InfectionEvent {
+    pub fn new(hour: Hour, infector: Uuid, infectee: Uuid, location: Point, area: AreaType) -> InfectionEvent {
+        InfectionEvent { hour, infector, infectee, location, area }
+    }
+}
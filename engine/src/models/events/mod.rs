@@ -19,10 +19,14 @@
 
 mod citizen_state;
 mod counts;
+mod heartbeat;
+mod infection_event;
 mod tick;
 mod tick_ack;
 
 pub use citizen_state::*;
 pub use counts::Counts;
-pub use tick::Tick;
-pub use tick_ack::TickAck;
+pub use heartbeat::Heartbeat;
+pub use infection_event::InfectionEvent;
+pub use tick::{Tick, TravelRestriction};
+pub use tick_ack::{Outgoing, TickAck};
@@ -19,10 +19,23 @@
 
 use common::models::custom_types::Hour;
 
-#[derive(Debug, Copy, Deserialize, PartialEq, Eq, Clone)]
+#[derive(Debug, Deserialize, PartialEq, Clone)]
 pub struct Tick {
     hour: Hour,
     terminate: bool,
+    #[serde(default)]
+    restrictions: Vec<TravelRestriction>,
+}
+
+/// Travel from one region to another that the orchestrator holds back because of travel restrictions or screening
+#[derive(Debug, Deserialize, PartialEq, Clone)]
+pub struct TravelRestriction {
+    pub from: String,
+    pub to: String,
+    /// Share of the travellers allowed through
+    pub travel_allowed: f64,
+    /// Chance that an infected traveller is detected and turned back
+    pub turned_back: f64,
 }
 
 impl Tick {
@@ -34,6 +47,10 @@ impl Tick {
         self.terminate
     }
 
+    pub fn restrictions(&self) -> &[TravelRestriction] {
+        &self.restrictions
+    }
+
     pub fn parse_tick(message: &str) -> Tick {
         serde_json::from_str(message).expect("Could not parse tick")
     }
@@ -41,7 +58,7 @@ impl Tick {
 
 #[cfg(test)]
 mod tests {
-    use crate::models::events::tick::Tick;
+    use crate::models::events::tick::{Tick, TravelRestriction};
 
     #[test]
     fn should_parse_tick() {
@@ -50,7 +67,27 @@ mod tests {
             "hour": 1,
             "terminate": false
         }"#;
-        let expected = Tick { hour: 1, terminate: false };
+        let expected = Tick { hour: 1, terminate: false, restrictions: Vec::new() };
         assert_eq!(expected, Tick::parse_tick(json));
     }
+
+    #[test]
+    fn should_parse_tick_with_travel_restrictions() {
+        let json = r#"
+        {
+            "hour": 7,
+            "terminate": false,
+            "restrictions": [{"from": "engine1", "to": "engine2", "travel_allowed": 0.5, "turned_back": 0.0}]
+        }"#;
+        let tick = Tick::parse_tick(json);
+        assert_eq!(
+            tick.restrictions(),
+            &[TravelRestriction {
+                from: "engine1".to_string(),
+                to: "engine2".to_string(),
+                travel_allowed: 0.5,
+                turned_back: 0.0
+            }]
+        );
+    }
 }
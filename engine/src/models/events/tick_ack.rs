@@ -17,13 +17,30 @@
  *
  */
 
+use std::collections::HashMap;
+
 use crate::models::events::Counts;
 use common::models::custom_types::Hour;
 
+/// Travellers sent to one region, and those held back by travel restrictions or entry screening
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Outgoing {
+    pub commuters: usize,
+    pub migrators: usize,
+    pub blocked_commuters: usize,
+    pub blocked_migrators: usize,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TickAck {
     pub engine_id: String,
     pub hour: Hour,
     pub counts: Counts,
     pub locked_down: bool,
+    /// Travellers sent to each region since the previous ack
+    pub outgoing: HashMap<String, Outgoing>,
+    /// Arrivals quarantined by entry screening since the previous ack
+    pub quarantined: usize,
+    /// Travellers infected in the region's transit hub since the previous ack
+    pub hub_infections: usize,
 }
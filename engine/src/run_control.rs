@@ -0,0 +1,133 @@
+/*
+ * EpiRust
+ * Copyright (c) 2020  ThoughtWorks, Inc.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ */
+
+use std::any::Any;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::broadcast;
+
+use crate::listeners::listener::Listener;
+use crate::models::events::Counts;
+
+const EVENTS_CAPACITY: usize = 256;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunEvent {
+    Counts(Counts),
+    Ended,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct RunProgress {
+    pub counts: Option<Counts>,
+    pub ended: bool,
+}
+
+/// Shared between a running simulation and whoever started it, to follow its progress and to stop it early.
+/// Cancelling stops the simulation at the start of the next hour; the outputs are still written.
+#[derive(Clone)]
+pub struct RunControl {
+    cancelled: Arc<AtomicBool>,
+    progress: Arc<Mutex<RunProgress>>,
+    events: broadcast::Sender<RunEvent>,
+}
+
+impl RunControl {
+    pub fn new() -> RunControl {
+        let (events, _) = broadcast::channel(EVENTS_CAPACITY);
+        RunControl { cancelled: Arc::new(AtomicBool::new(false)), progress: Arc::new(Mutex::new(RunProgress::default())), events }
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    pub fn progress(&self) -> RunProgress {
+        *self.progress.lock().unwrap()
+    }
+
+    /// Receives the counts of every hour from now on, and `RunEvent::Ended` once the outputs are written.
+    /// Receivers that fall behind by more than a few hundred hours miss the oldest events.
+    pub fn subscribe(&self) -> broadcast::Receiver<RunEvent> {
+        self.events.subscribe()
+    }
+
+    fn publish(&self, event: RunEvent) {
+        // there may be nobody listening, which is fine
+        let _ = self.events.send(event);
+    }
+}
+
+impl Default for RunControl {
+    fn default() -> Self {
+        RunControl::new()
+    }
+}
+
+impl Listener for RunControl {
+    fn counts_updated(&mut self, counts: Counts) {
+        self.progress.lock().unwrap().counts = Some(counts);
+        self.publish(RunEvent::Counts(counts));
+    }
+
+    fn simulation_ended(&mut self) {
+        self.progress.lock().unwrap().ended = true;
+        self.publish(RunEvent::Ended);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_share_progress_and_events_between_clones() {
+        let control = RunControl::new();
+        let mut listener = control.clone();
+        let mut events = control.subscribe();
+        let counts = Counts::new_test(1, 9, 1, 0, 0, 0, 0);
+
+        listener.counts_updated(counts);
+        listener.simulation_ended();
+
+        assert_eq!(control.progress(), RunProgress { counts: Some(counts), ended: true });
+        assert_eq!(events.try_recv().unwrap(), RunEvent::Counts(counts));
+        assert_eq!(events.try_recv().unwrap(), RunEvent::Ended);
+    }
+
+    #[test]
+    fn should_cancel_every_clone() {
+        let control = RunControl::new();
+        let clone = control.clone();
+
+        clone.cancel();
+
+        assert!(control.is_cancelled());
+    }
+}
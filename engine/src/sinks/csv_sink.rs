@@ -0,0 +1,85 @@
+/*
+ * EpiRust
+ * Copyright (c) 2020  ThoughtWorks, Inc.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ */
+
+use std::error::Error;
+use std::fs::File;
+
+use common::config::OutputStream;
+use csv::Writer;
+use fnv::FnvHashMap;
+
+use crate::sinks::{columns_of, Sink, Value};
+
+/// One CSV file per stream, named `<prefix>_<stream>.csv`
+pub struct CsvSink {
+    writers: FnvHashMap<OutputStream, Writer<File>>,
+}
+
+impl CsvSink {
+    pub fn open(output_path: &str, streams: &[OutputStream]) -> Result<CsvSink, Box<dyn Error>> {
+        let mut writers = FnvHashMap::default();
+        for stream in streams {
+            let mut writer = Writer::from_path(format!("{}_{}.csv", output_path, stream.name()))?;
+            writer.write_record(columns_of(*stream).iter().map(|c| c.name))?;
+            writers.insert(*stream, writer);
+        }
+        Ok(CsvSink { writers })
+    }
+}
+
+impl Sink for CsvSink {
+    fn write(&mut self, stream: OutputStream, row: &[Value]) -> Result<(), Box<dyn Error>> {
+        if let Some(writer) = self.writers.get_mut(&stream) {
+            writer.write_record(row.iter().map(|v| v.to_string()))?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn Error>> {
+        for writer in self.writers.values_mut() {
+            writer.flush()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sinks::test_utils::temp_prefix;
+
+    #[test]
+    fn should_write_header_and_rows() {
+        let prefix = temp_prefix("csv");
+        let mut sink = CsvSink::open(&prefix, &[OutputStream::Interventions]).unwrap();
+
+        sink.write(
+            OutputStream::Interventions,
+            &[Value::Int(3), Value::Text("lockdown".to_string()), Value::Text("{}".to_string())],
+        )
+        .unwrap();
+        sink.write(OutputStream::Counts, &[Value::Int(1)]).unwrap();
+        sink.finish().unwrap();
+
+        let file_name = format!("{prefix}_interventions.csv");
+        let contents = std::fs::read_to_string(&file_name).unwrap();
+        assert_eq!(contents, "hour,intervention,data\n3,lockdown,{}\n");
+        std::fs::remove_file(file_name).unwrap();
+    }
+}
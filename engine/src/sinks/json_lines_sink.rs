@@ -0,0 +1,104 @@
+/*
+ * EpiRust
+ * Copyright (c) 2020  ThoughtWorks, Inc.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ */
+
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use common::config::OutputStream;
+use fnv::FnvHashMap;
+use serde::ser::{Serialize, SerializeMap, Serializer};
+
+use crate::sinks::{columns_of, Column, Sink, Value};
+
+/// Serializes a row as an object, keeping the column order
+struct Record<'a> {
+    columns: &'a [Column],
+    row: &'a [Value],
+}
+
+impl Serialize for Record<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.columns.len()))?;
+        for (column, value) in self.columns.iter().zip(self.row) {
+            map.serialize_entry(column.name, value)?;
+        }
+        map.end()
+    }
+}
+
+/// One file per stream, named `<prefix>_<stream>.jsonl`, with a JSON object per line
+pub struct JsonLinesSink {
+    writers: FnvHashMap<OutputStream, BufWriter<File>>,
+}
+
+impl JsonLinesSink {
+    pub fn open(output_path: &str, streams: &[OutputStream]) -> Result<JsonLinesSink, Box<dyn Error>> {
+        let mut writers = FnvHashMap::default();
+        for stream in streams {
+            let file = File::create(format!("{}_{}.jsonl", output_path, stream.name()))?;
+            writers.insert(*stream, BufWriter::new(file));
+        }
+        Ok(JsonLinesSink { writers })
+    }
+}
+
+impl Sink for JsonLinesSink {
+    fn write(&mut self, stream: OutputStream, row: &[Value]) -> Result<(), Box<dyn Error>> {
+        if let Some(writer) = self.writers.get_mut(&stream) {
+            serde_json::to_writer(&mut *writer, &Record { columns: columns_of(stream), row })?;
+            writer.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn Error>> {
+        for writer in self.writers.values_mut() {
+            writer.flush()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sinks::test_utils::temp_prefix;
+
+    #[test]
+    fn should_write_one_object_per_line() {
+        let prefix = temp_prefix("jsonl");
+        let mut sink = JsonLinesSink::open(&prefix, &[OutputStream::Counts]).unwrap();
+
+        let row: Vec<Value> = (0..7).map(Value::Int).collect();
+        sink.write(OutputStream::Counts, &row).unwrap();
+        sink.write(OutputStream::Counts, &row).unwrap();
+        sink.finish().unwrap();
+
+        let file_name = format!("{prefix}_counts.jsonl");
+        let contents = std::fs::read_to_string(&file_name).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[0],
+            r#"{"hour":0,"susceptible":1,"exposed":2,"infected":3,"hospitalized":4,"recovered":5,"deceased":6}"#
+        );
+        std::fs::remove_file(file_name).unwrap();
+    }
+}
@@ -0,0 +1,110 @@
+/*
+ * EpiRust
+ * Copyright (c) 2020  ThoughtWorks, Inc.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ */
+
+mod csv_sink;
+mod json_lines_sink;
+mod parquet_sink;
+mod sqlite_sink;
+
+use std::error::Error;
+use std::fmt;
+
+use common::config::{OutputSink, OutputStream};
+
+use crate::utils::environment;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Value {
+    Int(i64),
+    Text(String),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Int(v) => write!(f, "{v}"),
+            Value::Text(v) => write!(f, "{v}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    Int,
+    Text,
+}
+
+pub struct Column {
+    pub name: &'static str,
+    pub column_type: ColumnType,
+}
+
+const fn int(name: &'static str) -> Column {
+    Column { name, column_type: ColumnType::Int }
+}
+
+const fn text(name: &'static str) -> Column {
+    Column { name, column_type: ColumnType::Text }
+}
+
+pub const COUNTS_COLUMNS: [Column; 7] =
+    [int("hour"), int("susceptible"), int("exposed"), int("infected"), int("hospitalized"), int("recovered"), int("deceased")];
+pub const INTERVENTIONS_COLUMNS: [Column; 3] = [int("hour"), text("intervention"), text("data")];
+pub const TRAVEL_COLUMNS: [Column; 6] =
+    [int("hour"), text("destination"), int("susceptible"), int("exposed"), int("infected"), int("recovered")];
+pub const CITIZEN_STATES_COLUMNS: [Column; 5] = [int("hour"), text("citizen_id"), text("state"), int("x"), int("y")];
+
+pub fn columns_of(stream: OutputStream) -> &'static [Column] {
+    match stream {
+        OutputStream::Counts => &COUNTS_COLUMNS,
+        OutputStream::Interventions => &INTERVENTIONS_COLUMNS,
+        OutputStream::Travel => &TRAVEL_COLUMNS,
+        OutputStream::CitizenStates => &CITIZEN_STATES_COLUMNS,
+    }
+}
+
+/// A destination for output rows. Rows are written as they arrive; `finish` flushes whatever is still buffered.
+pub trait Sink {
+    fn write(&mut self, stream: OutputStream, row: &[Value]) -> Result<(), Box<dyn Error>>;
+    fn finish(&mut self) -> Result<(), Box<dyn Error>>;
+}
+
+pub fn open_sink(sink: OutputSink, output_file_prefix: &str, streams: &[OutputStream]) -> Result<Box<dyn Sink>, Box<dyn Error>> {
+    let mut output_path = environment::output_dir();
+    output_path.push(output_file_prefix);
+    let output_path = output_path.to_string_lossy().to_string();
+    Ok(match sink {
+        OutputSink::Csv => Box::new(csv_sink::CsvSink::open(&output_path, streams)?),
+        OutputSink::JsonLines => Box::new(json_lines_sink::JsonLinesSink::open(&output_path, streams)?),
+        OutputSink::Parquet => Box::new(parquet_sink::ParquetSink::open(&output_path, streams)?),
+        OutputSink::Sqlite => Box::new(sqlite_sink::SqliteSink::open(&output_path, streams)?),
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod test_utils {
+    use std::path::PathBuf;
+
+    pub fn temp_prefix(name: &str) -> String {
+        let mut path: PathBuf = std::env::temp_dir();
+        path.push(format!("epirust_{}_{}", name, uuid::Uuid::new_v4()));
+        path.to_string_lossy().to_string()
+    }
+}
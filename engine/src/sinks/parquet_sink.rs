@@ -0,0 +1,153 @@
+/*
+ * EpiRust
+ * Copyright (c) 2020  ThoughtWorks, Inc.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ */
+
+use std::error::Error;
+use std::fs::File;
+use std::sync::Arc;
+
+use common::config::OutputStream;
+use fnv::FnvHashMap;
+use parquet::basic::{LogicalType, Repetition, Type as PhysicalType};
+use parquet::data_type::{ByteArray, ByteArrayType, Int64Type};
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::SerializedFileWriter;
+use parquet::schema::types::Type;
+
+use crate::sinks::{columns_of, ColumnType, Sink, Value};
+
+const ROW_GROUP_SIZE: usize = 10_000;
+
+struct ParquetStream {
+    writer: Option<SerializedFileWriter<File>>,
+    rows: Vec<Vec<Value>>,
+}
+
+/// One Parquet file per stream, named `<prefix>_<stream>.parquet`. Rows are flushed as row groups of `ROW_GROUP_SIZE`.
+pub struct ParquetSink {
+    streams: FnvHashMap<OutputStream, ParquetStream>,
+}
+
+impl ParquetSink {
+    pub fn open(output_path: &str, streams: &[OutputStream]) -> Result<ParquetSink, Box<dyn Error>> {
+        let mut writers = FnvHashMap::default();
+        for stream in streams {
+            let file = File::create(format!("{}_{}.parquet", output_path, stream.name()))?;
+            let writer =
+                SerializedFileWriter::new(file, Arc::new(Self::schema(*stream)?), Arc::new(WriterProperties::builder().build()))?;
+            writers.insert(*stream, ParquetStream { writer: Some(writer), rows: Vec::with_capacity(ROW_GROUP_SIZE) });
+        }
+        Ok(ParquetSink { streams: writers })
+    }
+
+    fn schema(stream: OutputStream) -> Result<Type, Box<dyn Error>> {
+        let mut fields = Vec::new();
+        for column in columns_of(stream) {
+            let (physical_type, logical_type) = match column.column_type {
+                ColumnType::Int => (PhysicalType::INT64, None),
+                ColumnType::Text => (PhysicalType::BYTE_ARRAY, Some(LogicalType::String)),
+            };
+            let field = Type::primitive_type_builder(column.name, physical_type)
+                .with_repetition(Repetition::REQUIRED)
+                .with_logical_type(logical_type)
+                .build()?;
+            fields.push(Arc::new(field));
+        }
+        Ok(Type::group_type_builder(stream.name()).with_fields(fields).build()?)
+    }
+
+    fn flush(stream: OutputStream, parquet_stream: &mut ParquetStream) -> Result<(), Box<dyn Error>> {
+        if parquet_stream.rows.is_empty() {
+            return Ok(());
+        }
+        let writer = parquet_stream.writer.as_mut().ok_or("Parquet writer is already closed")?;
+        let mut row_group = writer.next_row_group()?;
+        for (index, column) in columns_of(stream).iter().enumerate() {
+            let values = parquet_stream.rows.iter().map(|row| &row[index]);
+            let mut column_writer = row_group.next_column()?.ok_or("Parquet schema has fewer columns than the stream")?;
+            match column.column_type {
+                ColumnType::Int => {
+                    let values: Vec<i64> = values.map(|v| if let Value::Int(i) = v { *i } else { 0 }).collect();
+                    column_writer.typed::<Int64Type>().write_batch(&values, None, None)?;
+                }
+                ColumnType::Text => {
+                    let values: Vec<ByteArray> = values.map(|v| ByteArray::from(v.to_string().into_bytes())).collect();
+                    column_writer.typed::<ByteArrayType>().write_batch(&values, None, None)?;
+                }
+            }
+            column_writer.close()?;
+        }
+        row_group.close()?;
+        parquet_stream.rows.clear();
+        Ok(())
+    }
+}
+
+impl Sink for ParquetSink {
+    fn write(&mut self, stream: OutputStream, row: &[Value]) -> Result<(), Box<dyn Error>> {
+        if let Some(parquet_stream) = self.streams.get_mut(&stream) {
+            parquet_stream.rows.push(row.to_vec());
+            if parquet_stream.rows.len() >= ROW_GROUP_SIZE {
+                Self::flush(stream, parquet_stream)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn Error>> {
+        for (stream, parquet_stream) in self.streams.iter_mut() {
+            Self::flush(*stream, parquet_stream)?;
+            if let Some(writer) = parquet_stream.writer.take() {
+                writer.close()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sinks::test_utils::temp_prefix;
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+
+    #[test]
+    fn should_write_rows_in_row_groups() {
+        let prefix = temp_prefix("parquet");
+        let mut sink = ParquetSink::open(&prefix, &[OutputStream::CitizenStates]).unwrap();
+
+        for i in 0..(ROW_GROUP_SIZE + 5) {
+            let row = [
+                Value::Int(1),
+                Value::Text(format!("citizen-{i}")),
+                Value::Text("s".to_string()),
+                Value::Int(i as i64),
+                Value::Int(0),
+            ];
+            sink.write(OutputStream::CitizenStates, &row).unwrap();
+        }
+        sink.finish().unwrap();
+
+        let file_name = format!("{prefix}_citizen_states.parquet");
+        let reader = SerializedFileReader::new(File::open(&file_name).unwrap()).unwrap();
+        assert_eq!(reader.metadata().file_metadata().num_rows(), (ROW_GROUP_SIZE + 5) as i64);
+        assert_eq!(reader.metadata().num_row_groups(), 2);
+        assert_eq!(reader.metadata().file_metadata().schema_descr().num_columns(), 5);
+        std::fs::remove_file(file_name).unwrap();
+    }
+}
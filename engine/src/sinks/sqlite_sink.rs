@@ -0,0 +1,125 @@
+/*
+ * EpiRust
+ * Copyright (c) 2020  ThoughtWorks, Inc.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ */
+
+use std::error::Error;
+
+use common::config::OutputStream;
+use fnv::FnvHashMap;
+use rusqlite::types::ToSqlOutput;
+use rusqlite::{params_from_iter, Connection, ToSql};
+
+use crate::sinks::{columns_of, ColumnType, Sink, Value};
+
+const ROWS_PER_TRANSACTION: usize = 10_000;
+
+impl ToSql for Value {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        match self {
+            Value::Int(v) => v.to_sql(),
+            Value::Text(v) => v.to_sql(),
+        }
+    }
+}
+
+/// A single `<prefix>.sqlite` database with a table per stream
+pub struct SqliteSink {
+    connection: Connection,
+    insert_statements: FnvHashMap<OutputStream, String>,
+    uncommitted_rows: usize,
+}
+
+impl SqliteSink {
+    pub fn open(output_path: &str, streams: &[OutputStream]) -> Result<SqliteSink, Box<dyn Error>> {
+        let connection = Connection::open(format!("{output_path}.sqlite"))?;
+        let mut insert_statements = FnvHashMap::default();
+        for stream in streams {
+            let columns = columns_of(*stream);
+            let definitions: Vec<String> = columns
+                .iter()
+                .map(|c| {
+                    let sql_type = match c.column_type {
+                        ColumnType::Int => "INTEGER",
+                        ColumnType::Text => "TEXT",
+                    };
+                    format!("{} {}", c.name, sql_type)
+                })
+                .collect();
+            connection.execute_batch(&format!(
+                "DROP TABLE IF EXISTS {table}; CREATE TABLE {table} ({columns});",
+                table = stream.name(),
+                columns = definitions.join(", ")
+            ))?;
+            let placeholders = vec!["?"; columns.len()].join(", ");
+            insert_statements.insert(*stream, format!("INSERT INTO {} VALUES ({})", stream.name(), placeholders));
+        }
+        connection.execute_batch("BEGIN")?;
+        Ok(SqliteSink { connection, insert_statements, uncommitted_rows: 0 })
+    }
+}
+
+impl Sink for SqliteSink {
+    fn write(&mut self, stream: OutputStream, row: &[Value]) -> Result<(), Box<dyn Error>> {
+        if let Some(insert) = self.insert_statements.get(&stream) {
+            self.connection.prepare_cached(insert)?.execute(params_from_iter(row.iter()))?;
+            self.uncommitted_rows += 1;
+            if self.uncommitted_rows >= ROWS_PER_TRANSACTION {
+                self.connection.execute_batch("COMMIT; BEGIN")?;
+                self.uncommitted_rows = 0;
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn Error>> {
+        if !self.connection.is_autocommit() {
+            self.connection.execute_batch("COMMIT")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sinks::test_utils::temp_prefix;
+
+    #[test]
+    fn should_insert_rows_into_stream_tables() {
+        let prefix = temp_prefix("sqlite");
+        let mut sink = SqliteSink::open(&prefix, &[OutputStream::Counts, OutputStream::Interventions]).unwrap();
+
+        let row: Vec<Value> = (0..7).map(Value::Int).collect();
+        sink.write(OutputStream::Counts, &row).unwrap();
+        sink.write(OutputStream::Counts, &row).unwrap();
+        sink.write(
+            OutputStream::Interventions,
+            &[Value::Int(2), Value::Text("vaccinate".to_string()), Value::Text("{}".to_string())],
+        )
+        .unwrap();
+        sink.finish().unwrap();
+
+        let file_name = format!("{prefix}.sqlite");
+        let connection = Connection::open(&file_name).unwrap();
+        let counts: i64 = connection.query_row("SELECT COUNT(*) FROM counts", [], |r| r.get(0)).unwrap();
+        let intervention: String = connection.query_row("SELECT intervention FROM interventions", [], |r| r.get(0)).unwrap();
+        assert_eq!(counts, 2);
+        assert_eq!(intervention, "vaccinate");
+        std::fs::remove_file(file_name).unwrap();
+    }
+}
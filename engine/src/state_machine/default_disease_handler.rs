@@ -23,10 +23,11 @@ use crate::geography::Point;
 use crate::models::constants;
 use crate::state_machine::{DiseaseHandler, Severity, State};
 use common::disease::Disease;
-use common::models::custom_types::{Day, Hour};
+use common::models::custom_types::{Day, Hour, Percentage};
 use common::utils::RandomWrapper;
 use rand::prelude::SliceRandom;
 use rand::Rng;
+use uuid::Uuid;
 
 impl DiseaseHandler for Disease {
     fn is_to_be_hospitalize(&self, current_state: &State, immunity: i32) -> bool {
@@ -68,7 +69,7 @@ impl DiseaseHandler for Disease {
         citizen: &Citizen,
         map: &CitizenLocationMap,
         rng: &mut RandomWrapper,
-    ) -> Option<State> {
+    ) -> Option<(State, Uuid)> {
         if !citizen.work_quarantined && !citizen.is_vaccinated() {
             let neighbours = citizen.current_area.get_neighbors_of(cell);
 
@@ -78,8 +79,8 @@ impl DiseaseHandler for Disease {
                 .filter(|agent| agent.state_machine.is_infected() && !agent.is_hospitalized())
                 .find(|neighbor| rng.get().gen_bool(neighbor.get_infection_transmission_rate(&self)));
 
-            if neighbor_that_spreads_infection.is_some() {
-                return Some(State::Exposed { at_hour: sim_hr });
+            if let Some(infector) = neighbor_that_spreads_infection {
+                return Some((State::Exposed { at_hour: sim_hr }, infector.id));
             }
         };
         None
@@ -100,4 +101,8 @@ impl DiseaseHandler for Disease {
             None
         }
     }
+
+    fn transmission_rate(&self, infection_day: Day, immunity: i32) -> Percentage {
+        self.get_current_transmission_rate((infection_day as i32 + immunity) as Day)
+    }
 }
@@ -21,8 +21,9 @@ use crate::allocation_map::CitizenLocationMap;
 use crate::citizen::Citizen;
 use crate::geography::Point;
 use crate::state_machine::{Severity, State};
-use common::models::custom_types::{Day, Hour};
+use common::models::custom_types::{Day, Hour, Percentage};
 use common::utils::RandomWrapper;
+use uuid::Uuid;
 
 pub trait DiseaseHandler {
     fn is_to_be_hospitalize(&self, current_state: &State, immunity: i32) -> bool;
@@ -31,6 +32,7 @@ pub trait DiseaseHandler {
 
     fn on_exposed(&self, at_hour: Hour, sim_hr: Hour, rng: &mut RandomWrapper) -> Option<State>;
 
+    /// Returns the new state along with the id of the citizen that spread the infection
     fn on_susceptible(
         &self,
         sim_hr: Hour,
@@ -38,7 +40,10 @@ pub trait DiseaseHandler {
         citizen: &Citizen,
         map: &CitizenLocationMap,
         rng: &mut RandomWrapper,
-    ) -> Option<State>;
+    ) -> Option<(State, Uuid)>;
 
     fn on_routine_end(&self, current_state: &State, rng: &mut RandomWrapper) -> Option<State>;
+
+    /// Chance that an infected citizen passes the infection on to a susceptible one it meets
+    fn transmission_rate(&self, infection_day: Day, immunity: i32) -> Percentage;
 }
@@ -19,4 +19,4 @@
 
 pub mod tick_util;
 
-pub use tick_util::{extract_tick, get_tick, receive_tick, send_ack};
+pub use tick_util::{receive_tick, send_ack, AckReport, Heartbeats};
@@ -17,60 +17,60 @@
  *
  */
 
-use crate::interventions::lockdown::LockdownIntervention;
-use crate::kafka::kafka_producer::KafkaProducer;
+use crate::interventions::Interventions;
 use crate::kafka::ticks_consumer;
 use crate::models::constants;
-use crate::models::events::{Counts, Tick, TickAck};
+use crate::models::events::{Counts, Heartbeat, Outgoing, Tick, TickAck};
 use crate::run_mode::RunMode;
+use crate::travel::Hub;
 use common::models::custom_types::Hour;
-use futures::StreamExt;
+use common::transport::{EngineTransport, Payload};
 use opentelemetry::trace::{FutureExt, Span, TraceContextExt, Tracer};
 use opentelemetry::{global, Context, KeyValue};
-use rdkafka::consumer::MessageStream;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::time::timeout;
 
-pub async fn extract_tick(message_stream: &mut MessageStream<'_>) -> Tick {
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(constants::HEARTBEAT_INTERVAL_SECONDS);
+
+async fn extract_tick<E: EngineTransport>(transport: &mut E) -> Tick {
     debug!("Start receiving tick");
-    let msg = message_stream.next().await;
-    let mut maybe_tick = ticks_consumer::read(msg);
-    while maybe_tick.is_none() {
-        debug!("Retry for Tick");
-        let next_msg = message_stream.next().await;
-        maybe_tick = ticks_consumer::read(next_msg);
-    }
+    let payload = transport.receive_tick().await.expect("Orchestrator stopped sending ticks");
     debug!("Received Tick Successfully");
-    maybe_tick.unwrap()
+    ticks_consumer::read(&payload)
 }
 
-pub async fn get_tick(message_stream: &mut MessageStream<'_>, simulation_hour: Hour) -> Tick {
-    let mut tick = extract_tick(message_stream).await;
+async fn get_tick<E: EngineTransport>(transport: &mut E, simulation_hour: Hour) -> Tick {
+    let mut tick = extract_tick(transport).await;
     let mut tick_hour = tick.hour();
     while tick_hour < simulation_hour {
-        tick = extract_tick(message_stream).await;
+        tick = extract_tick(transport).await;
         tick_hour = tick.hour();
     }
     tick
 }
 
-pub async fn receive_tick(
+/// Whether the orchestrator ticks this hour: at the end of every day, whether or not anyone migrates, so the run
+/// outputs get a row per day, and at the commute hours of days with commuting
+fn is_tick_hour(simulation_hour: Hour, is_commute_enabled: bool) -> bool {
+    let day_hour = simulation_hour % 24;
+    let is_commute_hour = day_hour == constants::ROUTINE_TRAVEL_END_TIME || day_hour == constants::ROUTINE_TRAVEL_START_TIME;
+    day_hour == 0 || (is_commute_enabled && is_commute_hour)
+}
+
+pub async fn receive_tick<E: EngineTransport>(
     run_mode: &RunMode,
-    message_stream: &mut MessageStream<'_>,
+    transport: &mut E,
     simulation_hour: Hour,
     is_commute_enabled: bool,
-    is_migration_enabled: bool,
 ) -> Option<Tick> {
-    let day_hour = simulation_hour % 24;
-    let is_commute_hour = day_hour == constants::ROUTINE_TRAVEL_END_TIME || day_hour == constants::ROUTINE_TRAVEL_START_TIME;
-    let is_migration_hour = day_hour == 0;
-    let receive_tick_for_commute: bool = is_commute_enabled && is_commute_hour;
-    let receive_tick_for_migration: bool = is_migration_enabled && is_migration_hour;
-    if receive_tick_for_commute || receive_tick_for_migration {
+    if is_tick_hour(simulation_hour, is_commute_enabled) {
         if let RunMode::MultiEngine { engine_id: _e } = run_mode {
             let tracer = global::tracer("epirust-trace");
             let mut span = tracer.start("tick_wait_time");
             span.set_attribute(KeyValue::new("hour", simulation_hour.to_string()));
             let cx = Context::current_with_span(span);
-            let t = get_tick(message_stream, simulation_hour).with_context(cx).await;
+            let t = get_tick(transport, simulation_hour).with_context(cx).await;
             if t.hour() != simulation_hour {
                 panic!("Local hour is {}, but received tick for {}", simulation_hour, t.hour());
             }
@@ -80,34 +80,112 @@ pub async fn receive_tick(
     None
 }
 
-pub fn send_ack(
+/// What the engine reports to the orchestrator when it acknowledges a tick
+pub struct AckReport<'a> {
+    pub counts: Counts,
+    /// Infections in the hub are only taken when an ack goes out, so none are lost between acks
+    pub hub: Option<&'a mut Hub>,
+    pub outgoing: &'a mut HashMap<String, Outgoing>,
+}
+
+pub fn send_ack<E: EngineTransport>(
     run_mode: &RunMode,
-    producer: &mut KafkaProducer,
-    counts: Counts,
+    transport: &mut E,
     simulation_hour: Hour,
-    lockdown: &LockdownIntervention,
+    interventions: &mut Interventions,
+    report: AckReport,
     is_commute_enabled: bool,
-    is_migration_enabled: bool,
 ) {
-    let day_hour = simulation_hour % 24;
-    let is_commute_hour = day_hour == constants::ROUTINE_TRAVEL_END_TIME || day_hour == constants::ROUTINE_TRAVEL_START_TIME;
-    let is_migration_hour = day_hour == 0;
-    let received_tick_for_commute: bool = is_commute_enabled && is_commute_hour;
-    let received_tick_for_migration: bool = is_migration_enabled && is_migration_hour;
-
-    if simulation_hour == 1 || received_tick_for_commute || received_tick_for_migration {
+    if simulation_hour == 1 || is_tick_hour(simulation_hour, is_commute_enabled) {
         if let RunMode::MultiEngine { engine_id } = run_mode {
             let ack = TickAck {
                 engine_id: engine_id.to_string(),
                 hour: simulation_hour,
-                counts,
-                locked_down: lockdown.is_locked_down(),
+                counts: report.counts,
+                locked_down: interventions.lockdown.is_locked_down(),
+                outgoing: std::mem::take(report.outgoing),
+                quarantined: interventions.entry_screening.take_quarantined_count(),
+                hub_infections: report.hub.map_or(0, Hub::take_infections_since_ack),
             };
-            let tick_string = serde_json::to_string(&ack).unwrap();
-            match producer.send_ack(&tick_string) {
-                Ok(_) => {}
-                Err(e) => panic!("Failed while sending acknowledgement: {:?}", e.0),
+            transport.send_ack(serde_json::to_vec(&ack).unwrap());
+        }
+    }
+}
+
+/// Lets the orchestrator know the engine is still making progress while it simulates the hours between ticks
+pub struct Heartbeats {
+    last_sent: Instant,
+}
+
+impl Heartbeats {
+    pub fn start() -> Heartbeats {
+        Heartbeats { last_sent: Instant::now() }
+    }
+
+    /// Sends a heartbeat if none was sent in the last `HEARTBEAT_INTERVAL_SECONDS`
+    pub fn beat<E: EngineTransport>(&mut self, run_mode: &RunMode, transport: &mut E, simulation_hour: Hour) {
+        if self.last_sent.elapsed() < HEARTBEAT_INTERVAL {
+            return;
+        }
+        if let RunMode::MultiEngine { engine_id } = run_mode {
+            let heartbeat = Heartbeat { engine_id: engine_id.to_string(), hour: simulation_hour };
+            transport.send_ack(serde_json::to_vec(&heartbeat).unwrap());
+            self.last_sent = Instant::now();
+        }
+    }
+
+    /// The transport, sending heartbeats while it waits on travellers, so the orchestrator can tell an engine that is
+    /// waiting on the others from one that went silent
+    pub fn while_waiting<'a, E: EngineTransport>(
+        &'a mut self,
+        run_mode: &'a RunMode,
+        transport: &'a mut E,
+        simulation_hour: Hour,
+    ) -> WaitingTransport<'a, E> {
+        WaitingTransport { heartbeats: self, run_mode, transport, simulation_hour }
+    }
+}
+
+/// See `Heartbeats::while_waiting`
+pub struct WaitingTransport<'a, E> {
+    heartbeats: &'a mut Heartbeats,
+    run_mode: &'a RunMode,
+    transport: &'a mut E,
+    simulation_hour: Hour,
+}
+
+impl<E: EngineTransport> EngineTransport for WaitingTransport<'_, E> {
+    async fn receive_tick(&mut self) -> Option<Payload> {
+        self.transport.receive_tick().await
+    }
+
+    fn send_ack(&mut self, ack: Payload) {
+        self.transport.send_ack(ack)
+    }
+
+    fn send_commuters(&mut self, to_engine_id: &str, commuters: Payload) {
+        self.transport.send_commuters(to_engine_id, commuters)
+    }
+
+    fn send_migrators(&mut self, to_engine_id: &str, migrators: Payload) {
+        self.transport.send_migrators(to_engine_id, migrators)
+    }
+
+    async fn receive_commuters(&mut self) -> Option<Payload> {
+        loop {
+            if let Ok(commuters) = timeout(HEARTBEAT_INTERVAL, self.transport.receive_commuters()).await {
+                return commuters;
+            }
+            self.heartbeats.beat(self.run_mode, self.transport, self.simulation_hour);
+        }
+    }
+
+    async fn receive_migrators(&mut self) -> Option<Payload> {
+        loop {
+            if let Ok(migrators) = timeout(HEARTBEAT_INTERVAL, self.transport.receive_migrators()).await {
+                return migrators;
             }
+            self.heartbeats.beat(self.run_mode, self.transport, self.simulation_hour);
         }
     }
 }
@@ -17,40 +17,21 @@
  *
  */
 
-use crate::citizen::Citizen;
-use crate::disease_state_machine::DiseaseStateMachine;
-use crate::geography::Area;
-use uuid::Uuid;
+use crate::citizen::{Citizen, TravellingCitizen};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Commuter {
-    pub id: Uuid,
-    pub immunity: i32,
-    pub home_location: Area,
-    pub work_location: Area,
-    pub vaccinated: bool,
-    pub uses_public_transport: bool,
-    pub working: bool,
-    pub state_machine: DiseaseStateMachine,
+    pub citizen: TravellingCitizen,
 }
 
 impl PartialEq for Commuter {
     fn eq(&self, other: &Self) -> bool {
-        self.id == other.id
+        self.citizen.id == other.citizen.id
     }
 }
 
 impl From<&Citizen> for Commuter {
     fn from(citizen: &Citizen) -> Self {
-        Commuter {
-            id: citizen.id,
-            immunity: citizen.get_immunity(),
-            home_location: citizen.home_location.clone(),
-            work_location: citizen.work_location.clone(),
-            vaccinated: citizen.is_vaccinated(),
-            uses_public_transport: citizen.uses_public_transport,
-            working: citizen.is_working(),
-            state_machine: citizen.state_machine,
-        }
+        Commuter { citizen: TravellingCitizen::from(citizen) }
     }
 }
@@ -22,8 +22,7 @@ use crate::kafka::travel_consumer;
 use crate::models::constants;
 use crate::travel::commute::Commuter;
 use common::models::custom_types::Hour;
-use futures::StreamExt;
-use rdkafka::consumer::MessageStream;
+use common::transport::EngineTransport;
 
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
 pub struct CommutersByRegion {
@@ -32,6 +31,11 @@ pub struct CommutersByRegion {
 }
 
 impl CommutersByRegion {
+    #[cfg(any(test, feature = "bench"))]
+    pub(crate) fn new(to_engine_id: String, commuters: Vec<Commuter>) -> CommutersByRegion {
+        CommutersByRegion { to_engine_id, commuters }
+    }
+
     pub fn to_engine_id(&self) -> &String {
         &self.to_engine_id
     }
@@ -40,20 +44,8 @@ impl CommutersByRegion {
         self.commuters
     }
 
-    pub(crate) async fn receive_commuters_from_region(
-        message_stream: &mut MessageStream<'_>,
-        engine_id: &String,
-    ) -> Option<CommutersByRegion> {
-        let msg = message_stream.next().await;
-        let mut maybe_commuters = travel_consumer::read_commuters(msg);
-        while maybe_commuters.is_none()
-            || (maybe_commuters.as_ref().unwrap().commuters.is_empty()
-                && maybe_commuters.as_ref().unwrap().to_engine_id() == engine_id)
-        {
-            let next_msg = message_stream.next().await;
-            maybe_commuters = travel_consumer::read_commuters(next_msg);
-        }
-        maybe_commuters
+    pub(crate) async fn receive_commuters_from_region<E: EngineTransport>(transport: &mut E) -> Option<CommutersByRegion> {
+        transport.receive_commuters().await.map(|payload| travel_consumer::read_commuters(&payload))
     }
 
     pub fn get_commuters_by_region(
@@ -65,10 +57,14 @@ impl CommutersByRegion {
         for region in regions {
             let mut commuters_for_region: Vec<Commuter> = Vec::new();
             for (_point, commuter) in commuters {
-                if simulation_hour % 24 == constants::ROUTINE_TRAVEL_START_TIME && commuter.work_location.location_id == *region {
+                if simulation_hour % 24 == constants::ROUTINE_TRAVEL_START_TIME
+                    && commuter.citizen.work_location.location_id == *region
+                {
                     commuters_for_region.push(commuter.clone())
                 }
-                if simulation_hour % 24 == constants::ROUTINE_TRAVEL_END_TIME && commuter.home_location.location_id == *region {
+                if simulation_hour % 24 == constants::ROUTINE_TRAVEL_END_TIME
+                    && commuter.citizen.home_location.location_id == *region
+                {
                     commuters_for_region.push(commuter.clone())
                 }
             }
@@ -19,36 +19,46 @@
 mod commuter;
 mod commuters_by_region;
 
+use std::collections::HashMap;
+
 use common::models::custom_types::Hour;
 use common::models::travel_plan::TravelPlan;
 use common::models::CommutePlan;
-use rdkafka::consumer::MessageStream;
+use common::transport::EngineTransport;
+use common::utils::RandomWrapper;
+use rand::Rng;
 
+use crate::geography::Point;
 use crate::models::constants;
 use crate::models::events::Tick;
+use crate::travel::Restrictions;
 
 pub use commuter::Commuter;
 pub use commuters_by_region::CommutersByRegion;
 
-pub(crate) async fn receive_commuters(
+pub(crate) async fn receive_commuters<E: EngineTransport>(
     commute_plan: &CommutePlan,
-    tick: Option<Tick>,
-    message_stream: &mut MessageStream<'_>,
+    tick: Option<&Tick>,
+    transport: &mut E,
     engine_id: &String,
 ) -> Vec<Commuter> {
-    if tick.is_some() {
+    if let Some(tick) = tick {
         let mut incoming: Vec<Commuter> = Vec::new();
-        let hour = tick.unwrap().hour() % 24;
+        let hour = tick.hour() % 24;
         if hour == constants::ROUTINE_TRAVEL_START_TIME || hour == constants::ROUTINE_TRAVEL_END_TIME {
-            let expected_incoming_regions = commute_plan.incoming_regions_count(engine_id);
+            // every connected region sends a batch, empty when nobody is travelling, e.g. on a closed route
+            let expected_incoming_regions = commute_plan.connected_regions(engine_id).len();
             let mut received_incoming_regions = 0;
             debug!("Receiving commuters from {} regions", expected_incoming_regions);
             while expected_incoming_regions != received_incoming_regions {
-                let maybe_msg = CommutersByRegion::receive_commuters_from_region(message_stream, engine_id).await;
-                if let Some(region_incoming) = maybe_msg {
-                    trace_commuters(&region_incoming, hour);
-                    incoming.extend(region_incoming.get_commuters());
-                    received_incoming_regions += 1;
+                let maybe_msg = CommutersByRegion::receive_commuters_from_region(transport).await;
+                match maybe_msg {
+                    None => panic!("{}: Commuters stopped arriving at hour {}", engine_id, hour),
+                    Some(region_incoming) => {
+                        trace_commuters(&region_incoming, hour);
+                        incoming.extend(region_incoming.get_commuters());
+                        received_incoming_regions += 1;
+                    }
                 }
             }
         }
@@ -58,19 +68,182 @@ pub(crate) async fn receive_commuters(
     }
 }
 
+/// Keeps as many of the commuters leaving for work as `today` plans compared to the `planned` commute of the whole run,
+/// scaled by `scale`. The others stay home for the day.
+pub(crate) fn select_departures(
+    commuters: &mut Vec<(Point, Commuter)>,
+    planned: &CommutePlan,
+    today: &CommutePlan,
+    engine_id: &String,
+    scale: f64,
+    rng: &mut RandomWrapper,
+) {
+    let fractions: Vec<(&String, f64)> = planned
+        .regions
+        .iter()
+        .map(|region| {
+            let planned_commuters = planned.get_outgoing(engine_id, region);
+            let fraction = if planned_commuters == 0 {
+                1.0
+            } else {
+                today.get_outgoing(engine_id, region) as f64 / planned_commuters as f64
+            };
+            (region, (fraction * scale).clamp(0.0, 1.0))
+        })
+        .collect();
+    if fractions.iter().all(|(_, fraction)| *fraction >= 1.0) {
+        return;
+    }
+    commuters.retain(|(_, commuter)| {
+        let fraction = fractions
+            .iter()
+            .find(|(region, _)| commuter.citizen.work_location.location_id == **region)
+            .map_or(1.0, |(_, fraction)| *fraction);
+        rng.get().gen_bool(fraction)
+    });
+}
+
+/// Holds back the commuters leaving for work that travel restrictions or screening don't let through, and returns how
+/// many were held back for each region
+pub(crate) fn hold_back(
+    commuters: &mut Vec<(Point, Commuter)>,
+    restrictions: &Restrictions,
+    rng: &mut RandomWrapper,
+) -> HashMap<String, usize> {
+    let mut held_back: HashMap<String, usize> = HashMap::new();
+    if restrictions.is_empty() {
+        return held_back;
+    }
+    commuters.retain(|(_, commuter)| {
+        let region = commuter.citizen.work_location.location_id.to_string();
+        let through = restrictions.lets_through(&region, &commuter.citizen.state_machine, rng);
+        if !through {
+            *held_back.entry(region).or_default() += 1;
+        }
+        through
+    });
+    held_back
+}
+
 fn trace_commuters(commuters_by_region: &CommutersByRegion, hour: Hour) {
     if hour == constants::ROUTINE_TRAVEL_START_TIME {
         trace!(
             "Travel_start: Received {} commuters from {:?} region",
             commuters_by_region.commuters.len(),
-            commuters_by_region.commuters.get(0).map(|x| x.home_location.location_id.to_string())
+            commuters_by_region.commuters.first().map(|x| x.citizen.home_location.location_id.to_string())
         );
     }
     if hour == constants::ROUTINE_TRAVEL_END_TIME {
         trace!(
             "Travel_end: Received {} commuters from {:?} region",
             commuters_by_region.commuters.len(),
-            commuters_by_region.commuters.get(0).map(|x| x.work_location.location_id.to_string())
+            commuters_by_region.commuters.first().map(|x| x.citizen.work_location.location_id.to_string())
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use common::transport::wire_format::{self, WireFormat};
+
+    use super::*;
+    use crate::citizen::{Citizen, WorkStatus};
+    use crate::geography::Area;
+
+    fn commuters(work_region: &str, count: usize) -> Vec<(Point, Commuter)> {
+        (0..count)
+            .map(|_| {
+                let home = Area::new(&"engine1".to_string(), Point::new(0, 0), Point::new(1, 1));
+                let work = Area::new(&work_region.to_string(), Point::new(5, 5), Point::new(6, 6));
+                let citizen = Citizen::new(home, work, Point::new(0, 0), true, WorkStatus::Normal, &mut RandomWrapper::new());
+                let commuter = Commuter::from(&citizen);
+                (Point::new(0, 0), commuter)
+            })
+            .collect()
+    }
+
+    fn plan(matrix: Vec<Vec<u32>>) -> CommutePlan {
+        CommutePlan { regions: vec!["engine1".to_string(), "engine2".to_string(), "engine3".to_string()], matrix }
+    }
+
+    #[test]
+    fn should_keep_commuters_in_proportion_to_the_plan_of_the_day() {
+        let planned = plan(vec![vec![0, 100, 100], vec![0, 0, 0], vec![0, 0, 0]]);
+        let today = plan(vec![vec![0, 0, 100], vec![0, 0, 0], vec![0, 0, 0]]);
+        let mut outgoing = commuters("engine2", 100);
+        outgoing.extend(commuters("engine3", 100));
+
+        select_departures(&mut outgoing, &planned, &today, &"engine1".to_string(), 1.0, &mut RandomWrapper::new());
+
+        assert_eq!(outgoing.len(), 100);
+        assert!(outgoing.iter().all(|(_, commuter)| commuter.citizen.work_location.location_id == "engine3"));
+    }
+
+    #[test]
+    fn should_keep_every_commuter_on_a_planned_day() {
+        let planned = plan(vec![vec![0, 100, 100], vec![0, 0, 0], vec![0, 0, 0]]);
+        let mut outgoing = commuters("engine2", 10);
+
+        select_departures(&mut outgoing, &planned, &planned.clone(), &"engine1".to_string(), 1.0, &mut RandomWrapper::new());
+
+        assert_eq!(outgoing.len(), 10);
+    }
+
+    /// Sends the batches of `engine1`'s commuters at `hour` and waits for `engine2` to receive everything it expects
+    fn exchange_commuters(planned: &CommutePlan, outgoing: &Vec<(Point, Commuter)>, hour: Hour) -> Vec<Commuter> {
+        let (_orchestrator, mut transports) = common::transport::connect(&planned.regions);
+        let sender = &planned.regions[0];
+        for batch in CommutersByRegion::get_commuters_by_region(&planned.connected_regions(sender), outgoing, hour) {
+            transports[0].send_commuters(batch.to_engine_id(), wire_format::encode(WireFormat::Json, &batch));
+        }
+        let tick = Tick::parse_tick(&format!(r#"{{"hour": {}, "terminate": false}}"#, hour));
+        let receiving = receive_commuters(planned, Some(&tick), &mut transports[1], &planned.regions[1]);
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(tokio::time::timeout(Duration::from_secs(5), receiving)).expect("commuters never arrived")
+    }
+
+    #[test]
+    fn should_complete_the_commute_hour_when_a_lockdown_closes_the_route() {
+        let regions = vec!["engine1".to_string(), "engine2".to_string()];
+        let planned = CommutePlan { regions, matrix: vec![vec![0, 10], vec![0, 0]] };
+        let mut outgoing = commuters("engine2", 10);
+
+        select_departures(&mut outgoing, &planned, &planned.clone(), &"engine1".to_string(), 0.0, &mut RandomWrapper::new());
+        assert!(outgoing.is_empty());
+
+        assert!(exchange_commuters(&planned, &outgoing, constants::ROUTINE_TRAVEL_START_TIME).is_empty());
+        assert!(exchange_commuters(&planned, &outgoing, constants::ROUTINE_TRAVEL_END_TIME).is_empty());
+    }
+
+    #[test]
+    fn should_complete_the_commute_hour_when_a_phase_closes_the_route() {
+        let regions = vec!["engine1".to_string(), "engine2".to_string()];
+        let planned = CommutePlan { regions: regions.clone(), matrix: vec![vec![0, 10], vec![0, 0]] };
+        let today = CommutePlan { regions, matrix: vec![vec![0, 0], vec![0, 0]] };
+        let mut outgoing = commuters("engine2", 10);
+
+        select_departures(&mut outgoing, &planned, &today, &"engine1".to_string(), 1.0, &mut RandomWrapper::new());
+
+        assert!(exchange_commuters(&planned, &outgoing, constants::ROUTINE_TRAVEL_START_TIME).is_empty());
+    }
+
+    #[test]
+    fn should_complete_the_commute_hour_when_a_travel_restriction_closes_the_route() {
+        let regions = vec!["engine1".to_string(), "engine2".to_string()];
+        let planned = CommutePlan { regions, matrix: vec![vec![0, 10], vec![10, 0]] };
+        let tick = Tick::parse_tick(
+            r#"{"hour": 7, "terminate": false, "restrictions": [
+                {"from": "engine1", "to": "engine2", "travel_allowed": 0.0, "turned_back": 0.0}
+            ]}"#,
+        );
+        let restrictions = Restrictions::for_tick(Some(&tick), "engine1");
+        let mut outgoing = commuters("engine2", 10);
+
+        let held_back = hold_back(&mut outgoing, &restrictions, &mut RandomWrapper::new());
+
+        assert_eq!(held_back.get("engine2"), Some(&10));
+        assert!(exchange_commuters(&planned, &outgoing, constants::ROUTINE_TRAVEL_START_TIME).is_empty());
+    }
+}
@@ -0,0 +1,177 @@
+/*
+ * EpiRust
+ * Copyright (c) 2020  ThoughtWorks, Inc.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ */
+
+use common::config::{TransitHub, TravelPlanConfig};
+use common::models::custom_types::{Hour, Percentage};
+use common::utils::RandomWrapper;
+use rand::Rng;
+
+use crate::citizen::TravellingCitizen;
+use crate::state_machine::DiseaseHandler;
+
+/// The transit hub of this engine's region, where the travellers arriving from other regions meet before they
+/// spread out. Travellers aren't held up there, the hours they spend in it only set how many of the others they meet.
+pub struct Hub {
+    hub: TransitHub,
+    infections: usize,
+    infections_since_ack: usize,
+}
+
+impl Hub {
+    pub fn for_engine(travel_plan: &TravelPlanConfig, engine_id: &str) -> Option<Hub> {
+        travel_plan.get_transit_hub(engine_id).map(|hub| Hub { hub: hub.clone(), infections: 0, infections_since_ack: 0 })
+    }
+
+    /// Exposes the susceptible travellers to the infected ones they meet in the hub, and returns how many got exposed
+    pub fn pass_through<T: DiseaseHandler>(
+        &mut self,
+        travellers: &mut [&mut TravellingCitizen],
+        simulation_hour: Hour,
+        disease_handler: &T,
+        rng: &mut RandomWrapper,
+    ) -> usize {
+        if travellers.len() < 2 {
+            return 0;
+        }
+        let spreading: Vec<Option<Percentage>> = travellers
+            .iter()
+            .map(|traveller| {
+                let infectious = traveller.state_machine.is_infected() && !traveller.hospitalized && !traveller.isolated;
+                infectious
+                    .then(|| disease_handler.transmission_rate(traveller.state_machine.get_infection_day(), traveller.immunity))
+            })
+            .collect();
+        if spreading.iter().all(Option::is_none) {
+            return 0;
+        }
+
+        let contacts = self.hub.hours * self.hub.contacts_per_hour;
+        let infector_ids: Vec<_> = travellers.iter().map(|traveller| traveller.id).collect();
+        let mut exposed = 0;
+        for (i, traveller) in travellers.iter_mut().enumerate() {
+            if !traveller.state_machine.is_susceptible() || traveller.vaccinated || traveller.work_quarantined {
+                continue;
+            }
+            let infector = (0..contacts).find_map(|_| {
+                let other = (i + rng.get().gen_range(1..spreading.len())) % spreading.len();
+                spreading[other].filter(|rate| rng.get().gen_bool(*rate)).map(|_| other)
+            });
+            if let Some(infector) = infector {
+                traveller.state_machine.expose(simulation_hour);
+                traveller.infected_by = Some(infector_ids[infector]);
+                exposed += 1;
+            }
+        }
+        if exposed > 0 {
+            debug!("{}: {} travellers exposed in {}", self.hub.engine_id, exposed, self.hub.name);
+        }
+        self.infections += exposed;
+        self.infections_since_ack += exposed;
+        exposed
+    }
+
+    pub fn name(&self) -> &str {
+        &self.hub.name
+    }
+
+    pub fn infections(&self) -> usize {
+        self.infections
+    }
+
+    pub fn take_infections_since_ack(&mut self) -> usize {
+        std::mem::take(&mut self.infections_since_ack)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::disease::Disease;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::citizen::{Citizen, WorkStatus};
+    use crate::geography::{Area, Point};
+    use crate::state_machine::State;
+
+    fn hub(contacts_per_hour: u32) -> Hub {
+        let hub = TransitHub { name: "airport".to_string(), engine_id: "engine1".to_string(), hours: 2, contacts_per_hour };
+        Hub { hub, infections: 0, infections_since_ack: 0 }
+    }
+
+    fn travellers(count: usize, infected: usize) -> Vec<TravellingCitizen> {
+        let mut rng = RandomWrapper::new();
+        let area = Area::new(&"engine2".to_string(), Point::new(0, 0), Point::new(1, 1));
+        (0..count)
+            .map(|i| {
+                let citizen = Citizen::new(area, area, Point::new(0, 0), false, WorkStatus::Normal, &mut rng);
+                let mut traveller = TravellingCitizen::from(&citizen);
+                traveller.immunity = 0;
+                if i < infected {
+                    traveller.state_machine.state = State::mild_infected(15);
+                }
+                traveller
+            })
+            .collect()
+    }
+
+    #[test]
+    fn should_expose_travellers_meeting_infected_ones() {
+        let disease = Disease::new(5, 10, 40, 9, 12, 0.5, 1.0, 0.02, 0.3, 0.3, 24, 24);
+        let mut rng = RandomWrapper::new();
+        let mut hub = hub(50);
+        let mut arrivals = travellers(10, 5);
+        let infected_ids: Vec<Uuid> = arrivals[..5].iter().map(|traveller| traveller.id).collect();
+
+        let exposed = hub.pass_through(&mut arrivals.iter_mut().collect::<Vec<_>>(), 31, &disease, &mut rng);
+
+        assert_eq!(exposed, 5);
+        for traveller in &arrivals[5..] {
+            assert_eq!(traveller.state_machine.state, State::Exposed { at_hour: 31 });
+            assert!(infected_ids.contains(&traveller.infected_by.unwrap()));
+        }
+        assert_eq!(hub.take_infections_since_ack(), 5);
+        assert_eq!(hub.take_infections_since_ack(), 0);
+        assert_eq!(hub.infections(), 5);
+    }
+
+    #[test]
+    fn should_not_expose_anyone_to_isolated_travellers() {
+        let disease = Disease::new(5, 10, 40, 9, 12, 0.5, 1.0, 0.02, 0.3, 0.3, 24, 24);
+        let mut rng = RandomWrapper::new();
+        let mut arrivals = travellers(10, 5);
+        arrivals[..5].iter_mut().for_each(|traveller| traveller.isolated = true);
+
+        let exposed = hub(50).pass_through(&mut arrivals.iter_mut().collect::<Vec<_>>(), 31, &disease, &mut rng);
+
+        assert_eq!(exposed, 0);
+        assert!(arrivals[5..].iter().all(|traveller| traveller.state_machine.is_susceptible()));
+    }
+
+    #[test]
+    fn should_not_expose_anyone_without_infected_travellers() {
+        let disease = Disease::new(5, 10, 40, 9, 12, 0.5, 1.0, 0.02, 0.3, 0.3, 24, 24);
+        let mut rng = RandomWrapper::new();
+        let mut arrivals = travellers(10, 0);
+
+        let exposed = hub(50).pass_through(&mut arrivals.iter_mut().collect::<Vec<_>>(), 31, &disease, &mut rng);
+
+        assert_eq!(exposed, 0);
+        assert!(arrivals.iter().all(|traveller| traveller.state_machine.is_susceptible()));
+    }
+}
@@ -17,28 +17,56 @@
  *
  */
 
-use common::models::custom_types::Count;
+use std::collections::HashMap;
+
+use common::config::StayLength;
+use common::models::custom_types::{Count, Hour};
 use common::models::travel_plan::TravelPlan;
 use common::models::MigrationPlan;
-use futures::StreamExt;
-use rdkafka::consumer::MessageStream;
+use common::transport::EngineTransport;
+use common::utils::RandomWrapper;
+
+use uuid::Uuid;
 
+use crate::allocation_map::CitizenLocationMap;
 use crate::geography::Point;
 use crate::kafka::travel_consumer;
+use crate::models::constants;
 use crate::models::events::Tick;
-use crate::travel::migration::{Migrator, MigratorsByRegion};
+use crate::travel::migration::{Migrator, MigratorsByRegion, Trip};
+use crate::travel::Restrictions;
 
 /// Travel plan in the context of the current engine
-#[derive(Debug, Deserialize, PartialEq, Eq, Clone)]
+#[derive(Debug, Deserialize, PartialEq, Clone)]
 pub struct EngineMigrationPlan {
     engine_id: String,
     migration_plan: Option<MigrationPlan>,
     current_total_population: Count,
+    stay_length: Option<StayLength>,
+    /// Trips of the migrators from other regions that are staying here
+    visitors: HashMap<Uuid, Trip>,
 }
 
 impl EngineMigrationPlan {
     pub fn new(engine_id: String, migration_plan: Option<MigrationPlan>, current_population: Count) -> EngineMigrationPlan {
-        EngineMigrationPlan { engine_id, migration_plan, current_total_population: current_population }
+        EngineMigrationPlan {
+            engine_id,
+            migration_plan,
+            current_total_population: current_population,
+            stay_length: None,
+            visitors: HashMap::new(),
+        }
+    }
+
+    /// Makes migrators return home after a stay of this length, instead of settling where they go
+    pub fn set_stay_length(&mut self, stay_length: Option<StayLength>) {
+        self.stay_length = stay_length;
+    }
+
+    /// Migrators are sent along the planned routes, and with round trips also back along the routes they arrived by
+    fn is_connected(&self, migration_plan: &MigrationPlan, from_region: &String, to_region: &String) -> bool {
+        migration_plan.get_outgoing(from_region, to_region) > 0
+            || (self.stay_length.is_some() && migration_plan.get_outgoing(to_region, from_region) > 0)
     }
 
     pub fn percent_outgoing(&self) -> f64 {
@@ -57,7 +85,7 @@ impl EngineMigrationPlan {
                 .regions
                 .iter()
                 .filter(|region| !self.engine_id.eq(*region))
-                .filter(|region| tp.get_outgoing(self.engine_id(), region) > 0)
+                .filter(|region| self.is_connected(tp, self.engine_id(), region))
                 .map(|region| {
                     let mut outgoing_by_region = MigratorsByRegion::create(region);
                     outgoing_by_region.alloc_citizens(&mut migrators, tp, &self.engine_id, total_outgoing as i32);
@@ -76,10 +104,110 @@ impl EngineMigrationPlan {
         (outgoing_by_region, actual_outgoing_migrators)
     }
 
+    /// Holds back the migrators that travel restrictions or screening don't let through, so they stay in this region,
+    /// and returns how many were held back for each region
+    pub fn hold_back(
+        &self,
+        outgoing_by_region: &mut [MigratorsByRegion],
+        actual_outgoing: &mut Vec<(Point, Migrator)>,
+        restrictions: &Restrictions,
+        rng: &mut RandomWrapper,
+    ) -> HashMap<String, usize> {
+        let mut held_back: HashMap<String, usize> = HashMap::new();
+        if restrictions.is_empty() {
+            return held_back;
+        }
+        let mut staying = Vec::new();
+        for region in outgoing_by_region.iter_mut() {
+            let to_engine_id = region.to_engine_id().clone();
+            let before = staying.len();
+            region.migrators.retain(|migrator| {
+                let through = restrictions.lets_through(&to_engine_id, &migrator.citizen.state_machine, rng);
+                if !through {
+                    staying.push(migrator.citizen.id);
+                }
+                through
+            });
+            if staying.len() > before {
+                held_back.insert(to_engine_id, staying.len() - before);
+            }
+        }
+        actual_outgoing.retain(|(_, migrator)| !staying.contains(&migrator.citizen.id));
+        held_back
+    }
+
     pub fn incoming_regions_count(&self) -> u32 {
         match &self.migration_plan {
             None => 0,
-            Some(tp) => tp.incoming_regions_count(self.engine_id()),
+            Some(tp) => tp
+                .regions
+                .iter()
+                .filter(|region| *region != self.engine_id() && self.is_connected(tp, region, self.engine_id()))
+                .count() as u32,
+        }
+    }
+
+    /// Gives the outgoing migrators their trips: visitors moving on keep the trip they arrived with, and residents
+    /// start a new one. Does nothing when migrators settle where they go.
+    pub fn plan_trips(
+        &self,
+        outgoing: &mut [(Point, Migrator)],
+        location_map: &CitizenLocationMap,
+        simulation_hour: Hour,
+        rng: &mut RandomWrapper,
+    ) {
+        let stay_length = match self.stay_length {
+            None => return,
+            Some(stay_length) => stay_length,
+        };
+        for (point, migrator) in outgoing.iter_mut() {
+            migrator.trip = self.visitors.get(&migrator.citizen.id).copied().or_else(|| {
+                location_map.get_agent_for(point).map(|citizen| Trip {
+                    home_location: citizen.home_location,
+                    work_location: citizen.work_location,
+                    return_at: simulation_hour + stay_length.sample(rng) * constants::HOURS_IN_A_DAY,
+                })
+            });
+        }
+    }
+
+    /// Trips of the visitors whose stay is over, and whose home region can be reached today
+    pub fn due_returns(&self, simulation_hour: Hour) -> HashMap<Uuid, Trip> {
+        let migration_plan = match &self.migration_plan {
+            None => return HashMap::new(),
+            Some(migration_plan) => migration_plan,
+        };
+        self.visitors
+            .iter()
+            .filter(|(_, trip)| trip.return_at <= simulation_hour)
+            .filter(|(_, trip)| {
+                let home_region = trip.home_region();
+                migration_plan.regions.contains(&home_region) && self.is_connected(migration_plan, &self.engine_id, &home_region)
+            })
+            .map(|(id, trip)| (*id, *trip))
+            .collect()
+    }
+
+    /// Adds the visitors going home to the migrators sent to their home regions
+    pub fn send_home(&self, outgoing_by_region: &mut [MigratorsByRegion], returning: &[(Point, Migrator)]) {
+        for (_, migrator) in returning {
+            let home_region = migrator.trip.map(|trip| trip.home_region());
+            match outgoing_by_region.iter_mut().find(|region| Some(region.to_engine_id()) == home_region.as_ref()) {
+                None => warn!("{}: No route home for visitor {}", self.engine_id, migrator.citizen.id),
+                Some(region) => region.migrators.push(*migrator),
+            }
+        }
+    }
+
+    /// Forgets the visitors that left, and remembers the trips of the ones that arrived
+    pub fn update_visitors(&mut self, departed: &[(Point, Migrator)], arrived: &[Migrator]) {
+        for (_, migrator) in departed {
+            self.visitors.remove(&migrator.citizen.id);
+        }
+        for migrator in arrived {
+            if let Some(trip) = migrator.trip.filter(|trip| trip.home_region() != self.engine_id) {
+                self.visitors.insert(migrator.citizen.id, trip);
+            }
         }
     }
 
@@ -87,21 +215,29 @@ impl EngineMigrationPlan {
         &self.engine_id
     }
 
+    /// Switches to the plan of the day, for engines whose travel matrices change during the run
+    pub fn set_migration_plan(&mut self, migration_plan: MigrationPlan) {
+        self.migration_plan = Some(migration_plan);
+    }
+
     pub fn set_current_population(&mut self, val: Count) {
         self.current_total_population = val;
     }
 
-    pub async fn receive_migrators(&self, tick: Option<Tick>, message_stream: &mut MessageStream<'_>) -> Vec<Migrator> {
-        if tick.is_some() && tick.unwrap().hour() % 24 == 0 {
+    pub async fn receive_migrators<E: EngineTransport>(&self, tick: Option<&Tick>, transport: &mut E) -> Vec<Migrator> {
+        if tick.is_some_and(|tick| tick.hour() % 24 == 0) {
             let expected_incoming_regions = self.incoming_regions_count();
             let mut received_incoming_regions = 0;
             debug!("Receiving migrators from {} regions", expected_incoming_regions);
             let mut incoming: Vec<Migrator> = Vec::new();
             while expected_incoming_regions != received_incoming_regions {
-                let maybe_msg = travel_consumer::read_migrators(message_stream.next().await);
-                if let Some(region_incoming) = maybe_msg {
-                    incoming.extend(region_incoming.get_migrators());
-                    received_incoming_regions += 1;
+                match transport.receive_migrators().await {
+                    None => panic!("{}: Migrators stopped arriving", self.engine_id),
+                    Some(payload) => {
+                        let region_incoming = travel_consumer::read_migrators(&payload);
+                        incoming.extend(region_incoming.get_migrators());
+                        received_incoming_regions += 1;
+                    }
                 }
             }
             incoming
@@ -114,6 +250,7 @@ impl EngineMigrationPlan {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::geography::Area;
     use common::models::MigrationPlan;
 
     #[test]
@@ -149,6 +286,49 @@ mod tests {
         assert_eq!(24, outgoing_by_region.get(1).unwrap().migrators.len());
     }
 
+    #[test]
+    fn should_connect_regions_both_ways_for_round_trips() {
+        let migration_plan = MigrationPlan::new(
+            vec!["engine1".into(), "engine2".into(), "engine3".into()],
+            vec![vec![0, 10, 0], vec![0, 0, 0], vec![5, 0, 0]],
+        );
+        let mut engine_migration_plan = EngineMigrationPlan::new("engine1".into(), Some(migration_plan), 10000);
+        assert_eq!(1, engine_migration_plan.incoming_regions_count());
+        assert_eq!(1, engine_migration_plan.alloc_outgoing_to_regions(&[]).0.len());
+
+        engine_migration_plan.set_stay_length(Some(StayLength::Fixed { days: 2 }));
+
+        assert_eq!(2, engine_migration_plan.incoming_regions_count());
+        let (outgoing_by_region, _) = engine_migration_plan.alloc_outgoing_to_regions(&[]);
+        let regions: Vec<&String> = outgoing_by_region.iter().map(|region| region.to_engine_id()).collect();
+        assert_eq!(regions, vec!["engine2", "engine3"]);
+    }
+
+    #[test]
+    fn should_send_visitors_home_when_their_stay_is_over() {
+        let mut engine_migration_plan = create_engine_with_travel_plan();
+        engine_migration_plan.set_stay_length(Some(StayLength::Fixed { days: 2 }));
+        let home = Area::new(&"engine2".to_string(), Point::new(0, 0), Point::new(2, 2));
+        let visitor =
+            Migrator { trip: Some(Trip { home_location: home, work_location: home, return_at: 48 }), ..Migrator::new() };
+        let resident = Migrator { trip: None, ..Migrator::new() };
+
+        engine_migration_plan.update_visitors(&[], &[visitor, resident]);
+
+        assert!(engine_migration_plan.due_returns(24).is_empty());
+        let due = engine_migration_plan.due_returns(48);
+        assert_eq!(due.len(), 1);
+        assert_eq!(due.get(&visitor.citizen.id), visitor.trip.as_ref());
+
+        let mut outgoing_by_region = vec![MigratorsByRegion::create("engine2"), MigratorsByRegion::create("engine3")];
+        engine_migration_plan.send_home(&mut outgoing_by_region, &[(Point::new(1, 1), visitor)]);
+        assert_eq!(outgoing_by_region[0].migrators, vec![visitor]);
+        assert!(outgoing_by_region[1].migrators.is_empty());
+
+        engine_migration_plan.update_visitors(&[(Point::new(1, 1), visitor)], &[]);
+        assert!(engine_migration_plan.due_returns(48).is_empty());
+    }
+
     // Removed the logic of adding remaining traveller in the last engine temporarily.
     // #[test]
     // fn should_handle_outgoing_with_actual_total_less_than_planned() {
@@ -17,49 +17,52 @@
  *
  */
 
-use crate::citizen::Citizen;
-use crate::disease_state_machine::DiseaseStateMachine;
-use uuid::Uuid;
+use common::models::custom_types::Hour;
+
+use crate::citizen::{Citizen, TravellingCitizen};
+use crate::geography::Area;
+
+/// A round trip away from home, carried by the migrator until it is back in its home region
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Trip {
+    pub home_location: Area,
+    pub work_location: Area,
+    pub return_at: Hour,
+}
+
+impl Trip {
+    pub fn home_region(&self) -> String {
+        self.home_location.location_id.to_string()
+    }
+}
 
 #[derive(Copy, Clone, Debug, Serialize, Deserialize)]
 pub struct Migrator {
-    pub id: Uuid,
-    pub immunity: i32,
-    pub vaccinated: bool,
-    pub uses_public_transport: bool,
-    pub working: bool,
-    pub state_machine: DiseaseStateMachine,
+    pub citizen: TravellingCitizen,
+    pub trip: Option<Trip>,
 }
 
 impl Migrator {
     #[cfg(test)]
     pub fn new() -> Migrator {
-        Migrator {
-            id: Uuid::new_v4(),
-            immunity: 0,
-            vaccinated: false,
-            uses_public_transport: false,
-            working: false,
-            state_machine: DiseaseStateMachine::new(),
-        }
+        use crate::citizen::WorkStatus;
+        use crate::geography::Point;
+        use common::utils::RandomWrapper;
+
+        let area = Area::new(&"engine1".to_string(), Point::new(0, 0), Point::new(1, 1));
+        let citizen = Citizen::new(area, area, Point::new(0, 0), false, WorkStatus::NA, &mut RandomWrapper::new());
+        Migrator::from(&citizen)
     }
 }
 
 impl From<&Citizen> for Migrator {
     fn from(citizen: &Citizen) -> Self {
-        Migrator {
-            id: citizen.id,
-            immunity: citizen.get_immunity(),
-            vaccinated: citizen.is_vaccinated(),
-            uses_public_transport: citizen.uses_public_transport,
-            working: citizen.is_working(),
-            state_machine: citizen.state_machine,
-        }
+        Migrator { citizen: TravellingCitizen::from(citizen), trip: None }
     }
 }
 
 impl PartialEq for Migrator {
     fn eq(&self, other: &Self) -> bool {
-        self.id == other.id
+        self.citizen.id == other.citizen.id
     }
 }
@@ -59,6 +59,11 @@ impl MigratorsByRegion {
         self.migrators.push(traveller);
     }
 
+    #[cfg(any(test, feature = "bench"))]
+    pub(crate) fn new(to_engine_id: &str, migrators: Vec<Migrator>) -> MigratorsByRegion {
+        MigratorsByRegion { to_engine_id: to_engine_id.to_owned(), migrators }
+    }
+
     pub fn create(to_engine_id: &str) -> MigratorsByRegion {
         MigratorsByRegion { to_engine_id: to_engine_id.to_owned(), migrators: Vec::new() }
     }
@@ -21,5 +21,5 @@ mod migrator;
 mod migrators_by_engine;
 
 pub use engine_migration_plan::EngineMigrationPlan;
-pub use migrator::Migrator;
+pub use migrator::{Migrator, Trip};
 pub use migrators_by_engine::MigratorsByRegion;
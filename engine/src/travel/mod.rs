@@ -17,4 +17,9 @@
  *
  */
 pub mod commute;
+mod hub;
 pub mod migration;
+mod restrictions;
+
+pub use hub::Hub;
+pub use restrictions::Restrictions;
@@ -0,0 +1,108 @@
+/*
+ * EpiRust
+ * Copyright (c) 2020  ThoughtWorks, Inc.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ */
+
+use std::collections::HashMap;
+
+use common::utils::RandomWrapper;
+use rand::Rng;
+
+use crate::disease_state_machine::DiseaseStateMachine;
+use crate::models::events::{Tick, TravelRestriction};
+
+/// The restrictions on travel out of this engine that came with the current tick
+pub struct Restrictions<'a> {
+    to_regions: HashMap<&'a str, &'a TravelRestriction>,
+}
+
+impl<'a> Restrictions<'a> {
+    pub fn for_tick(tick: Option<&'a Tick>, engine_id: &str) -> Restrictions<'a> {
+        let to_regions = tick
+            .map(|tick| tick.restrictions())
+            .unwrap_or_default()
+            .iter()
+            .filter(|restriction| restriction.from == engine_id)
+            .map(|restriction| (restriction.to.as_str(), restriction))
+            .collect();
+        Restrictions { to_regions }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.to_regions.is_empty()
+    }
+
+    /// Whether a traveller to `region` gets through, or is held back by the restrictions or turned back by screening
+    pub fn lets_through(&self, region: &str, state_machine: &DiseaseStateMachine, rng: &mut RandomWrapper) -> bool {
+        match self.to_regions.get(region) {
+            None => true,
+            Some(restriction) => {
+                let allowed = rng.get().gen_bool(restriction.travel_allowed.clamp(0.0, 1.0));
+                let turned_back = state_machine.is_infected() && rng.get().gen_bool(restriction.turned_back.clamp(0.0, 1.0));
+                allowed && !turned_back
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tick(json: &str) -> Tick {
+        Tick::parse_tick(json)
+    }
+
+    #[test]
+    fn should_only_hold_back_travel_out_of_this_engine() {
+        let tick = tick(
+            r#"{"hour": 7, "terminate": false, "restrictions": [
+                {"from": "engine1", "to": "engine2", "travel_allowed": 0.0, "turned_back": 0.0},
+                {"from": "engine2", "to": "engine1", "travel_allowed": 0.0, "turned_back": 0.0}
+            ]}"#,
+        );
+        let restrictions = Restrictions::for_tick(Some(&tick), "engine1");
+        let mut rng = RandomWrapper::new();
+
+        assert!(!restrictions.lets_through("engine2", &DiseaseStateMachine::new(), &mut rng));
+        assert!(restrictions.lets_through("engine3", &DiseaseStateMachine::new(), &mut rng));
+    }
+
+    #[test]
+    fn should_turn_back_infected_travellers_detected_by_screening() {
+        let tick = tick(
+            r#"{"hour": 7, "terminate": false, "restrictions": [
+                {"from": "engine1", "to": "engine2", "travel_allowed": 1.0, "turned_back": 1.0}
+            ]}"#,
+        );
+        let restrictions = Restrictions::for_tick(Some(&tick), "engine1");
+        let mut rng = RandomWrapper::new();
+        let mut infected = DiseaseStateMachine::new();
+        infected.set_mild_asymptomatic();
+
+        assert!(!restrictions.lets_through("engine2", &infected, &mut rng));
+        assert!(restrictions.lets_through("engine2", &DiseaseStateMachine::new(), &mut rng));
+    }
+
+    #[test]
+    fn should_let_everyone_through_without_a_tick() {
+        let restrictions = Restrictions::for_tick(None, "engine1");
+
+        assert!(restrictions.is_empty());
+        assert!(restrictions.lets_through("engine2", &DiseaseStateMachine::new(), &mut RandomWrapper::new()));
+    }
+}
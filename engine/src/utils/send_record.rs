@@ -17,17 +17,20 @@
  *
  */
 
+use std::fmt::Debug;
+
 use rdkafka::error::KafkaError;
+use rdkafka::message::ToBytes;
 use rdkafka::producer::{BaseRecord, DefaultProducerContext, ThreadedProducer};
 
-pub type SendResult<'a> = Result<(), (KafkaError, BaseRecord<'a, String, String>)>;
+pub type SendResult<'a, P = String> = Result<(), (KafkaError, BaseRecord<'a, String, P>)>;
 
 pub trait SendRecord {
-    fn send_record(&self, record: BaseRecord<String, String>);
+    fn send_record<P: ToBytes + Debug + ?Sized>(&self, record: BaseRecord<String, P>);
 }
 
 impl SendRecord for ThreadedProducer<DefaultProducerContext> {
-    fn send_record(&self, record: BaseRecord<String, String>) {
+    fn send_record<P: ToBytes + Debug + ?Sized>(&self, record: BaseRecord<String, P>) {
         let msg = &*format!("Failed to send msg {:?}, Reason", record.payload);
         self.send(record).expect(msg);
     }
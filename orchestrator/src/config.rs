@@ -20,6 +20,7 @@
 use std::error::Error;
 use std::fs::File;
 
+use common::config::travel_matrix::{self, TravelMatrixConfig};
 use common::config::Population::Auto;
 use common::config::{Config, TravelPlanConfig};
 use common::models::custom_types::Percentage;
@@ -42,12 +43,58 @@ impl Configuration {
         self.engine_configs.iter().map(|s| s.engine_id.clone()).collect()
     }
 
+    pub fn get_engine_configs(&self) -> Vec<(String, Config)> {
+        self.engine_configs.iter().map(|s| (s.engine_id.clone(), s.config.clone())).collect()
+    }
+
+    /// Replaces the commute and migration matrices with ones generated from the populations and locations of the
+    /// regions, checking that no engine sends more travellers than it has agents for
+    pub fn generate_travel_matrices(&mut self, spec: &TravelMatrixConfig) -> Result<(), String> {
+        let regions = self.travel_plan.get_regions();
+        let mut locations = Vec::new();
+        let mut populations = Vec::new();
+        let mut agents = Vec::new();
+        let mut public_transport_users = Vec::new();
+        for region in &regions {
+            let location = spec
+                .regions
+                .iter()
+                .find(|location| &location.engine_id == region)
+                .ok_or_else(|| format!("No location for region {}", region))?;
+            let engine = self.engine_configs.iter().find(|engine| &engine.engine_id == region).unwrap();
+            let population = match engine.config.get_population() {
+                Auto(x) => x,
+                _ => return Err(format!("Generating travel matrices needs an Auto population for engine {}", region)),
+            };
+            locations.push(location.clone());
+            populations.push(location.population.unwrap_or(population.number_of_agents) as f64);
+            agents.push(population.number_of_agents);
+            public_transport_users.push((population.number_of_agents as f64 * population.public_transport_percentage) as u32);
+        }
+
+        let outgoing = |share: f64| agents.iter().map(|agents| (*agents as f64 * share).round() as u32).collect::<Vec<u32>>();
+        if let Some(commute) = spec.commute {
+            let matrix = travel_matrix::generate_matrix(commute.model, &locations, &populations, &outgoing(commute.share));
+            travel_matrix::validate_matrix("commuters", &regions, &matrix, &public_transport_users)?;
+            self.travel_plan.set_commute_matrix(matrix);
+        }
+        if let Some(migration) = spec.migration {
+            let matrix = travel_matrix::generate_matrix(migration.model, &locations, &populations, &outgoing(migration.share));
+            travel_matrix::validate_matrix("migrators", &regions, &matrix, &agents)?;
+            self.travel_plan.set_migration_matrix(matrix);
+        }
+        Ok(())
+    }
+
     pub fn read(filename: &str) -> Result<Configuration, Box<dyn Error>> {
         let reader = File::open(filename)?;
         let config: Configuration = serde_json::from_reader(reader)?;
         if !config.travel_plan.validate_regions(&config.get_engine_ids()) {
             panic!("Engine names should match regions in travel plan");
         }
+        if let Err(e) = config.travel_plan.validate_phases().and_then(|_| config.travel_plan.validate_hubs()) {
+            panic!("Invalid travel plan: {}", e);
+        }
         Ok(config)
     }
 
@@ -140,6 +187,48 @@ mod tests {
         assert!(!config_for_engines.is_empty())
     }
 
+    #[test]
+    fn should_generate_travel_matrices_for_the_regions() {
+        let mut config = Configuration::read("config/test/travel_plan.json").unwrap();
+        let spec: TravelMatrixConfig = serde_json::from_str(
+            r#"{
+                "regions": [
+                    {"engine_id": "engine1", "latitude": 18.52, "longitude": 73.85},
+                    {"engine_id": "engine2", "latitude": 18.53, "longitude": 73.86},
+                    {"engine_id": "engine3", "latitude": 18.70, "longitude": 74.10}
+                ],
+                "commute": {"model": {"Gravity": {"destination_exponent": 1.0, "distance_exponent": 2.0}}, "share": 0.01},
+                "migration": {"model": "Radiation", "share": 0.001}
+            }"#,
+        )
+        .unwrap();
+
+        config.generate_travel_matrices(&spec).unwrap();
+
+        let commute = config.get_travel_plan().commute_plan();
+        assert_eq!(commute.matrix[0][0], 0);
+        assert!(commute.matrix[0][1] > commute.matrix[0][2]);
+        assert!(config.get_travel_plan().migration.enabled);
+    }
+
+    #[test]
+    fn should_not_generate_more_commuters_than_use_public_transport() {
+        let mut config = Configuration::read("config/test/travel_plan.json").unwrap();
+        let spec: TravelMatrixConfig = serde_json::from_str(
+            r#"{
+                "regions": [
+                    {"engine_id": "engine1", "latitude": 18.52, "longitude": 73.85},
+                    {"engine_id": "engine2", "latitude": 18.53, "longitude": 73.86},
+                    {"engine_id": "engine3", "latitude": 18.70, "longitude": 74.10}
+                ],
+                "commute": {"model": "Radiation", "share": 0.9}
+            }"#,
+        )
+        .unwrap();
+
+        assert!(config.generate_travel_matrices(&spec).is_err());
+    }
+
     #[test]
     fn should_read_hours() {
         let hours = get_hours("config/test/travel_plan.json");
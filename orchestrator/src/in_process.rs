@@ -0,0 +1,100 @@
+/*
+ * EpiRust
+ * Copyright (c) 2020  ThoughtWorks, Inc.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ */
+
+use std::ops::Range;
+use std::thread;
+
+use common::transport;
+use engine::EngineApp;
+
+use crate::config::Configuration;
+use crate::run_outputs::RunOutputs;
+use crate::ticks;
+use crate::ticks::{EngineFailure, TickAcks, Timeouts};
+use crate::travel_restrictions::TravelRestrictions;
+
+/// Runs every engine on its own thread of this process, wired to the orchestrator with channels instead of Kafka.
+///
+/// When engines fail, the others are stopped from waiting on travellers from them, and every thread is joined before
+/// the failure is returned.
+pub async fn start(
+    config: &Configuration,
+    hours: Range<i64>,
+    threads: u32,
+    timeouts: Timeouts,
+    outputs: &mut RunOutputs,
+) -> Result<TickAcks, EngineFailure> {
+    let travel_plan = config.get_travel_plan();
+    let (mut orchestrator, engines) = transport::connect(&config.get_engine_ids());
+
+    let handles: Vec<(String, _)> = config
+        .get_engine_configs()
+        .into_iter()
+        .zip(engines)
+        .map(|((engine_id, engine_config), mut engine_transport)| {
+            let travel_plan = travel_plan.clone();
+            let name = engine_id.clone();
+            let handle = thread::Builder::new()
+                .name(engine_id.clone())
+                .spawn(move || {
+                    EngineApp::start_with_transport(&engine_id, engine_config, travel_plan, &mut engine_transport, threads)
+                })
+                .expect("Failed to start engine thread");
+            (name, handle)
+        })
+        .collect();
+
+    let restrictions = TravelRestrictions::new(&config.get_engine_configs());
+    let result = ticks::start_ticking(travel_plan, &restrictions, hours, &mut orchestrator, timeouts, outputs).await;
+    if result.is_err() {
+        orchestrator.stop_engines();
+    }
+    drop(orchestrator);
+    for (engine_id, handle) in handles {
+        match (handle.join(), &result) {
+            (Ok(_), _) => {}
+            (Err(_), Ok(_)) => panic!("Engine {} failed while running in-process", engine_id),
+            (Err(_), Err(_)) => error!("Engine {} stopped after the simulation failed", engine_id),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn should_run_engines_in_process_and_keep_travellers() {
+        let mut output_dir = std::env::temp_dir();
+        output_dir.push(format!("epirust_in_process_{}", std::process::id()));
+        std::fs::create_dir_all(&output_dir).unwrap();
+        std::env::set_var("EPI_OUTPUT_DIR", &output_dir);
+
+        let config = Configuration::read("config/test/in_process.json").unwrap();
+        let timeouts = Timeouts { tick: None, heartbeat: Duration::from_secs(60) };
+        let mut outputs = RunOutputs::new("in_process".to_string(), config.get_engine_ids(), "xxh64:0".to_string());
+        let acks = start(&config, 1..80, 1, timeouts, &mut outputs).await.unwrap();
+
+        assert!(acks.all_received());
+        assert_eq!(acks.total_population(), 400);
+        std::fs::remove_dir_all(output_dir).unwrap();
+    }
+}
@@ -1,5 +1,5 @@
-use rdkafka::consumer::{Consumer, MessageStream, StreamConsumer};
-use rdkafka::ClientConfig;
+use rdkafka::consumer::{Consumer, StreamConsumer};
+use rdkafka::{ClientConfig, Message};
 
 use crate::environment;
 
@@ -24,7 +24,16 @@ impl KafkaConsumer {
         KafkaConsumer { consumer }
     }
 
-    pub fn start_message_stream(&self) -> MessageStream {
-        self.consumer.stream()
+    /// Waits for the next message with a payload, skipping over errors
+    pub async fn recv(&self) -> Vec<u8> {
+        loop {
+            match self.consumer.recv().await {
+                Err(e) => error!("Failed to receive a message: {}", e),
+                Ok(message) => match message.payload() {
+                    None => debug!("Received an empty message"),
+                    Some(payload) => return payload.to_vec(),
+                },
+            }
+        }
     }
 }
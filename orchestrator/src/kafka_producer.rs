@@ -8,7 +8,7 @@ pub struct KafkaProducer {
     producer: ThreadedProducer<DefaultProducerContext>,
 }
 
-type SendResult<'a> = Result<(), (KafkaError, BaseRecord<'a, String, String>)>;
+type SendResult<'a, P = String> = Result<(), (KafkaError, BaseRecord<'a, String, P>)>;
 
 impl KafkaProducer {
     pub fn new() -> KafkaProducer {
@@ -28,9 +28,9 @@ impl KafkaProducer {
         self.producer.send(record)
     }
 
-    pub fn send_tick<'a>(&mut self, tick_ack_str: &'a String) -> SendResult<'a> {
-        let record: BaseRecord<String, String> = BaseRecord::to("ticks").payload(tick_ack_str);
-        debug!("Send tick: {}", record.payload.unwrap());
+    pub fn send_tick<'a>(&mut self, tick: &'a Vec<u8>) -> SendResult<'a, Vec<u8>> {
+        let record: BaseRecord<String, Vec<u8>> = BaseRecord::to("ticks").payload(tick);
+        debug!("Send tick: {}", String::from_utf8_lossy(tick));
         self.producer.send(record)
     }
 }
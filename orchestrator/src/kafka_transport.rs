@@ -0,0 +1,47 @@
+/*
+ * EpiRust
+ * Copyright (c) 2020  ThoughtWorks, Inc.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ */
+
+use common::transport::{OrchestratorTransport, Payload};
+
+use crate::kafka_consumer::KafkaConsumer;
+use crate::kafka_producer::KafkaProducer;
+
+/// Talks to engines running as separate processes through Kafka topics
+pub struct KafkaTransport {
+    producer: KafkaProducer,
+    consumer: KafkaConsumer,
+}
+
+impl KafkaTransport {
+    pub fn new() -> KafkaTransport {
+        KafkaTransport { producer: KafkaProducer::new(), consumer: KafkaConsumer::new() }
+    }
+}
+
+impl OrchestratorTransport for KafkaTransport {
+    fn send_tick(&mut self, tick: Payload) {
+        if self.producer.send_tick(&tick).is_err() {
+            panic!("Failed to send tick to engines");
+        }
+    }
+
+    async fn receive_ack(&mut self) -> Option<Payload> {
+        Some(self.consumer.recv().await)
+    }
+}
@@ -23,24 +23,40 @@ extern crate log;
 #[macro_use]
 extern crate serde_derive;
 
+use std::fs::{self, File};
 use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::process;
 use std::string::String;
+use std::time::Duration;
 
 use clap::Parser;
+use common::config::travel_matrix::TravelMatrixConfig;
 use common::config::TravelPlanConfig;
+use common::transport::TcpOrchestratorTransport;
 use rdkafka::admin::{AdminClient, AdminOptions, NewTopic, TopicReplication};
 use rdkafka::client::DefaultClientContext;
 use rdkafka::ClientConfig;
+use tokio::net::TcpListener;
 
 use crate::config::Configuration;
 use crate::kafka_producer::KafkaProducer;
+use crate::kafka_transport::KafkaTransport;
+use crate::run_outputs::RunOutputs;
+use crate::ticks::{EngineFailure, Timeouts};
+use crate::travel_restrictions::TravelRestrictions;
 use crate::utils::get_hours;
 
 mod config;
 mod environment;
+mod in_process;
 mod kafka_consumer;
 mod kafka_producer;
+mod kafka_transport;
+mod run_outputs;
+mod spawned;
 mod ticks;
+mod travel_restrictions;
 mod utils;
 
 #[derive(Parser)]
@@ -48,6 +64,44 @@ mod utils;
 struct Args {
     #[arg(short, long, value_name = "FILE", help = "Use a config file to run the simulation")]
     config: Option<String>,
+
+    #[arg(long, help = "Run all the engines inside the orchestrator instead of talking to them through Kafka")]
+    in_process: bool,
+
+    #[arg(long, value_name = "ADDRESS", conflicts_with = "in_process")]
+    #[arg(help = "Wait for the engines to connect on the given address (e.g. 0.0.0.0:7000) and talk to them \
+            over TCP instead of Kafka")]
+    listen: Option<String>,
+
+    #[arg(long, conflicts_with_all = ["in_process", "listen"])]
+    #[arg(help = "Start an engine process for every engine in the config and talk to them over TCP instead of Kafka. \
+            The engines are stopped when the simulation ends")]
+    spawn_engines: bool,
+
+    #[arg(long, value_name = "FILE", requires = "spawn_engines")]
+    #[arg(help = "The engine binary to start, by default the epirust-engine or engine-app next to the orchestrator")]
+    engine_binary: Option<PathBuf>,
+
+    #[arg(short, long, default_value_t = 4)]
+    #[arg(help = "Number of parallel threads for data parallelization of in-process or spawned engines")]
+    threads: u32,
+
+    #[arg(long, value_name = "SECONDS")]
+    #[arg(help = "Abort the simulation if the engines take longer than this to acknowledge a tick")]
+    tick_timeout: Option<u64>,
+
+    #[arg(long, value_name = "SECONDS", default_value_t = 60)]
+    #[arg(help = "Abort the simulation if an engine sends neither an acknowledgement nor a heartbeat for this long")]
+    heartbeat_timeout: u64,
+
+    #[arg(long, value_name = "FILE")]
+    #[arg(help = "Instead of running the simulation, generate the travel matrices of the config from the region \
+            locations and travel models in this file, and write out the resulting config")]
+    generate_travel_plan: Option<PathBuf>,
+
+    #[arg(long, value_name = "FILE", requires = "generate_travel_plan")]
+    #[arg(help = "Where to write the config with generated travel matrices, standard output by default")]
+    output: Option<PathBuf>,
 }
 
 #[tokio::main]
@@ -60,15 +114,59 @@ async fn main() {
     let config_path = args.config.unwrap_or(default_config_path);
 
     let config = Configuration::read(&config_path).expect("Error while reading config");
+    if let Some(spec_path) = args.generate_travel_plan {
+        generate_travel_plan(config, &spec_path, args.output.as_deref());
+        return;
+    }
     let sim_conf = utils::read_simulation_conf(&config_path);
     let travel_plan = config.get_travel_plan();
+    let restrictions = TravelRestrictions::new(&config.get_engine_configs());
 
     //TODO: use already read config instead of passing config path and reading file again
     let hours = 1..get_hours(&config_path);
 
+    let timeouts =
+        Timeouts { tick: args.tick_timeout.map(Duration::from_secs), heartbeat: Duration::from_secs(args.heartbeat_timeout) };
+
+    config.validate();
+    let mut outputs = RunOutputs::for_config(&config_path, &config).expect("Error while preparing the run outputs");
+    let result = if args.in_process {
+        in_process::start(&config, hours, args.threads, timeouts, &mut outputs).await.map(|_| ())
+    } else if args.spawn_engines {
+        let engine_binary = args
+            .engine_binary
+            .or_else(spawned::default_engine_binary)
+            .expect("Could not find the engine binary next to the orchestrator, use --engine-binary to point to it");
+        spawned::start(&config, hours, sim_conf, &engine_binary, args.threads, timeouts, &mut outputs).await
+    } else if let Some(address) = args.listen {
+        start_over_tcp(&address, &config, &restrictions, hours, sim_conf, timeouts, &mut outputs).await
+    } else {
+        cleanup(&travel_plan.get_regions()).await;
+        start(travel_plan, &restrictions, hours, &sim_conf, timeouts, &mut outputs).await
+    };
+    if let Err(e) = outputs.write(&engine::output_dir(), result.as_ref().err()) {
+        error!("Failed to write the combined outputs of the run: {}", e);
+    }
+    if result.is_err() {
+        error!("Aborted the simulation, the remaining engines were told to stop and write their outputs");
+        process::exit(1);
+    }
+}
+
+/// Writes the config with the travel matrices generated from the region locations and models in `spec_path`
+fn generate_travel_plan(mut config: Configuration, spec_path: &Path, output: Option<&Path>) {
+    let spec_file = File::open(spec_path).expect("Error while reading the travel matrix config");
+    let spec: TravelMatrixConfig = serde_json::from_reader(spec_file).expect("Error while parsing the travel matrix config");
+    if let Err(e) = config.generate_travel_matrices(&spec) {
+        error!("Could not generate the travel matrices: {}", e);
+        process::exit(1);
+    }
     config.validate();
-    cleanup(&travel_plan.get_regions()).await;
-    start(travel_plan, hours, &sim_conf).await;
+    let json = serde_json::to_string_pretty(&config).unwrap();
+    match output {
+        None => println!("{}", json),
+        Some(path) => fs::write(path, json).expect("Error while writing the config"),
+    }
 }
 
 async fn cleanup(regions: &Vec<String>) {
@@ -111,16 +209,43 @@ async fn create_topics(topic_names: Vec<&str>, kafka_admin: AdminClient<DefaultC
     }
 }
 
-async fn start(travel_plan: &TravelPlanConfig, hours: Range<i64>, sim_conf: &String) {
+async fn start(
+    travel_plan: &TravelPlanConfig,
+    restrictions: &TravelRestrictions,
+    hours: Range<i64>,
+    sim_conf: &String,
+    timeouts: Timeouts,
+    outputs: &mut RunOutputs,
+) -> Result<(), EngineFailure> {
     let mut producer = KafkaProducer::new();
 
     match producer.start_request(sim_conf) {
         Ok(_) => {
             debug!("Sent Request Successfully");
-            ticks::start_ticking(travel_plan, hours).await;
+            ticks::start_ticking(travel_plan, restrictions, hours, &mut KafkaTransport::new(), timeouts, outputs)
+                .await
+                .map(|_| ())
         }
         Err(_) => {
             panic!("Failed to send simulation request to engines");
         }
     }
 }
+
+async fn start_over_tcp(
+    address: &str,
+    config: &Configuration,
+    restrictions: &TravelRestrictions,
+    hours: Range<i64>,
+    sim_conf: String,
+    timeouts: Timeouts,
+    outputs: &mut RunOutputs,
+) -> Result<(), EngineFailure> {
+    let listener = TcpListener::bind(address).await.expect("Failed to listen for engines");
+    let mut transport = TcpOrchestratorTransport::accept(listener, &config.get_engine_ids(), sim_conf.into_bytes())
+        .await
+        .expect("Failed to connect the engines");
+    let result = ticks::start_ticking(config.get_travel_plan(), restrictions, hours, &mut transport, timeouts, outputs).await;
+    transport.shutdown().await;
+    result.map(|_| ())
+}
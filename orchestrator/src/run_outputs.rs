@@ -0,0 +1,276 @@
+/*
+ * EpiRust
+ * Copyright (c) 2020  ThoughtWorks, Inc.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ */
+
+//! Outputs combining every engine of a multi-engine run, so the per-engine CSVs don't have to be stitched together
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs::{self, File};
+use std::hash::Hasher;
+use std::path::{Path, PathBuf};
+
+use csv::Writer;
+use serde::Serialize;
+use time::format_description::well_known::Rfc3339;
+use time::macros::format_description;
+use time::OffsetDateTime;
+use twox_hash::XxHash64;
+
+use crate::config::Configuration;
+use crate::ticks::{Counts, EngineFailure, Outgoing, TickAcks};
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+struct RegionCounts {
+    hour: i32,
+    region: String,
+    susceptible: i32,
+    exposed: i32,
+    infected: i32,
+    hospitalized: i32,
+    recovered: i32,
+    deceased: i32,
+    quarantined_arrivals: usize,
+    hub_infections: usize,
+}
+
+impl RegionCounts {
+    fn new(region: &str, counts: &Counts, quarantined_arrivals: usize, hub_infections: usize) -> RegionCounts {
+        RegionCounts {
+            hour: counts.hour,
+            region: region.to_string(),
+            susceptible: counts.susceptible,
+            exposed: counts.exposed,
+            infected: counts.infected,
+            hospitalized: counts.hospitalized,
+            recovered: counts.recovered,
+            deceased: counts.deceased,
+            quarantined_arrivals,
+            hub_infections,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+struct TravelFlow<'a> {
+    from: &'a str,
+    to: &'a str,
+    commuters: usize,
+    migrators: usize,
+    blocked_commuters: usize,
+    blocked_migrators: usize,
+}
+
+#[derive(Serialize)]
+struct Manifest<'a> {
+    config_hash: &'a str,
+    engine_ids: &'a [String],
+    started_at: String,
+    ended_at: String,
+    last_hour: Option<i32>,
+    completed: bool,
+    failure: Option<String>,
+}
+
+/// Collects the counts and travellers the engines acknowledge every tick, and writes them out once the run is over
+pub struct RunOutputs {
+    prefix: String,
+    engine_ids: Vec<String>,
+    config_hash: String,
+    started_at: OffsetDateTime,
+    by_region: Vec<RegionCounts>,
+    totals: Vec<RegionCounts>,
+    flows: BTreeMap<(String, String), Outgoing>,
+}
+
+impl RunOutputs {
+    pub fn new(prefix: String, engine_ids: Vec<String>, config_hash: String) -> RunOutputs {
+        RunOutputs {
+            prefix,
+            engine_ids,
+            config_hash,
+            started_at: OffsetDateTime::now_utc(),
+            by_region: Vec::new(),
+            totals: Vec::new(),
+            flows: BTreeMap::new(),
+        }
+    }
+
+    /// Outputs named after the first engine's `output_file`, like the engines name theirs
+    pub fn for_config(config_path: &str, config: &Configuration) -> Result<RunOutputs, Box<dyn Error>> {
+        let output_file = config.get_engine_configs().into_iter().find_map(|(_, config)| config.get_output_file());
+        let started_at = OffsetDateTime::now_utc();
+        let format = format_description!("[year]-[month]-[day]T[hour]:[minute]:[second]");
+        let prefix = format!("{}_{}", output_file.unwrap_or_else(|| "simulation".to_string()), started_at.format(&format)?);
+        Ok(RunOutputs::new(prefix, config.get_engine_ids(), hash_file(config_path)?))
+    }
+
+    /// Records the acks of a tick every engine has acknowledged
+    pub fn record(&mut self, acks: &TickAcks) {
+        let mut total: Option<Counts> = None;
+        let mut quarantined = 0;
+        let mut hub_infections = 0;
+        for ack in acks.received() {
+            self.by_region.push(RegionCounts::new(&ack.engine_id, &ack.counts, ack.quarantined, ack.hub_infections));
+            total = Some(total.map_or(ack.counts, |total| total.add(&ack.counts)));
+            quarantined += ack.quarantined;
+            hub_infections += ack.hub_infections;
+            for (to, outgoing) in &ack.outgoing {
+                let flow = self.flows.entry((ack.engine_id.clone(), to.clone())).or_default();
+                flow.commuters += outgoing.commuters;
+                flow.migrators += outgoing.migrators;
+                flow.blocked_commuters += outgoing.blocked_commuters;
+                flow.blocked_migrators += outgoing.blocked_migrators;
+            }
+        }
+        if let Some(total) = total {
+            self.totals.push(RegionCounts::new("total", &total, quarantined, hub_infections));
+        }
+    }
+
+    /// Writes the counts, travel flows and manifest of the run into `output_dir`
+    pub fn write(&self, output_dir: &Path, failure: Option<&EngineFailure>) -> Result<(), Box<dyn Error>> {
+        fs::create_dir_all(output_dir)?;
+        write_csv(&self.path(output_dir, "regions.csv"), &self.by_region)?;
+        write_csv(&self.path(output_dir, "total.csv"), &self.totals)?;
+        let flows: Vec<TravelFlow> = self
+            .flows
+            .iter()
+            .map(|((from, to), outgoing)| TravelFlow {
+                from,
+                to,
+                commuters: outgoing.commuters,
+                migrators: outgoing.migrators,
+                blocked_commuters: outgoing.blocked_commuters,
+                blocked_migrators: outgoing.blocked_migrators,
+            })
+            .collect();
+        write_csv(&self.path(output_dir, "travel_flows.csv"), &flows)?;
+
+        let manifest = Manifest {
+            config_hash: &self.config_hash,
+            engine_ids: &self.engine_ids,
+            started_at: self.started_at.format(&Rfc3339)?,
+            ended_at: OffsetDateTime::now_utc().format(&Rfc3339)?,
+            last_hour: self.totals.last().map(|total| total.hour),
+            completed: failure.is_none(),
+            failure: failure.map(|failure| failure.to_string()),
+        };
+        serde_json::to_writer_pretty(File::create(self.path(output_dir, "manifest.json"))?, &manifest)?;
+        info!("Wrote the combined outputs of the run to {}", self.path(output_dir, "*").display());
+        Ok(())
+    }
+
+    fn path(&self, output_dir: &Path, suffix: &str) -> PathBuf {
+        output_dir.join(format!("{}_{}", self.prefix, suffix))
+    }
+}
+
+fn hash_file(path: &str) -> Result<String, Box<dyn Error>> {
+    let mut hasher = XxHash64::with_seed(0);
+    hasher.write(&fs::read(path)?);
+    Ok(format!("xxh64:{:016x}", hasher.finish()))
+}
+
+fn write_csv<T: Serialize>(path: &Path, rows: &[T]) -> Result<(), Box<dyn Error>> {
+    let mut wtr = Writer::from_path(path)?;
+    for row in rows {
+        wtr.serialize(row)?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ticks::TickAck;
+    use std::collections::HashMap;
+
+    fn ack(engine_id: &str, hour: i64, infected: i32, outgoing: &[(&str, usize, usize)]) -> TickAck {
+        TickAck {
+            engine_id: engine_id.to_string(),
+            hour,
+            counts: Counts::new(hour as i32, 100 - infected, 0, infected, 0, 0, 0),
+            outgoing: outgoing
+                .iter()
+                .map(|(to, commuters, migrators)| {
+                    (to.to_string(), Outgoing { commuters: *commuters, migrators: *migrators, ..Default::default() })
+                })
+                .collect::<HashMap<_, _>>(),
+            quarantined: 0,
+            hub_infections: 0,
+        }
+    }
+
+    fn engine_ids() -> Vec<String> {
+        vec!["engine1".to_string(), "engine2".to_string()]
+    }
+
+    fn record(outputs: &mut RunOutputs, hour: i64, acks: Vec<TickAck>) {
+        let mut tick_acks = TickAcks::new(&engine_ids());
+        tick_acks.reset(hour);
+        acks.into_iter().for_each(|ack| tick_acks.push(ack));
+        outputs.record(&tick_acks);
+    }
+
+    #[test]
+    fn should_combine_counts_and_travel_flows_of_all_engines() {
+        let mut outputs = RunOutputs::new("test".to_string(), engine_ids(), "xxh64:0".to_string());
+        record(&mut outputs, 7, vec![ack("engine1", 7, 1, &[("engine2", 10, 0)]), ack("engine2", 7, 2, &[("engine1", 5, 0)])]);
+        record(&mut outputs, 17, vec![ack("engine2", 17, 4, &[("engine1", 5, 0)]), ack("engine1", 17, 3, &[("engine2", 10, 0)])]);
+
+        assert_eq!(outputs.by_region.len(), 4);
+        assert_eq!(outputs.by_region[2], RegionCounts::new("engine1", &Counts::new(17, 97, 0, 3, 0, 0, 0), 0, 0));
+        assert_eq!(
+            outputs.totals,
+            vec![
+                RegionCounts::new("total", &Counts::new(7, 197, 0, 3, 0, 0, 0), 0, 0),
+                RegionCounts::new("total", &Counts::new(17, 193, 0, 7, 0, 0, 0), 0, 0),
+            ]
+        );
+        assert_eq!(
+            outputs.flows[&("engine1".to_string(), "engine2".to_string())],
+            Outgoing { commuters: 20, migrators: 0, ..Default::default() }
+        );
+        assert_eq!(
+            outputs.flows[&("engine2".to_string(), "engine1".to_string())],
+            Outgoing { commuters: 10, migrators: 0, ..Default::default() }
+        );
+    }
+
+    #[test]
+    fn should_write_outputs_and_manifest() {
+        let output_dir = std::env::temp_dir().join(format!("epirust_run_outputs_{}", std::process::id()));
+        let mut outputs = RunOutputs::new("test".to_string(), engine_ids(), "xxh64:0".to_string());
+        record(&mut outputs, 1, vec![ack("engine1", 1, 1, &[]), ack("engine2", 1, 0, &[("engine1", 0, 3)])]);
+
+        outputs.write(&output_dir, None).unwrap();
+
+        let total = fs::read_to_string(output_dir.join("test_total.csv")).unwrap();
+        assert_eq!(total, "hour,region,susceptible,exposed,infected,hospitalized,recovered,deceased,quarantined_arrivals,hub_infections\n1,total,199,0,1,0,0,0,0,0\n");
+        let flows = fs::read_to_string(output_dir.join("test_travel_flows.csv")).unwrap();
+        assert_eq!(flows, "from,to,commuters,migrators,blocked_commuters,blocked_migrators\nengine2,engine1,0,3,0,0\n");
+        let manifest: serde_json::Value =
+            serde_json::from_reader(File::open(output_dir.join("test_manifest.json")).unwrap()).unwrap();
+        assert_eq!(manifest["engine_ids"], serde_json::json!(["engine1", "engine2"]));
+        assert_eq!(manifest["last_hour"], 1);
+        assert_eq!(manifest["completed"], true);
+        fs::remove_dir_all(output_dir).unwrap();
+    }
+}
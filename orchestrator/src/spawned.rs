@@ -0,0 +1,273 @@
+/*
+ * EpiRust
+ * Copyright (c) 2020  ThoughtWorks, Inc.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ */
+
+//! Runs every engine as a child process of the orchestrator, connected to it over TCP
+
+use std::env;
+use std::future::Future;
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::process::{ExitStatus, Stdio};
+use std::time::Duration;
+
+use common::transport::TcpOrchestratorTransport;
+use futures::future::select_all;
+use tokio::net::TcpListener;
+use tokio::process::{Child, Command};
+use tokio::time::timeout;
+
+use crate::config::Configuration;
+use crate::run_outputs::RunOutputs;
+use crate::ticks;
+use crate::ticks::{EngineFailure, Timeouts};
+use crate::travel_restrictions::TravelRestrictions;
+
+/// How long engines get to write their outputs and exit after a failed run before they are killed
+const SHUTDOWN_GRACE_PERIOD: Duration = Duration::from_secs(30);
+
+const ENGINE_BINARIES: [&str; 2] = ["epirust-engine", "engine-app"];
+
+/// The engine binary next to the orchestrator's own, under either the name it is built with or the one it is
+/// packaged with
+pub fn default_engine_binary() -> Option<PathBuf> {
+    let dir = env::current_exe().ok()?.parent()?.to_path_buf();
+    ENGINE_BINARIES.iter().map(|name| dir.join(name).with_extension(env::consts::EXE_EXTENSION)).find(|path| path.is_file())
+}
+
+fn engine_command(engine_binary: &Path, engine_id: &str, orchestrator: &str, threads: u32) -> Command {
+    let mut command = Command::new(engine_binary);
+    command
+        .args(["--id", engine_id, "--orchestrator", orchestrator, "--listen", "127.0.0.1:0"])
+        .args(["--threads", &threads.to_string()])
+        .stdin(Stdio::null())
+        .kill_on_drop(true);
+    command
+}
+
+/// The engine processes of a run, killed if they are dropped before they exit
+struct Engines {
+    running: Vec<(String, Child)>,
+}
+
+impl Engines {
+    fn spawn(commands: Vec<(String, Command)>) -> io::Result<Engines> {
+        let mut running = Vec::new();
+        for (engine_id, mut command) in commands {
+            let child = command.spawn()?;
+            debug!("Started engine {} as process {:?}", engine_id, child.id());
+            running.push((engine_id, child));
+        }
+        Ok(Engines { running })
+    }
+
+    /// Waits for the next engine to exit. Never completes once all of them have.
+    async fn next_exit(&mut self) -> (String, io::Result<ExitStatus>) {
+        if self.running.is_empty() {
+            return futures::future::pending().await;
+        }
+        let waits = self.running.iter_mut().map(|(_, child)| Box::pin(child.wait()));
+        let (status, index, _) = select_all(waits).await;
+        let (engine_id, _) = self.running.remove(index);
+        (engine_id, status)
+    }
+
+    /// Waits for every engine to exit, killing the ones still running after `grace_period`
+    async fn shut_down(mut self, grace_period: Option<Duration>) {
+        while !self.running.is_empty() {
+            let exit = match grace_period {
+                None => Ok(self.next_exit().await),
+                Some(grace_period) => timeout(grace_period, self.next_exit()).await,
+            };
+            match exit {
+                Ok((engine_id, status)) => log_exit(&engine_id, status),
+                Err(_) => {
+                    for (engine_id, child) in self.running.iter_mut() {
+                        warn!("Engine {} did not exit, killing it", engine_id);
+                        if let Err(e) = child.kill().await {
+                            error!("Failed to kill engine {}: {}", engine_id, e);
+                        }
+                    }
+                    return;
+                }
+            }
+        }
+    }
+}
+
+fn log_exit(engine_id: &str, status: io::Result<ExitStatus>) {
+    match status {
+        Ok(status) if status.success() => debug!("Engine {} finished", engine_id),
+        Ok(status) => error!("Engine {} exited with {}", engine_id, status),
+        Err(e) => error!("Failed to wait for engine {}: {}", engine_id, e),
+    }
+}
+
+/// Starts a process of `engine_binary` for every engine in the config, runs the simulation with them over TCP on
+/// the loopback interface, and waits for all of them to exit
+pub async fn start(
+    config: &Configuration,
+    hours: Range<i64>,
+    sim_conf: String,
+    engine_binary: &Path,
+    threads: u32,
+    timeouts: Timeouts,
+    outputs: &mut RunOutputs,
+) -> Result<(), EngineFailure> {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("Failed to listen for engines");
+    let address = listener.local_addr().expect("Failed to listen for engines").to_string();
+    let engine_ids = config.get_engine_ids();
+    let commands = engine_ids
+        .iter()
+        .map(|engine_id| (engine_id.clone(), engine_command(engine_binary, engine_id, &address, threads)))
+        .collect();
+    let mut engines = Engines::spawn(commands)
+        .unwrap_or_else(|e| panic!("Failed to start the engines with {}: {}", engine_binary.display(), e));
+    info!("Started {} engines, waiting for them on {}", engines.running.len(), address);
+
+    let accept = TcpOrchestratorTransport::accept(listener, &engine_ids, sim_conf.into_bytes());
+    let mut transport = tokio::select! {
+        transport = accept => transport.expect("Failed to connect the engines"),
+        (engine_id, status) = engines.next_exit() => {
+            log_exit(&engine_id, status);
+            engines.shut_down(Some(Duration::ZERO)).await;
+            panic!("Engine {} exited before the simulation started", engine_id);
+        }
+    };
+
+    let restrictions = TravelRestrictions::new(&config.get_engine_configs());
+    let ticking = ticks::start_ticking(config.get_travel_plan(), &restrictions, hours, &mut transport, timeouts, outputs);
+    let result = tick_while_running(ticking, &mut engines).await;
+    transport.shutdown().await;
+    engines.shut_down(result.as_ref().err().map(|_| SHUTDOWN_GRACE_PERIOD)).await;
+    result
+}
+
+/// Waits for `ticking` to finish, failing as soon as any engine exits with an error. Engines run through their hours
+/// on their own after the last tick, so a fast one may finish and exit while the others are still acknowledging it.
+/// One that exits before acknowledging a tick it was sent is reported by the ticks as disconnected.
+async fn tick_while_running<T>(
+    ticking: impl Future<Output = Result<T, EngineFailure>>,
+    engines: &mut Engines,
+) -> Result<(), EngineFailure> {
+    tokio::pin!(ticking);
+    loop {
+        tokio::select! {
+            result = &mut ticking => return result.map(|_| ()),
+            (engine_id, status) = engines.next_exit() => {
+                if matches!(&status, Ok(status) if status.success()) {
+                    log_exit(&engine_id, status);
+                    continue;
+                }
+                let failure = EngineFailure::exited(&engine_id, describe_exit(&status));
+                log_exit(&engine_id, status);
+                return Err(failure);
+            }
+        }
+    }
+}
+
+fn describe_exit(status: &io::Result<ExitStatus>) -> String {
+    match status {
+        Ok(status) => status.to_string(),
+        Err(e) => format!("an unknown status, waiting for it failed: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shell(script: &str) -> Command {
+        let mut command = Command::new("sh");
+        command.args(["-c", script]).kill_on_drop(true);
+        command
+    }
+
+    #[test]
+    fn should_point_engines_at_the_orchestrator() {
+        let command = engine_command(Path::new("/opt/epirust/epirust-engine"), "engine1", "127.0.0.1:7000", 2);
+        let command = command.as_std();
+        assert_eq!(command.get_program(), "/opt/epirust/epirust-engine");
+        let args: Vec<_> = command.get_args().collect();
+        assert_eq!(
+            args,
+            vec!["--id", "engine1", "--orchestrator", "127.0.0.1:7000", "--listen", "127.0.0.1:0", "--threads", "2"]
+        );
+    }
+
+    #[tokio::test]
+    async fn should_report_engines_as_they_exit() {
+        let mut engines =
+            Engines::spawn(vec![("engine1".to_string(), shell("sleep 0.2")), ("engine2".to_string(), shell("exit 3"))]).unwrap();
+
+        let (engine_id, status) = engines.next_exit().await;
+        assert_eq!((engine_id.as_str(), status.unwrap().code()), ("engine2", Some(3)));
+        let (engine_id, status) = engines.next_exit().await;
+        assert_eq!((engine_id.as_str(), status.unwrap().code()), ("engine1", Some(0)));
+    }
+
+    #[tokio::test]
+    async fn should_fail_when_an_engine_exits_while_ticking() {
+        let mut engines =
+            Engines::spawn(vec![("engine1".to_string(), shell("sleep 60")), ("engine2".to_string(), shell("exit 3"))]).unwrap();
+
+        let ticking = futures::future::pending::<Result<(), EngineFailure>>();
+        let result = timeout(Duration::from_secs(5), tick_while_running(ticking, &mut engines)).await.unwrap();
+
+        assert_eq!(result.unwrap_err().to_string(), "The simulation stopped: engine engine2 exited with exit status: 3");
+        assert_eq!(engines.running.len(), 1);
+        let shut_down = timeout(Duration::from_secs(5), engines.shut_down(Some(Duration::from_millis(100)))).await;
+        assert!(shut_down.is_ok());
+    }
+
+    #[tokio::test]
+    async fn should_keep_ticking_when_an_engine_finishes_first() {
+        let mut engines =
+            Engines::spawn(vec![("engine1".to_string(), shell("sleep 60")), ("engine2".to_string(), shell("exit 0"))]).unwrap();
+
+        let ticking = async {
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            Ok::<_, EngineFailure>(())
+        };
+        let result = tick_while_running(ticking, &mut engines).await;
+
+        assert!(result.is_ok());
+        assert_eq!(engines.running.len(), 1);
+        assert_eq!(engines.running[0].0, "engine1");
+    }
+
+    #[tokio::test]
+    async fn should_finish_ticking_while_the_engines_run() {
+        let mut engines = Engines::spawn(vec![("engine1".to_string(), shell("sleep 60"))]).unwrap();
+
+        let result = tick_while_running(async { Ok::<_, EngineFailure>(()) }, &mut engines).await;
+
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn should_kill_engines_that_do_not_exit_in_time() {
+        let engines = Engines::spawn(vec![("engine1".to_string(), shell("sleep 60"))]).unwrap();
+
+        let shut_down = timeout(Duration::from_secs(5), engines.shut_down(Some(Duration::from_millis(100)))).await;
+
+        assert!(shut_down.is_ok());
+    }
+}
@@ -16,125 +16,378 @@
  * along with this program.  If not, see <http://www.gnu.org/licenses/>.
  *
  */
-use crate::kafka_consumer::KafkaConsumer;
-use crate::kafka_producer::KafkaProducer;
 use common::config::TravelPlanConfig;
-use futures::StreamExt;
-use rdkafka::error::KafkaError;
-use rdkafka::message::BorrowedMessage;
-use rdkafka::Message;
+use common::models::custom_types::Day;
+use common::transport::OrchestratorTransport;
 use std::collections::HashMap;
 use std::error::Error;
+use std::fmt;
 use std::ops::Range;
+use std::time::Duration;
+use tokio::time::{timeout_at, Instant};
+
+use crate::run_outputs::RunOutputs;
+use crate::travel_restrictions::TravelRestrictions;
 
 const ROUTINE_TRAVEL_START_TIME: i64 = 7;
 const ROUTINE_TRAVEL_END_TIME: i64 = 17;
 
 //Note: these ticks are safe, they don't cause Lyme disease
 
-pub async fn start_ticking(travel_plan: &TravelPlanConfig, hours: Range<i64>) {
+/// How long to wait on the engines before giving up on the simulation
+#[derive(Clone, Copy, Debug)]
+pub struct Timeouts {
+    /// Longest wait for every engine to acknowledge a tick, `None` to wait as long as they send heartbeats
+    pub tick: Option<Duration>,
+    /// Longest an engine that has not acknowledged the current tick may go without acking or sending a heartbeat
+    pub heartbeat: Duration,
+}
+
+/// Ticks the engines through the simulation, returning the acknowledgements for the last tick they all received.
+///
+/// If some engines fail to acknowledge a tick in time, the others are told to terminate so they write out what they
+/// have simulated so far, and the engines that failed are reported.
+pub async fn start_ticking<O: OrchestratorTransport>(
+    travel_plan: &TravelPlanConfig,
+    restrictions: &TravelRestrictions,
+    hours: Range<i64>,
+    transport: &mut O,
+    timeouts: Timeouts,
+    outputs: &mut RunOutputs,
+) -> Result<TickAcks, EngineFailure> {
     let mut acks: TickAcks = TickAcks::new(&travel_plan.get_regions());
-    let mut producer = KafkaProducer::new();
-    let consumer = KafkaConsumer::new();
-    let mut message_stream = consumer.start_message_stream();
+    let mut heard_from: HashMap<String, (Instant, i64)> = HashMap::new();
+    let mut failure = None;
     let mut should_terminate = false;
-    let is_commute_enabled = travel_plan.commute.enabled;
-    let is_migration_enabled = travel_plan.migration.enabled;
+    let mut is_commute_active = false;
+    let first_hour = hours.start;
     for h in hours {
-        if !is_commute_enabled && (h % 24 == ROUTINE_TRAVEL_END_TIME || h % 24 == ROUTINE_TRAVEL_START_TIME) {
-            continue;
+        if h == first_hour || h % 24 == 0 {
+            // the travel phases only change from one day to the next
+            is_commute_active = travel_plan.is_commute_active((h / 24) as Day);
         }
-        if !is_migration_enabled && h % 24 == 0 {
+        if !is_commute_active && (h % 24 == ROUTINE_TRAVEL_END_TIME || h % 24 == ROUTINE_TRAVEL_START_TIME) {
             continue;
         }
+        // the end of every day is ticked whether or not anyone migrates, so the outputs get a row per day
         if h > 1 && h % 24 != 0 && h % 24 != ROUTINE_TRAVEL_START_TIME && h % 24 != ROUTINE_TRAVEL_END_TIME {
             continue;
         }
+        let tick = Tick::new(h, should_terminate, restrictions.between(&acks));
+        transport.send_tick(serde_json::to_vec(&tick).unwrap());
+        if should_terminate {
+            break;
+        }
+        debug!("Sent tick successfully");
         acks.reset(h);
-        let tick = Tick::new(h, should_terminate);
+        if let Err(e) = receive_acks(transport, &mut acks, &mut heard_from, timeouts).await {
+            error!("{}", e);
+            failure = Some(e);
+            should_terminate = true;
+            continue;
+        }
+        outputs.record(&acks);
+        should_terminate = acks.should_terminate();
+    }
+    match failure {
+        None => Ok(acks),
+        Some(failure) => Err(failure),
+    }
+}
 
-        let tick_ack_str = serde_json::to_string(&tick).unwrap();
-        match producer.send_tick(&tick_ack_str) {
-            Ok(_) => {
-                if should_terminate {
-                    break;
-                }
-                debug!("Sent tick successfully");
-                while let Some(message) = message_stream.next().await {
-                    let tick_ack = TickAck::parse_message(message);
-                    match tick_ack {
-                        Err(e) => {
-                            error!(
-                                "Received a message, but could not parse it.\n\
-                                Error Details: {}",
-                                e
-                            )
-                        }
-                        Ok(ack) => {
-                            debug!("Received tick acknowledgement successfully");
-                            acks.push(ack);
-                            if acks.all_received() {
-                                should_terminate = acks.should_terminate();
-                                break;
-                            }
-                        }
-                    };
-                }
+async fn receive_acks<O: OrchestratorTransport>(
+    transport: &mut O,
+    acks: &mut TickAcks,
+    heard_from: &mut HashMap<String, (Instant, i64)>,
+    timeouts: Timeouts,
+) -> Result<(), EngineFailure> {
+    let tick_sent = Instant::now();
+    let tick_deadline = timeouts.tick.map(|timeout| tick_sent + timeout);
+    while !acks.all_received() {
+        let missing = acks.missing();
+        let last_heard = |engine_id: &String| last_heard(heard_from, engine_id, tick_sent);
+        let now = Instant::now();
+        let reason = if missing.iter().any(|engine_id| transport.gone().contains(engine_id)) {
+            Some(FailureReason::Disconnected)
+        } else if tick_deadline.is_some_and(|deadline| deadline <= now) {
+            Some(FailureReason::TimedOut(timeouts.tick.unwrap()))
+        } else if missing.iter().any(|engine_id| last_heard(engine_id) + timeouts.heartbeat <= now) {
+            Some(FailureReason::Silent(timeouts.heartbeat))
+        } else {
+            None
+        };
+        if let Some(reason) = reason {
+            return Err(EngineFailure::new(acks.current_hour, reason, &missing, heard_from, tick_sent, timeouts.heartbeat));
+        }
+
+        let heartbeat_deadline = missing.iter().map(|engine_id| last_heard(engine_id) + timeouts.heartbeat).min().unwrap();
+        let deadline = tick_deadline.map_or(heartbeat_deadline, |deadline| deadline.min(heartbeat_deadline));
+        let message = match timeout_at(deadline, transport.receive_ack()).await {
+            Err(_) => continue,
+            // an engine that has acknowledged the tick may finish its last hours and go away before the others
+            Ok(None) if !transport.gone().is_empty() => continue,
+            Ok(None) => {
+                let reason = FailureReason::Disconnected;
+                return Err(EngineFailure::new(acks.current_hour, reason, &missing, heard_from, tick_sent, timeouts.heartbeat));
+            }
+            Ok(Some(message)) => message,
+        };
+        match EngineMessage::parse_message(&message) {
+            Err(e) => {
+                error!(
+                    "Received a message, but could not parse it.\n\
+                    Error Details: {}",
+                    e
+                )
+            }
+            Ok(EngineMessage::Ack(ack)) => {
+                debug!("Received tick acknowledgement successfully");
+                heard_from.insert(ack.engine_id.clone(), (Instant::now(), ack.hour));
+                acks.push(ack);
+            }
+            Ok(EngineMessage::Heartbeat(heartbeat)) => {
+                trace!("Received heartbeat from {} at hour {}", heartbeat.engine_id, heartbeat.hour);
+                heard_from.insert(heartbeat.engine_id, (Instant::now(), heartbeat.hour));
+            }
+        };
+    }
+    Ok(())
+}
+
+/// When the engine was last heard from. Engines wait for ticks without sending heartbeats, so their silence only counts
+/// from when the tick was sent.
+fn last_heard(heard_from: &HashMap<String, (Instant, i64)>, engine_id: &String, tick_sent: Instant) -> Instant {
+    heard_from.get(engine_id).map_or(tick_sent, |(at, _)| *at.max(&tick_sent))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FailureReason {
+    TimedOut(Duration),
+    Silent(Duration),
+    Disconnected,
+    /// An engine process exited before the simulation ended
+    Exited {
+        engine_id: String,
+        status: String,
+    },
+}
+
+impl fmt::Display for FailureReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FailureReason::TimedOut(timeout) => write!(f, "no acknowledgement within {}s", timeout.as_secs_f32()),
+            FailureReason::Silent(timeout) => write!(f, "no acknowledgement or heartbeat for {}s", timeout.as_secs_f32()),
+            FailureReason::Disconnected => write!(f, "the engines disconnected"),
+            FailureReason::Exited { engine_id, status } => write!(f, "engine {} exited with {}", engine_id, status),
+        }
+    }
+}
+
+/// An engine that did not acknowledge a tick
+#[derive(Debug)]
+struct Unacknowledged {
+    engine_id: String,
+    /// How long ago, and at which hour, the engine was last heard from
+    last_heard: Option<(Duration, i64)>,
+    /// Whether it went without a heartbeat for longer than the heartbeat timeout. Engines that are still sending them
+    /// are most likely waiting on travellers from the silent ones.
+    silent: bool,
+}
+
+/// The engines that did not acknowledge a tick, and when each was last heard from, or the engine that exited
+/// before the simulation ended
+#[derive(Debug)]
+pub struct EngineFailure {
+    /// The tick that was not acknowledged, `None` when an engine exited
+    hour: Option<i64>,
+    reason: FailureReason,
+    missing: Vec<Unacknowledged>,
+}
+
+impl EngineFailure {
+    fn new(
+        hour: i64,
+        reason: FailureReason,
+        missing: &[String],
+        heard_from: &HashMap<String, (Instant, i64)>,
+        tick_sent: Instant,
+        heartbeat: Duration,
+    ) -> EngineFailure {
+        let now = Instant::now();
+        let missing = missing
+            .iter()
+            .map(|engine_id| Unacknowledged {
+                engine_id: engine_id.clone(),
+                last_heard: heard_from.get(engine_id).map(|(at, hour)| (at.elapsed(), *hour)),
+                silent: last_heard(heard_from, engine_id, tick_sent) + heartbeat <= now,
+            })
+            .collect();
+        EngineFailure { hour: Some(hour), reason, missing }
+    }
+
+    pub fn exited(engine_id: &str, status: String) -> EngineFailure {
+        EngineFailure {
+            hour: None,
+            reason: FailureReason::Exited { engine_id: engine_id.to_string(), status },
+            missing: Vec::new(),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn missing_engines(&self) -> Vec<&String> {
+        self.missing.iter().map(|engine| &engine.engine_id).collect()
+    }
+
+    #[cfg(test)]
+    fn silent_engines(&self) -> Vec<&String> {
+        self.missing.iter().filter(|engine| engine.silent).map(|engine| &engine.engine_id).collect()
+    }
+}
+
+impl fmt::Display for EngineFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.hour {
+            Some(hour) => write!(f, "Engines stopped acknowledging the tick for hour {}: {}", hour, self.reason)?,
+            None => write!(f, "The simulation stopped: {}", self.reason)?,
+        }
+        for silent in [true, false] {
+            let mut engines = self.missing.iter().filter(|engine| engine.silent == silent).peekable();
+            match (engines.peek(), silent) {
+                (None, _) => continue,
+                (Some(_), true) => write!(f, "\n  went silent:")?,
+                (Some(_), false) => write!(f, "\n  still sending heartbeats, most likely waiting on the others:")?,
             }
-            Err(_) => {
-                panic!("Failed to send simulation request to engines");
+            for engine in engines {
+                match engine.last_heard {
+                    None => write!(f, "\n    {}: never heard from", engine.engine_id)?,
+                    Some((ago, hour)) => {
+                        write!(f, "\n    {}: last heard from {:.1}s ago, at hour {}", engine.engine_id, ago.as_secs_f32(), hour)?
+                    }
+                }
             }
         }
+        Ok(())
     }
 }
 
+impl Error for EngineFailure {}
+
 #[derive(Debug, Serialize)]
 pub struct Tick {
     hour: i64,
     terminate: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    restrictions: Vec<TravelRestriction>,
 }
 
 impl Tick {
-    pub fn new(hour: i64, terminate: bool) -> Tick {
-        Tick { hour, terminate }
+    pub fn new(hour: i64, terminate: bool, restrictions: Vec<TravelRestriction>) -> Tick {
+        Tick { hour, terminate, restrictions }
+    }
+}
+
+/// Travel from one region to another that is held back until the next tick
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct TravelRestriction {
+    from: String,
+    to: String,
+    /// Share of the travellers allowed through
+    travel_allowed: f64,
+    /// Chance that an infected traveller is detected and turned back
+    turned_back: f64,
+}
+
+impl TravelRestriction {
+    pub fn new(from: &str, to: &str, travel_allowed: f64, turned_back: f64) -> TravelRestriction {
+        TravelRestriction { from: from.to_string(), to: to.to_string(), travel_allowed, turned_back }
     }
 }
 
+/// Travellers an engine sent to one region, and those it held back because of travel restrictions or screening
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(default)]
+pub struct Outgoing {
+    pub commuters: usize,
+    pub migrators: usize,
+    pub blocked_commuters: usize,
+    pub blocked_migrators: usize,
+}
+
 #[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
 pub struct TickAck {
+    pub(crate) engine_id: String,
+    pub(crate) hour: i64,
+    pub(crate) counts: Counts,
+    #[serde(default)]
+    pub(crate) outgoing: HashMap<String, Outgoing>,
+    /// Arrivals quarantined by entry screening since the previous ack
+    #[serde(default)]
+    pub(crate) quarantined: usize,
+    /// Travellers infected in the region's transit hub since the previous ack
+    #[serde(default)]
+    pub(crate) hub_infections: usize,
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+pub struct Heartbeat {
     engine_id: String,
     hour: i64,
-    counts: Counts,
 }
 
-impl TickAck {
-    pub fn parse_message(message: Result<BorrowedMessage, KafkaError>) -> Result<TickAck, Box<dyn Error>> {
-        let borrowed_message = message?;
-        let parsed_message = borrowed_message.payload_view::<str>().unwrap()?;
-        debug!("Received: {}", parsed_message);
-        serde_json::from_str(parsed_message).map_err(|e| e.into())
+/// Everything engines send back on the acknowledgements channel
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum EngineMessage {
+    // acks are tried first, a heartbeat only has a subset of their fields
+    Ack(TickAck),
+    Heartbeat(Heartbeat),
+}
+
+impl EngineMessage {
+    pub fn parse_message(message: &[u8]) -> Result<EngineMessage, Box<dyn Error>> {
+        debug!("Received: {}", String::from_utf8_lossy(message));
+        serde_json::from_slice(message).map_err(|e| e.into())
     }
 }
 
 #[derive(Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
 pub struct Counts {
-    hour: i32,
-    susceptible: i32,
-    exposed: i32,
-    infected: i32,
-    hospitalized: i32,
-    recovered: i32,
-    deceased: i32,
+    pub(crate) hour: i32,
+    pub(crate) susceptible: i32,
+    pub(crate) exposed: i32,
+    pub(crate) infected: i32,
+    pub(crate) hospitalized: i32,
+    pub(crate) recovered: i32,
+    pub(crate) deceased: i32,
 }
 
 impl Counts {
+    /// Counts of both regions together, at the hour of this one
+    pub fn add(&self, other: &Counts) -> Counts {
+        Counts {
+            hour: self.hour,
+            susceptible: self.susceptible + other.susceptible,
+            exposed: self.exposed + other.exposed,
+            infected: self.infected + other.infected,
+            hospitalized: self.hospitalized + other.hospitalized,
+            recovered: self.recovered + other.recovered,
+            deceased: self.deceased + other.deceased,
+        }
+    }
+
     #[cfg(test)]
     pub fn new(hr: i32, s: i32, e: i32, i: i32, h: i32, r: i32, d: i32) -> Counts {
         Counts { hour: hr, susceptible: s, exposed: e, infected: i, hospitalized: h, recovered: r, deceased: d }
     }
+
+    #[cfg(test)]
+    pub fn total(&self) -> i32 {
+        self.susceptible + self.exposed + self.infected + self.hospitalized + self.recovered + self.deceased
+    }
 }
 
 /// stores a record of all the acks received for a tick
+#[derive(Debug)]
 pub struct TickAcks {
     acks: HashMap<String, TickAck>,
     current_hour: i64,
@@ -172,6 +425,21 @@ impl TickAcks {
         self.acks.keys().count() == self.engines.len()
     }
 
+    /// The acks received for the current tick, in the order of the engines
+    pub fn received(&self) -> impl Iterator<Item = &TickAck> {
+        self.engines.iter().filter_map(move |engine_id| self.acks.get(engine_id))
+    }
+
+    /// Engines that have not acknowledged the current tick yet
+    pub fn missing(&self) -> Vec<String> {
+        self.engines.iter().filter(|engine_id| !self.acks.contains_key(*engine_id)).cloned().collect()
+    }
+
+    #[cfg(test)]
+    pub fn total_population(&self) -> i32 {
+        self.acks.values().map(|ack| ack.counts.total()).sum()
+    }
+
     pub fn should_terminate(&self) -> bool {
         let total_exposed: i32 = self.acks.values().map(|ack| ack.counts.exposed).sum();
         let total_infected: i32 = self.acks.values().map(|ack| ack.counts.infected).sum();
@@ -183,13 +451,22 @@ impl TickAcks {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use common::transport::{EngineTransport, TcpEngineTransport, TcpOrchestratorTransport};
+    use tokio::net::TcpListener;
 
     #[test]
     fn should_push_ack() {
         let engines = vec!["engine1".to_string(), "engine2".to_string()];
         let mut acks = TickAcks::new(&engines);
         acks.reset(22);
-        let ack = TickAck { engine_id: "engine1".to_string(), hour: 22, counts: Counts::new(1, 100, 0, 0, 0, 0, 0) };
+        let ack = TickAck {
+            engine_id: "engine1".to_string(),
+            hour: 22,
+            counts: Counts::new(1, 100, 0, 0, 0, 0, 0),
+            outgoing: HashMap::new(),
+            quarantined: 0,
+            hub_infections: 0,
+        };
         acks.push(ack.clone());
 
         assert_eq!(*acks.acks.get("engine1").unwrap(), ack);
@@ -208,26 +485,297 @@ mod tests {
     fn should_terminate_when_exposed_and_infected_and_hospitalized_are_zero() {
         let mut acks = TickAcks::new(&vec!["engine1".to_string(), "engine2".to_string()]);
         acks.reset(1);
-        acks.push(TickAck { engine_id: "engine1".to_string(), hour: 1, counts: Counts::new(1, 99, 0, 1, 0, 0, 0) });
-        acks.push(TickAck { engine_id: "engine2".to_string(), hour: 1, counts: Counts::new(1, 99, 0, 1, 0, 0, 0) });
+        acks.push(TickAck {
+            engine_id: "engine1".to_string(),
+            hour: 1,
+            counts: Counts::new(1, 99, 0, 1, 0, 0, 0),
+            outgoing: HashMap::new(),
+            quarantined: 0,
+            hub_infections: 0,
+        });
+        acks.push(TickAck {
+            engine_id: "engine2".to_string(),
+            hour: 1,
+            counts: Counts::new(1, 99, 0, 1, 0, 0, 0),
+            outgoing: HashMap::new(),
+            quarantined: 0,
+            hub_infections: 0,
+        });
         assert!(!acks.should_terminate());
 
         acks.reset(2);
-        acks.push(TickAck { engine_id: "engine1".to_string(), hour: 2, counts: Counts::new(2, 99, 0, 0, 1, 0, 0) });
-        acks.push(TickAck { engine_id: "engine2".to_string(), hour: 2, counts: Counts::new(2, 100, 0, 0, 0, 0, 0) });
+        acks.push(TickAck {
+            engine_id: "engine1".to_string(),
+            hour: 2,
+            counts: Counts::new(2, 99, 0, 0, 1, 0, 0),
+            outgoing: HashMap::new(),
+            quarantined: 0,
+            hub_infections: 0,
+        });
+        acks.push(TickAck {
+            engine_id: "engine2".to_string(),
+            hour: 2,
+            counts: Counts::new(2, 100, 0, 0, 0, 0, 0),
+            outgoing: HashMap::new(),
+            quarantined: 0,
+            hub_infections: 0,
+        });
         assert!(!acks.should_terminate());
 
         acks.reset(3);
-        acks.push(TickAck { engine_id: "engine1".to_string(), hour: 3, counts: Counts::new(2, 99, 1, 0, 0, 0, 0) });
-        acks.push(TickAck { engine_id: "engine2".to_string(), hour: 3, counts: Counts::new(2, 100, 0, 0, 0, 0, 0) });
+        acks.push(TickAck {
+            engine_id: "engine1".to_string(),
+            hour: 3,
+            counts: Counts::new(2, 99, 1, 0, 0, 0, 0),
+            outgoing: HashMap::new(),
+            quarantined: 0,
+            hub_infections: 0,
+        });
+        acks.push(TickAck {
+            engine_id: "engine2".to_string(),
+            hour: 3,
+            counts: Counts::new(2, 100, 0, 0, 0, 0, 0),
+            outgoing: HashMap::new(),
+            quarantined: 0,
+            hub_infections: 0,
+        });
         assert!(!acks.should_terminate());
 
         acks.reset(4);
-        acks.push(TickAck { engine_id: "engine1".to_string(), hour: 4, counts: Counts::new(3, 100, 0, 0, 0, 0, 0) });
-        acks.push(TickAck { engine_id: "engine2".to_string(), hour: 4, counts: Counts::new(3, 100, 0, 0, 0, 0, 0) });
+        acks.push(TickAck {
+            engine_id: "engine1".to_string(),
+            hour: 4,
+            counts: Counts::new(3, 100, 0, 0, 0, 0, 0),
+            outgoing: HashMap::new(),
+            quarantined: 0,
+            hub_infections: 0,
+        });
+        acks.push(TickAck {
+            engine_id: "engine2".to_string(),
+            hour: 4,
+            counts: Counts::new(3, 100, 0, 0, 0, 0, 0),
+            outgoing: HashMap::new(),
+            quarantined: 0,
+            hub_infections: 0,
+        });
         assert!(acks.should_terminate());
     }
 
+    fn travel_plan() -> TravelPlanConfig {
+        serde_json::from_str(
+            r#"{
+                "regions": ["engine1", "engine2"],
+                "migration": {"enabled": false, "matrix": null, "start_migration_hour": 0, "end_migration_hour": 0},
+                "commute": {"enabled": true, "matrix": [[0, 10], [10, 0]]}
+            }"#,
+        )
+        .unwrap()
+    }
+
+    fn outputs() -> RunOutputs {
+        RunOutputs::new("test".to_string(), travel_plan().get_regions(), "xxh64:0".to_string())
+    }
+
+    fn ack(engine_id: &str, hour: i64) -> Vec<u8> {
+        format!(
+            r#"{{"engine_id": "{}", "hour": {}, "locked_down": false, "counts": {{"hour": {}, "susceptible": 99,
+                "exposed": 0, "infected": 1, "hospitalized": 0, "recovered": 0, "deceased": 0}}}}"#,
+            engine_id, hour, hour
+        )
+        .into_bytes()
+    }
+
+    fn read_tick(payload: &[u8]) -> serde_json::Value {
+        serde_json::from_slice(payload).unwrap()
+    }
+
+    #[test]
+    fn should_parse_acks_and_heartbeats() {
+        let message = EngineMessage::parse_message(&ack("engine1", 7)).unwrap();
+        assert!(matches!(message, EngineMessage::Ack(TickAck { hour: 7, .. })));
+
+        let message = EngineMessage::parse_message(br#"{"engine_id": "engine1", "hour": 9}"#).unwrap();
+        assert_eq!(message, EngineMessage::Heartbeat(Heartbeat { engine_id: "engine1".to_string(), hour: 9 }));
+    }
+
+    #[test]
+    fn should_list_engines_missing_an_ack() {
+        let mut acks = TickAcks::new(&["engine1".to_string(), "engine2".to_string()]);
+        acks.reset(1);
+        acks.push(TickAck {
+            engine_id: "engine1".to_string(),
+            hour: 1,
+            counts: Counts::new(1, 99, 0, 1, 0, 0, 0),
+            outgoing: HashMap::new(),
+            quarantined: 0,
+            hub_infections: 0,
+        });
+        assert_eq!(acks.missing(), vec!["engine2".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn should_report_silent_engines_and_terminate_the_others() {
+        let (mut orchestrator, mut engines) = common::transport::connect(&travel_plan().get_regions());
+        let timeouts = Timeouts { tick: None, heartbeat: Duration::from_millis(200) };
+        engines[0].send_ack(ack("engine1", 1));
+        engines[1].send_ack(br#"{"engine_id": "engine2", "hour": 1}"#.to_vec());
+
+        let failure =
+            start_ticking(&travel_plan(), &TravelRestrictions::new(&[]), 1..24, &mut orchestrator, timeouts, &mut outputs())
+                .await
+                .unwrap_err();
+
+        assert_eq!(failure.hour, Some(1));
+        assert_eq!(failure.reason, FailureReason::Silent(Duration::from_millis(200)));
+        assert_eq!(failure.missing_engines(), vec!["engine2"]);
+        assert_eq!(failure.silent_engines(), vec!["engine2"]);
+        assert!(failure.to_string().contains("went silent:\n    engine2: last heard from"));
+        let first = read_tick(&engines[0].receive_tick().await.unwrap());
+        assert_eq!((first["hour"].as_i64(), first["terminate"].as_bool()), (Some(1), Some(false)));
+        let last = read_tick(&engines[0].receive_tick().await.unwrap());
+        assert_eq!((last["hour"].as_i64(), last["terminate"].as_bool()), (Some(7), Some(true)));
+    }
+
+    #[tokio::test]
+    async fn should_tell_silent_engines_from_the_ones_waiting_on_them() {
+        let (mut orchestrator, mut engines) = common::transport::connect(&travel_plan().get_regions());
+        let timeouts = Timeouts { tick: None, heartbeat: Duration::from_millis(300) };
+        let mut waiting = engines.remove(0);
+        let heartbeats = tokio::spawn(async move {
+            for _ in 0..20 {
+                waiting.send_ack(br#"{"engine_id": "engine1", "hour": 1}"#.to_vec());
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        });
+
+        let failure =
+            start_ticking(&travel_plan(), &TravelRestrictions::new(&[]), 1..24, &mut orchestrator, timeouts, &mut outputs())
+                .await
+                .unwrap_err();
+
+        assert_eq!(failure.reason, FailureReason::Silent(Duration::from_millis(300)));
+        assert_eq!(failure.missing_engines(), vec!["engine1", "engine2"]);
+        assert_eq!(failure.silent_engines(), vec!["engine2"]);
+        let report = failure.to_string();
+        assert!(report.contains("went silent:\n    engine2: never heard from"));
+        assert!(report.contains("waiting on the others:\n    engine1: last heard from"));
+        heartbeats.abort();
+    }
+
+    async fn connect_over_tcp() -> (TcpOrchestratorTransport, TcpEngineTransport, TcpEngineTransport) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let engine_ids = travel_plan().get_regions();
+        let (orchestrator, engine1, engine2) = tokio::join!(
+            TcpOrchestratorTransport::accept(listener, &engine_ids, Vec::new()),
+            TcpEngineTransport::connect("engine1", &address, "127.0.0.1:0"),
+            TcpEngineTransport::connect("engine2", &address, "127.0.0.1:0"),
+        );
+        (orchestrator.unwrap(), engine1.unwrap().0, engine2.unwrap().0)
+    }
+
+    #[tokio::test]
+    async fn should_wait_for_the_others_when_an_engine_goes_after_its_last_ack() {
+        let (mut orchestrator, mut engine1, mut engine2) = connect_over_tcp().await;
+        let timeouts = Timeouts { tick: Some(Duration::from_secs(5)), heartbeat: Duration::from_secs(5) };
+        engine1.send_ack(ack("engine1", 1));
+        engine1.shutdown().await;
+        let slower = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            engine2.send_ack(ack("engine2", 1));
+            engine2
+        });
+
+        let acks =
+            start_ticking(&travel_plan(), &TravelRestrictions::new(&[]), 1..2, &mut orchestrator, timeouts, &mut outputs())
+                .await
+                .unwrap();
+
+        assert!(acks.all_received());
+        slower.await.unwrap().shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn should_report_engines_that_go_before_acknowledging() {
+        let (mut orchestrator, engine1, _engine2) = connect_over_tcp().await;
+        let timeouts = Timeouts { tick: Some(Duration::from_secs(5)), heartbeat: Duration::from_secs(5) };
+        engine1.shutdown().await;
+
+        let failure =
+            start_ticking(&travel_plan(), &TravelRestrictions::new(&[]), 1..2, &mut orchestrator, timeouts, &mut outputs())
+                .await
+                .unwrap_err();
+
+        assert_eq!(failure.reason, FailureReason::Disconnected);
+        assert_eq!(failure.missing_engines(), vec!["engine1", "engine2"]);
+    }
+
+    #[tokio::test]
+    async fn should_time_out_a_tick_even_with_heartbeats() {
+        let (mut orchestrator, engines) = common::transport::connect(&travel_plan().get_regions());
+        let timeouts = Timeouts { tick: Some(Duration::from_millis(100)), heartbeat: Duration::from_secs(60) };
+
+        let failure =
+            start_ticking(&travel_plan(), &TravelRestrictions::new(&[]), 1..24, &mut orchestrator, timeouts, &mut outputs())
+                .await
+                .unwrap_err();
+
+        assert_eq!(failure.reason, FailureReason::TimedOut(Duration::from_millis(100)));
+        assert_eq!(failure.missing_engines(), vec!["engine1", "engine2"]);
+        assert!(failure.to_string().contains("engine1: never heard from"));
+        drop(engines);
+    }
+
+    #[tokio::test]
+    async fn should_tick_through_when_all_engines_ack() {
+        let (mut orchestrator, mut engines) = common::transport::connect(&travel_plan().get_regions());
+        let timeouts = Timeouts { tick: Some(Duration::from_secs(5)), heartbeat: Duration::from_secs(5) };
+        for hour in [1, 7, 17] {
+            for (engine, engine_id) in engines.iter_mut().zip(["engine1", "engine2"]) {
+                engine.send_ack(ack(engine_id, hour));
+            }
+        }
+
+        let mut outputs = outputs();
+        let acks = start_ticking(&travel_plan(), &TravelRestrictions::new(&[]), 1..18, &mut orchestrator, timeouts, &mut outputs)
+            .await
+            .unwrap();
+
+        assert!(acks.all_received());
+        assert_eq!(acks.current_hour, 17);
+        assert_eq!(acks.received().map(|ack| ack.engine_id.as_str()).collect::<Vec<_>>(), vec!["engine1", "engine2"]);
+    }
+
+    #[tokio::test]
+    async fn should_only_tick_the_end_of_days_without_travel() {
+        let travel_plan: TravelPlanConfig = serde_json::from_str(
+            r#"{
+                "regions": ["engine1", "engine2"],
+                "migration": {"enabled": false, "matrix": null, "start_migration_hour": 0, "end_migration_hour": 0},
+                "commute": {"enabled": true, "matrix": [[0, 10], [10, 0]], "phases": [{"from_day": 1, "scale": 0}]}
+            }"#,
+        )
+        .unwrap();
+        let (mut orchestrator, mut engines) = common::transport::connect(&travel_plan.get_regions());
+        let timeouts = Timeouts { tick: Some(Duration::from_secs(5)), heartbeat: Duration::from_secs(5) };
+        for hour in [1, 7, 17, 24, 48] {
+            for (engine, engine_id) in engines.iter_mut().zip(["engine1", "engine2"]) {
+                engine.send_ack(ack(engine_id, hour));
+            }
+        }
+
+        start_ticking(&travel_plan, &TravelRestrictions::new(&[]), 1..60, &mut orchestrator, timeouts, &mut outputs())
+            .await
+            .unwrap();
+        drop(orchestrator);
+
+        let mut hours = Vec::new();
+        while let Some(tick) = engines[0].receive_tick().await {
+            hours.push(read_tick(&tick)["hour"].as_i64().unwrap());
+        }
+        assert_eq!(hours, vec![1, 7, 17, 24, 48]);
+    }
+
     // #[test]
     // #[should_panic(expected = "Received ack for another hour. Current hour: 0, received: 22")]
     // fn should_panic_if_recv_ack_for_another_hour() {
@@ -0,0 +1,171 @@
+/*
+ * EpiRust
+ * Copyright (c) 2020  ThoughtWorks, Inc.
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU Affero General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU Affero General Public License for more details.
+ *
+ * You should have received a copy of the GNU Affero General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ */
+
+//! Travel between regions that the engines' travel restriction and entry screening interventions hold back.
+//!
+//! Only the orchestrator knows the infections in every region, so it works out the restrictions for each pair of
+//! regions and sends them along with the ticks. The sending engine then holds back its travellers, which keeps the
+//! number of messages every engine waits for the same.
+
+use std::collections::HashMap;
+
+use common::config::intervention_config::{EntryScreeningConfig, InterventionConfig, ScreeningAction, TravelRestrictionConfig};
+use common::config::Config;
+
+use crate::ticks::{TickAcks, TravelRestriction};
+
+struct RegionPolicy {
+    engine_id: String,
+    restriction: Option<TravelRestrictionConfig>,
+    screening: Option<EntryScreeningConfig>,
+}
+
+pub struct TravelRestrictions {
+    policies: Vec<RegionPolicy>,
+}
+
+impl TravelRestrictions {
+    pub fn new(engine_configs: &[(String, Config)]) -> TravelRestrictions {
+        let policies = engine_configs
+            .iter()
+            .map(|(engine_id, config)| {
+                let interventions = config.get_interventions();
+                RegionPolicy {
+                    engine_id: engine_id.clone(),
+                    restriction: interventions.iter().find_map(|i| match i {
+                        InterventionConfig::TravelRestriction(x) => Some(*x),
+                        _ => None,
+                    }),
+                    screening: interventions.iter().find_map(|i| match i {
+                        InterventionConfig::EntryScreening(x) => Some(*x),
+                        _ => None,
+                    }),
+                }
+            })
+            .collect();
+        TravelRestrictions { policies }
+    }
+
+    /// Restrictions on the travel between regions, given the infections the engines last acknowledged
+    pub fn between(&self, acks: &TickAcks) -> Vec<TravelRestriction> {
+        let infected: HashMap<&String, i32> = acks.received().map(|ack| (&ack.engine_id, ack.counts.infected)).collect();
+        let infected_in = |engine_id: &String| infected.get(engine_id).map_or(0, |infected| (*infected).max(0) as u32);
+        let mut restrictions = Vec::new();
+        for from in &self.policies {
+            for to in self.policies.iter().filter(|to| to.engine_id != from.engine_id) {
+                let (from_infected, to_infected) = (infected_in(&from.engine_id), infected_in(&to.engine_id));
+                let mut travel_allowed = 1.0;
+                if let Some(restriction) = from.restriction.filter(|r| r.applies(from_infected, to_infected)) {
+                    travel_allowed *= restriction.travel_allowed;
+                }
+                if let Some(restriction) = to.restriction.filter(|r| r.applies(to_infected, from_infected)) {
+                    travel_allowed *= restriction.travel_allowed;
+                }
+                let turned_back = match to.screening {
+                    Some(EntryScreeningConfig { detection_rate, action: ScreeningAction::Reject }) => detection_rate,
+                    _ => 0.0,
+                };
+                if travel_allowed < 1.0 || turned_back > 0.0 {
+                    restrictions.push(TravelRestriction::new(&from.engine_id, &to.engine_id, travel_allowed, turned_back));
+                }
+            }
+        }
+        restrictions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ticks::{Counts, TickAck};
+
+    fn policy(
+        engine_id: &str,
+        restriction: Option<TravelRestrictionConfig>,
+        screening: Option<EntryScreeningConfig>,
+    ) -> RegionPolicy {
+        RegionPolicy { engine_id: engine_id.to_string(), restriction, screening }
+    }
+
+    fn acks(infected: &[(&str, i32)]) -> TickAcks {
+        let engine_ids: Vec<String> = infected.iter().map(|(engine_id, _)| engine_id.to_string()).collect();
+        let mut acks = TickAcks::new(&engine_ids);
+        acks.reset(24);
+        for (engine_id, infected) in infected {
+            acks.push(TickAck {
+                engine_id: engine_id.to_string(),
+                hour: 24,
+                counts: Counts::new(24, 100 - infected, 0, *infected, 0, 0, 0),
+                outgoing: HashMap::new(),
+                quarantined: 0,
+                hub_infections: 0,
+            });
+        }
+        acks
+    }
+
+    #[test]
+    fn should_restrict_travel_in_and_out_of_a_region_above_its_threshold() {
+        let closed = TravelRestrictionConfig {
+            at_number_of_infections: Some(10),
+            at_number_of_remote_infections: None,
+            travel_allowed: 0.0,
+        };
+        let restrictions =
+            TravelRestrictions { policies: vec![policy("engine1", Some(closed), None), policy("engine2", None, None)] };
+
+        assert!(restrictions.between(&acks(&[("engine1", 10), ("engine2", 50)])).is_empty());
+        assert_eq!(
+            restrictions.between(&acks(&[("engine1", 11), ("engine2", 0)])),
+            vec![TravelRestriction::new("engine1", "engine2", 0.0, 0.0), TravelRestriction::new("engine2", "engine1", 0.0, 0.0)]
+        );
+    }
+
+    #[test]
+    fn should_restrict_travel_with_regions_above_the_remote_threshold() {
+        let throttled = TravelRestrictionConfig {
+            at_number_of_infections: None,
+            at_number_of_remote_infections: Some(20),
+            travel_allowed: 0.5,
+        };
+        let restrictions = TravelRestrictions {
+            policies: vec![
+                policy("engine1", Some(throttled), None),
+                policy("engine2", None, None),
+                policy("engine3", None, None),
+            ],
+        };
+
+        assert_eq!(
+            restrictions.between(&acks(&[("engine1", 0), ("engine2", 30), ("engine3", 5)])),
+            vec![TravelRestriction::new("engine1", "engine2", 0.5, 0.0), TravelRestriction::new("engine2", "engine1", 0.5, 0.0)]
+        );
+    }
+
+    #[test]
+    fn should_turn_back_travellers_detected_by_screening_that_rejects_them() {
+        let rejecting = EntryScreeningConfig { detection_rate: 0.8, action: ScreeningAction::Reject };
+        let quarantining = EntryScreeningConfig { detection_rate: 0.8, action: ScreeningAction::Quarantine };
+        let restrictions = TravelRestrictions {
+            policies: vec![policy("engine1", None, Some(rejecting)), policy("engine2", None, Some(quarantining))],
+        };
+
+        assert_eq!(restrictions.between(&acks(&[])), vec![TravelRestriction::new("engine2", "engine1", 1.0, 0.8)]);
+    }
+}